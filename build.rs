@@ -0,0 +1,38 @@
+//! Makes sure the bundled `solutions.json` is a map from problem ids to
+//! sources, so a bad entry fails the build instead of panicking at runtime.
+
+use std::{env, fs};
+
+const SOLUTIONS_PATH: &str = "src/pbinfo_user/solutions.json";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={SOLUTIONS_PATH}");
+
+    if env::var_os("CARGO_FEATURE_BUNDLED_SOLUTIONS").is_none() {
+        return;
+    }
+
+    let json = fs::read_to_string(SOLUTIONS_PATH)
+        .unwrap_or_else(|err| panic!("Couldn't read {SOLUTIONS_PATH}!\nGot error: {err}"));
+
+    let solutions: serde_json::Value = serde_json::from_str(&json)
+        .unwrap_or_else(|err| panic!("{SOLUTIONS_PATH} isn't valid json!\nGot error: {err}"));
+
+    let solutions = solutions.as_object().unwrap_or_else(|| {
+        panic!("{SOLUTIONS_PATH} must be an object mapping problem ids to sources!")
+    });
+
+    for (problem_id, source) in solutions {
+        if problem_id.is_empty() {
+            panic!("{SOLUTIONS_PATH} has an entry with an empty problem id!");
+        }
+        match source.as_str() {
+            Some(source) if !source.trim().is_empty() => (),
+            Some(_) => panic!("{SOLUTIONS_PATH}: the source of problem {problem_id} is empty!"),
+            None => panic!(
+                "{SOLUTIONS_PATH}: the source of problem {problem_id} must be a string, got {source}"
+            ),
+        }
+    }
+}
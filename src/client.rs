@@ -0,0 +1,474 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, ORIGIN, REFERER},
+    StatusCode, Url,
+};
+
+use crate::{endpoints::BASE_URL, transport::HttpTransport};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ClientBuildError {
+    #[error("Error: Couldn't build a reqwest client\nGot error:\n{err}")]
+    ReqwestError { err: String },
+    #[error("Error: The base url {url} isn't a valid url!\nGot error:\n{err}")]
+    InvalidBaseUrlError { url: String, err: String },
+    #[error("Error: The proxy {url} isn't a valid proxy url!\nGot error:\n{err}")]
+    InvalidProxyError { url: String, err: String },
+    #[error("Error: The rate limit has to be a finite number of requests per second above 0, got {requests_per_second}!")]
+    InvalidRateLimitError { requests_per_second: f64 },
+}
+
+/// Called after every request with the name of the endpoint (see
+/// [crate::endpoints::Endpoint::name]), how long the request took and the
+/// status code of the response (`None` if no response was received)
+pub type RequestCompleteHook = dyn Fn(&'static str, Duration, Option<StatusCode>) + Send + Sync;
+
+/// The reqwest client behind every [PbinfoClient::default], so all the users
+/// that don't bring their own client share one connection pool
+static SHARED_HTTP: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+/// The http side of a [crate::pbinfo_user::PbinfoUser], every request
+/// made on behalf of the user goes through it.
+///
+/// Cloning is cheap and the clones share the same connection pool and hooks.
+/// The default clients (like the one of [crate::pbinfo_user::PbinfoUser::new])
+/// also share their connection pool with each other
+#[derive(Clone)]
+pub struct PbinfoClient {
+    pub(crate) http: reqwest::Client,
+    pub(crate) on_request_complete: Option<Arc<RequestCompleteHook>>,
+    /// Sends the requests built with [PbinfoClient::http]
+    pub(crate) transport: Arc<dyn HttpTransport>,
+    /// Where the requests are sent instead of [BASE_URL]
+    base_url: Option<Arc<str>>,
+    #[cfg(feature = "tokio")]
+    pub(crate) rate_limiter: Option<Arc<crate::rate_limit::RateLimiter>>,
+}
+
+impl std::fmt::Debug for PbinfoClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PbinfoClient")
+            .field("http", &self.http)
+            .field("base_url", &self.base_url())
+            .field("on_request_complete", &self.on_request_complete.is_some())
+            .finish()
+    }
+}
+
+impl Default for PbinfoClient {
+    fn default() -> Self {
+        PbinfoClient {
+            http: SHARED_HTTP.clone(),
+            on_request_complete: None,
+            transport: Arc::new(SHARED_HTTP.clone()),
+            base_url: None,
+            #[cfg(feature = "tokio")]
+            rate_limiter: None,
+        }
+    }
+}
+
+impl PbinfoClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn builder() -> PbinfoClientBuilder {
+        PbinfoClientBuilder::default()
+    }
+
+    /// Returns the underlying reqwest client, useful for sending the
+    /// requests built with [crate::endpoints]
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Registers a hook that is called after every request, so the timings
+    /// can be fed into an external monitoring system. Replaces the previous hook
+    pub fn on_request_complete(
+        &mut self,
+        hook: impl Fn(&'static str, Duration, Option<StatusCode>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.on_request_complete = Some(Arc::new(hook));
+        self
+    }
+
+    /// Where the requests are sent, `https://www.pbinfo.ro` unless the
+    /// builder said otherwise
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_deref().unwrap_or(BASE_URL)
+    }
+
+    /// Points a request built for [BASE_URL] at the base url of the client,
+    /// together with its `Origin` and `Referer` headers
+    pub(crate) fn rebase(&self, request: &mut reqwest::Request) {
+        let Some(base_url) = &self.base_url else {
+            return;
+        };
+        let rebased = |url: &str| {
+            url.strip_prefix(BASE_URL)
+                .map(|rest| format!("{base_url}{rest}"))
+        };
+
+        // the base url was validated by the builder
+        if let Some(Ok(url)) = rebased(request.url().as_str()).map(|url| Url::parse(&url)) {
+            *request.url_mut() = url;
+        }
+        let headers = request.headers_mut();
+        if headers.contains_key(ORIGIN) {
+            if let Ok(origin) = Url::parse(base_url).map(|url| url.origin().ascii_serialization()) {
+                if let Ok(origin) = HeaderValue::from_str(&origin) {
+                    headers.insert(ORIGIN, origin);
+                }
+            }
+        }
+        let referer = headers
+            .get(REFERER)
+            .and_then(|referer| referer.to_str().ok())
+            .and_then(rebased);
+        if let Some(Ok(referer)) = referer.map(|referer| HeaderValue::from_str(&referer)) {
+            headers.insert(REFERER, referer);
+        }
+    }
+}
+
+/// Builds a [PbinfoClient] with tuned connections, for when a lot of
+/// requests are sent (like solving problems in batches).
+///
+/// Everything that isn't set keeps the default of reqwest. In the browser
+/// the connection settings (pool, keepalive, resolve, timeouts and proxy)
+/// are up to the browser and ignored
+#[derive(Default)]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub struct PbinfoClientBuilder {
+    pool_idle_timeout: Option<Option<Duration>>,
+    pool_max_idle_per_host: Option<usize>,
+    tcp_keepalive: Option<Option<Duration>>,
+    http2_prior_knowledge: bool,
+    resolve: Vec<(String, Vec<SocketAddr>)>,
+    on_request_complete: Option<Arc<RequestCompleteHook>>,
+    base_url: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    headers: HeaderMap,
+    transport: Option<Arc<dyn HttpTransport>>,
+    proxy: Option<String>,
+    proxy_credentials: Option<(String, String)>,
+    ignore_env_proxy: bool,
+    #[cfg(feature = "tokio")]
+    rate_limit: Option<(f64, u32)>,
+}
+
+impl PbinfoClientBuilder {
+    /// How long an idle connection is kept open, `None` keeps it forever
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// How many idle connections are kept open to pbinfo
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// How often TCP keepalive probes are sent, `None` disables them
+    pub fn tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Talk HTTP/2 right away instead of negotiating it
+    pub fn http2_prior_knowledge(mut self, enabled: bool) -> Self {
+        self.http2_prior_knowledge = enabled;
+        self
+    }
+
+    /// Sends the requests for `host` to `addr` instead of resolving it,
+    /// for example to point `www.pbinfo.ro` at a local replica of the site.
+    /// The port of `addr` is ignored, the one from the url is used
+    pub fn resolve(self, host: impl Into<String>, addr: SocketAddr) -> Self {
+        self.resolve_to_addrs(host, &[addr])
+    }
+
+    /// Same as [PbinfoClientBuilder::resolve] but with multiple addresses
+    pub fn resolve_to_addrs(mut self, host: impl Into<String>, addrs: &[SocketAddr]) -> Self {
+        self.resolve.push((host.into(), addrs.to_vec()));
+        self
+    }
+
+    /// Sends the requests to `base_url` instead of `https://www.pbinfo.ro`,
+    /// like a local mock server (`http://localhost:8080`) or a new domain
+    /// of pbinfo
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// How long a whole request can take, including reading the body
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// How long connecting to pbinfo can take
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// A header that is sent with every request, like a `User-Agent`. The
+    /// headers of the endpoints (cookies, referer, etc...) win over it
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Same as [PbinfoClientBuilder::header] for many headers at once
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Sends every request through the proxy at `url`, like
+    /// `http://proxy.school.ro:3128`
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// The username and password of the proxy set with [PbinfoClientBuilder::proxy],
+    /// building fails if no proxy was set
+    pub fn proxy_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.proxy_credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Whether the proxy in the `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY`
+    /// environment variables is used when no proxy was set. Enabled by default
+    pub fn proxy_from_env(mut self, enabled: bool) -> Self {
+        self.ignore_env_proxy = !enabled;
+        self
+    }
+
+    /// Keeps the requests under `requests_per_second`, letting `burst`
+    /// requests through at once. Shared by every endpoint and every clone of
+    /// the client, so bulk operations can't hammer pbinfo
+    #[cfg(feature = "tokio")]
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        self.rate_limit = Some((requests_per_second, burst));
+        self
+    }
+
+    /// Sends the requests with `transport` instead of reqwest, for answering
+    /// them with canned responses in tests. The connection settings of the
+    /// builder only apply to reqwest
+    pub fn transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Same as [PbinfoClient::on_request_complete]
+    pub fn on_request_complete(
+        mut self,
+        hook: impl Fn(&'static str, Duration, Option<StatusCode>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_request_complete = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn build(self) -> Result<PbinfoClient, ClientBuildError> {
+        let mut http = reqwest::Client::builder();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            http = self.connection_settings(http)?;
+        }
+        http = http.default_headers(self.headers);
+
+        let base_url = match self.base_url {
+            Some(url) => {
+                let url = url.trim_end_matches('/').to_string();
+                Url::parse(&url).map_err(|err| ClientBuildError::InvalidBaseUrlError {
+                    url: url.clone(),
+                    err: err.to_string(),
+                })?;
+                Some(Arc::from(url))
+            }
+            None => None,
+        };
+
+        #[cfg(feature = "tokio")]
+        let rate_limiter = match self.rate_limit {
+            Some((requests_per_second, _))
+                if !requests_per_second.is_finite() || requests_per_second <= 0.0 =>
+            {
+                return Err(ClientBuildError::InvalidRateLimitError {
+                    requests_per_second,
+                })
+            }
+            Some((requests_per_second, burst)) => Some(Arc::new(
+                crate::rate_limit::RateLimiter::new(requests_per_second, burst),
+            )),
+            None => None,
+        };
+
+        let http = http.build().map_err(|err| ClientBuildError::ReqwestError {
+            err: err.to_string(),
+        })?;
+        Ok(PbinfoClient {
+            transport: self.transport.unwrap_or_else(|| Arc::new(http.clone())),
+            http,
+            on_request_complete: self.on_request_complete,
+            base_url,
+            #[cfg(feature = "tokio")]
+            rate_limiter,
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PbinfoClientBuilder {
+    /// Applies everything about connections, which only exists outside the
+    /// browser
+    fn connection_settings(
+        &self,
+        mut http: reqwest::ClientBuilder,
+    ) -> Result<reqwest::ClientBuilder, ClientBuildError> {
+        if let Some(timeout) = self.pool_idle_timeout {
+            http = http.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            http = http.pool_max_idle_per_host(max);
+        }
+        if let Some(interval) = self.tcp_keepalive {
+            http = http.tcp_keepalive(interval);
+        }
+        if self.http2_prior_knowledge {
+            http = http.http2_prior_knowledge();
+        }
+        for (host, addrs) in &self.resolve {
+            http = http.resolve_to_addrs(host, addrs);
+        }
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            http = http.connect_timeout(timeout);
+        }
+        if self.proxy.is_none() && self.proxy_credentials.is_some() {
+            return Err(ClientBuildError::InvalidProxyError {
+                url: String::new(),
+                err: "Proxy credentials were set without a proxy".to_string(),
+            });
+        }
+        if let Some(url) = &self.proxy {
+            let mut proxy =
+                reqwest::Proxy::all(url).map_err(|err| ClientBuildError::InvalidProxyError {
+                    url: url.clone(),
+                    err: err.to_string(),
+                })?;
+            if let Some((username, password)) = &self.proxy_credentials {
+                proxy = proxy.basic_auth(username, password);
+            }
+            http = http.proxy(proxy);
+        } else if self.ignore_env_proxy {
+            http = http.no_proxy();
+        }
+        Ok(http)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn proxy_credentials_need_a_proxy() {
+        let build = |builder: PbinfoClientBuilder| {
+            builder
+                .proxy_credentials("user", "password")
+                .build()
+                .map(|_| ())
+        };
+        assert!(matches!(
+            build(PbinfoClient::builder()),
+            Err(ClientBuildError::InvalidProxyError { .. })
+        ));
+        assert!(build(PbinfoClient::builder().proxy("http://localhost:3128")).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "tokio")]
+    fn rate_limit_rejects_rates_that_are_not_positive_numbers() {
+        for requests_per_second in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                PbinfoClient::builder()
+                    .rate_limit(requests_per_second, 1)
+                    .build(),
+                Err(ClientBuildError::InvalidRateLimitError { .. })
+            ));
+        }
+        assert!(PbinfoClient::builder()
+            .rate_limit(1e-320, 1)
+            .build()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "tokio")]
+    async fn errors_have_the_url_the_request_was_sent_to() {
+        let (transport, sent) =
+            crate::test_support::transport(|_| crate::test_support::status(500));
+        let client = PbinfoClient::builder()
+            .base_url("http://localhost:8080")
+            .transport(transport)
+            .build()
+            .unwrap();
+        let pbinfo_user = crate::pbinfo_user::PbinfoUser::builder()
+            .client(client)
+            .build();
+
+        let err = pbinfo_user.get_problem("1").await.unwrap_err();
+        assert_eq!(
+            err.context().url.as_deref(),
+            Some("http://localhost:8080/probleme/1")
+        );
+        assert_eq!(sent.requests()[0].url, "http://localhost:8080/probleme/1");
+    }
+
+    #[test]
+    fn rebase_moves_the_origin_and_the_referer_too() {
+        let client = PbinfoClient::builder()
+            .base_url("http://localhost:8080/pbinfo")
+            .build()
+            .unwrap();
+        let mut request = client
+            .http
+            .get(format!("{BASE_URL}/probleme/1"))
+            .header(ORIGIN, BASE_URL)
+            .header(REFERER, format!("{BASE_URL}/probleme"))
+            .build()
+            .unwrap();
+
+        client.rebase(&mut request);
+        assert_eq!(
+            request.url().as_str(),
+            "http://localhost:8080/pbinfo/probleme/1"
+        );
+        assert_eq!(request.headers()[ORIGIN], "http://localhost:8080");
+        assert_eq!(
+            request.headers()[REFERER],
+            "http://localhost:8080/pbinfo/probleme"
+        );
+    }
+}
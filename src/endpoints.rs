@@ -0,0 +1,1233 @@
+//! Typed builders for the raw pbinfo endpoints.
+//!
+//! Every builder only knows how to turn itself into a [reqwest::RequestBuilder],
+//! nothing is sent and nothing is parsed. This is what the rest of the crate
+//! uses under the hood, so it's also the escape hatch for calling something
+//! the high level api of [crate::pbinfo_user::PbinfoUser] doesn't wrap yet.
+
+use std::collections::HashMap;
+
+use reqwest::{header::HeaderMap, Client, Method, RequestBuilder};
+
+/// The url every endpoint is relative to
+pub const BASE_URL: &str = "https://www.pbinfo.ro";
+
+/// A raw pbinfo endpoint that can be turned into a request
+pub trait Endpoint {
+    /// A short and stable name of the endpoint, used in errors and logs
+    fn name(&self) -> &'static str;
+
+    /// The http method of the endpoint
+    fn method(&self) -> Method;
+
+    /// The full url of the endpoint, including the query string
+    fn url(&self) -> String;
+
+    /// The headers that need to be sent with the request (cookies, referer, etc...)
+    fn headers(&self) -> HeaderMap;
+
+    /// Builds the request with `client` without sending it
+    fn request(&self, client: &Client) -> RequestBuilder {
+        client
+            .request(self.method(), self.url())
+            .headers(self.headers())
+    }
+}
+
+/// The url of the page at `segments` under [BASE_URL], every segment percent
+/// encoded so a username can't change the path
+fn page_url(segments: &[&str]) -> String {
+    let mut url = reqwest::Url::parse(BASE_URL).expect("the base url is valid");
+    url.path_segments_mut()
+        .expect("the base url has a path")
+        .pop_if_empty()
+        .extend(segments);
+    url.to_string()
+}
+
+/// Only the session cookie, all the json endpoints of the scores need
+fn cookie_headers(ssid: Option<&str>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(Ok(cookie)) = ssid.map(|ssid| format!("SSID={ssid}").parse()) {
+        headers.insert("Cookie", cookie);
+    }
+    headers
+}
+
+/// Builds the headers pbinfo expects from a browser: the session cookie
+/// and the page the request was "made" from
+fn session_headers(ssid: Option<&str>, referer: &str) -> HeaderMap {
+    let mut headers = cookie_headers(ssid);
+    if let Ok(referer) = referer.parse() {
+        headers.insert("Referer", referer);
+    }
+    headers
+}
+
+/// Same as [session_headers] with the `Origin` a browser adds to the forms
+/// it posts
+fn form_headers(ssid: Option<&str>, referer: &str) -> HeaderMap {
+    let mut headers = session_headers(ssid, referer);
+    headers.insert(
+        "Origin",
+        reqwest::header::HeaderValue::from_static(BASE_URL),
+    );
+    headers
+}
+
+/// `GET /`, the homepage. When logged in the page contains the id of the user
+#[derive(Debug, Clone, Default)]
+pub struct Homepage {
+    ssid: Option<String>,
+}
+
+impl Homepage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for Homepage {
+    fn name(&self) -> &'static str {
+        "homepage"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        BASE_URL.to_string()
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(self.ssid.as_deref(), &format!("{BASE_URL}/"))
+    }
+}
+
+/// `POST /ajx-module/php-login.php`, logs in a session.
+///
+/// Answers with a json like `{"raspuns": "...", "form_token": "..."}` and
+/// a `set-cookie` header with the new SSID on success
+#[derive(Debug, Clone)]
+pub struct Login {
+    user: String,
+    password: String,
+    form_token: String,
+    ssid: Option<String>,
+}
+
+impl Login {
+    /// `user` can be either the username or the email of the account
+    pub fn new(user: impl Into<String>, password: impl Into<String>) -> Self {
+        Login {
+            user: user.into(),
+            password: password.into(),
+            form_token: String::new(),
+            ssid: None,
+        }
+    }
+
+    /// The form token of the login form
+    pub fn form_token(mut self, form_token: impl Into<String>) -> Self {
+        self.form_token = form_token.into();
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for Login {
+    fn name(&self) -> &'static str {
+        "login"
+    }
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        format!("{BASE_URL}/ajx-module/php-login.php")
+    }
+
+    fn headers(&self) -> HeaderMap {
+        form_headers(self.ssid.as_deref(), &format!("{BASE_URL}/"))
+    }
+
+    fn request(&self, client: &Client) -> RequestBuilder {
+        let mut form_data = HashMap::new();
+        form_data.insert("user", self.user.as_str());
+        form_data.insert("parola", self.password.as_str());
+        form_data.insert("form_token", self.form_token.as_str());
+
+        client
+            .request(self.method(), self.url())
+            .headers(self.headers())
+            .form(&form_data)
+    }
+}
+
+/// `GET /ajx-module/php-logout.php`, ends the session on pbinfo so the
+/// SSID can't be used anymore
+#[derive(Debug, Clone, Default)]
+pub struct Logout {
+    ssid: Option<String>,
+}
+
+impl Logout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for Logout {
+    fn name(&self) -> &'static str {
+        "logout"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        format!("{BASE_URL}/ajx-module/php-logout.php")
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(self.ssid.as_deref(), &format!("{BASE_URL}/"))
+    }
+}
+
+/// `POST /ajx-module/php-inregistrare.php`, creates an account.
+///
+/// Answers with a json like the one of [Login], the `raspuns` says whether
+/// the account was created or what was wrong with the form
+#[derive(Debug, Clone)]
+pub struct Register {
+    user: String,
+    email: String,
+    password: String,
+    county: String,
+    school: String,
+    form_token: String,
+    ssid: Option<String>,
+}
+
+impl Register {
+    pub fn new(
+        user: impl Into<String>,
+        email: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        Register {
+            user: user.into(),
+            email: email.into(),
+            password: password.into(),
+            county: String::new(),
+            school: String::new(),
+            form_token: String::new(),
+            ssid: None,
+        }
+    }
+
+    /// The county of the school
+    pub fn county(mut self, county: impl Into<String>) -> Self {
+        self.county = county.into();
+        self
+    }
+
+    pub fn school(mut self, school: impl Into<String>) -> Self {
+        self.school = school.into();
+        self
+    }
+
+    /// The form token of the sign up form
+    pub fn form_token(mut self, form_token: impl Into<String>) -> Self {
+        self.form_token = form_token.into();
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for Register {
+    fn name(&self) -> &'static str {
+        "register"
+    }
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        format!("{BASE_URL}/ajx-module/php-inregistrare.php")
+    }
+
+    fn headers(&self) -> HeaderMap {
+        form_headers(self.ssid.as_deref(), &format!("{BASE_URL}/inregistrare"))
+    }
+
+    fn request(&self, client: &Client) -> RequestBuilder {
+        let mut form_data = HashMap::new();
+        form_data.insert("user", self.user.as_str());
+        form_data.insert("email", self.email.as_str());
+        form_data.insert("parola", self.password.as_str());
+        form_data.insert("parola2", self.password.as_str());
+        form_data.insert("judet", self.county.as_str());
+        form_data.insert("scoala", self.school.as_str());
+        form_data.insert("form_token", self.form_token.as_str());
+
+        client
+            .request(self.method(), self.url())
+            .headers(self.headers())
+            .form(&form_data)
+    }
+}
+
+/// `GET /probleme/{problem_id}`, the html page of a problem
+#[derive(Debug, Clone)]
+pub struct ProblemPage {
+    problem_id: String,
+    ssid: Option<String>,
+}
+
+impl ProblemPage {
+    pub fn new(problem_id: impl Into<String>) -> Self {
+        ProblemPage {
+            problem_id: problem_id.into(),
+            ssid: None,
+        }
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for ProblemPage {
+    fn name(&self) -> &'static str {
+        "problem-page"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        format!("{BASE_URL}/probleme/{}", self.problem_id)
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(self.ssid.as_deref(), &format!("{BASE_URL}/"))
+    }
+}
+
+/// `POST /ajx-module/php-solutie-incarcare.php`, uploads a source.
+///
+/// The source has to be sent under a field name that is encoded in the
+/// html of the problem page (see [ProblemPage]). Answers with a json like
+/// `{"stare": "success", "id_solutie": ...}`
+#[derive(Debug, Clone)]
+pub struct UploadSolution {
+    problem_id: String,
+    source: String,
+    source_field: String,
+    language: String,
+    assignment: Option<String>,
+    ssid: Option<String>,
+}
+
+impl UploadSolution {
+    /// `source_field` is the encoded name of the form field the source is sent in
+    pub fn new(
+        problem_id: impl Into<String>,
+        source: impl Into<String>,
+        source_field: impl Into<String>,
+    ) -> Self {
+        UploadSolution {
+            problem_id: problem_id.into(),
+            source: source.into(),
+            source_field: source_field.into(),
+            language: "cpp".to_string(),
+            assignment: None,
+            ssid: None,
+        }
+    }
+
+    /// The value of the `limbaj_de_programare` field, `cpp` by default
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = language.into();
+        self
+    }
+
+    /// Sends the solution for an assignment ("tema"), in the `id_tema` field
+    pub fn assignment(mut self, assignment_id: impl Into<String>) -> Self {
+        self.assignment = Some(assignment_id.into());
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for UploadSolution {
+    fn name(&self) -> &'static str {
+        "upload-solution"
+    }
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        format!("{BASE_URL}/ajx-module/php-solutie-incarcare.php")
+    }
+
+    fn headers(&self) -> HeaderMap {
+        let referer = match &self.assignment {
+            Some(assignment) => format!(
+                "{BASE_URL}/probleme/{}?id_tema={assignment}",
+                self.problem_id
+            ),
+            None => format!("{BASE_URL}/probleme/{}", self.problem_id),
+        };
+        form_headers(self.ssid.as_deref(), &referer)
+    }
+
+    fn request(&self, client: &Client) -> RequestBuilder {
+        let mut form = reqwest::multipart::Form::new()
+            .text("limbaj_de_programare", self.language.clone())
+            .text("sursa", "")
+            .text(self.source_field.clone(), self.source.clone())
+            .text("id", self.problem_id.clone());
+        if let Some(assignment) = &self.assignment {
+            form = form.text("id_tema", assignment.clone());
+        }
+
+        client
+            .request(self.method(), self.url())
+            .headers(self.headers())
+            .multipart(form)
+    }
+}
+
+/// `POST /ajx-module/ajx-solutii-lista-json.php`, the last solutions a user
+/// sent to a problem.
+///
+/// Answers with a json like `{"numar_total_solutii": ..., "surse": [...]}`
+#[derive(Debug, Clone)]
+pub struct SolutionList {
+    problem_id: String,
+    user_id: String,
+    count: u32,
+    ssid: Option<String>,
+}
+
+impl SolutionList {
+    pub fn new(problem_id: impl Into<String>, user_id: impl Into<String>) -> Self {
+        SolutionList {
+            problem_id: problem_id.into(),
+            user_id: user_id.into(),
+            count: 1,
+            ssid: None,
+        }
+    }
+
+    /// How many of the last solutions to return, 1 by default
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for SolutionList {
+    fn name(&self) -> &'static str {
+        "solution-list"
+    }
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{BASE_URL}/ajx-module/ajx-solutii-lista-json.php?id_problema={}&id_user={}&numar_solutii={}",
+            self.problem_id, self.user_id, self.count
+        )
+    }
+
+    fn headers(&self) -> HeaderMap {
+        cookie_headers(self.ssid.as_deref())
+    }
+}
+
+/// `POST /ajx-module/ajx-solutii-lista-json.php` without a problem, the last
+/// solutions of a user to any problem.
+///
+/// Answers with the same json as [SolutionList]
+#[derive(Debug, Clone)]
+pub struct UserSolutionList {
+    user_id: String,
+    count: u32,
+    ssid: Option<String>,
+}
+
+impl UserSolutionList {
+    pub fn new(user_id: impl Into<String>) -> Self {
+        UserSolutionList {
+            user_id: user_id.into(),
+            count: 1,
+            ssid: None,
+        }
+    }
+
+    /// How many of the last solutions to return, 1 by default
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for UserSolutionList {
+    fn name(&self) -> &'static str {
+        "user-solution-list"
+    }
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{BASE_URL}/ajx-module/ajx-solutii-lista-json.php?id_user={}&numar_solutii={}",
+            self.user_id, self.count
+        )
+    }
+
+    fn headers(&self) -> HeaderMap {
+        cookie_headers(self.ssid.as_deref())
+    }
+}
+
+/// `POST /ajx-module/ajx-solutii-lista-json.php?publice=1`, the last
+/// solutions other users made public for a problem.
+///
+/// Answers with the same json as [SolutionList], every solution also has
+/// the `user` that sent it
+#[derive(Debug, Clone)]
+pub struct PublicSolutionList {
+    problem_id: String,
+    count: u32,
+    ssid: Option<String>,
+}
+
+impl PublicSolutionList {
+    pub fn new(problem_id: impl Into<String>) -> Self {
+        PublicSolutionList {
+            problem_id: problem_id.into(),
+            count: 1,
+            ssid: None,
+        }
+    }
+
+    /// How many of the last solutions to return, 1 by default
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for PublicSolutionList {
+    fn name(&self) -> &'static str {
+        "public-solution-list"
+    }
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{BASE_URL}/ajx-module/ajx-solutii-lista-json.php?id_problema={}&publice=1&numar_solutii={}",
+            self.problem_id, self.count
+        )
+    }
+
+    fn headers(&self) -> HeaderMap {
+        cookie_headers(self.ssid.as_deref())
+    }
+}
+
+/// `GET /ajx-module/ajx-solutie-sursa.php`, the source of a solution.
+///
+/// Answers with a json like `{"sursa": "..."}`, only for solutions of the
+/// logged in user unless the solution is [SolutionSource::public]
+#[derive(Debug, Clone)]
+pub struct SolutionSource {
+    solution_id: String,
+    public: bool,
+    ssid: Option<String>,
+}
+
+impl SolutionSource {
+    pub fn new(solution_id: impl Into<String>) -> Self {
+        SolutionSource {
+            solution_id: solution_id.into(),
+            public: false,
+            ssid: None,
+        }
+    }
+
+    /// Asks for a solution another user made public, off by default
+    pub fn public(mut self, public: bool) -> Self {
+        self.public = public;
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for SolutionSource {
+    fn name(&self) -> &'static str {
+        "solution-source"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        let public = if self.public { "&publica=1" } else { "" };
+        format!(
+            "{BASE_URL}/ajx-module/ajx-solutie-sursa.php?id={}{public}",
+            self.solution_id
+        )
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(
+            self.ssid.as_deref(),
+            &format!("{BASE_URL}/detalii-evaluare/{}", self.solution_id),
+        )
+    }
+}
+
+/// `POST /ajx-module/ajx-solutie-detalii-evaluare.php`, the evaluation
+/// details of a solution.
+///
+/// Answers with a json that has the `status_sursa` field set to `pending`
+/// or `executing` while the solution is still being evaluated
+#[derive(Debug, Clone)]
+pub struct EvaluationDetails {
+    solution_id: String,
+    force_reload: bool,
+    ssid: Option<String>,
+}
+
+impl EvaluationDetails {
+    pub fn new(solution_id: impl Into<String>) -> Self {
+        EvaluationDetails {
+            solution_id: solution_id.into(),
+            force_reload: true,
+            ssid: None,
+        }
+    }
+
+    /// Asks pbinfo to not serve a cached result, on by default
+    pub fn force_reload(mut self, force_reload: bool) -> Self {
+        self.force_reload = force_reload;
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for EvaluationDetails {
+    fn name(&self) -> &'static str {
+        "evaluation-details"
+    }
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        let force_reload = if self.force_reload {
+            "force_reload&"
+        } else {
+            ""
+        };
+        format!(
+            "{BASE_URL}/ajx-module/ajx-solutie-detalii-evaluare.php?{force_reload}id={}",
+            self.solution_id
+        )
+    }
+
+    fn headers(&self) -> HeaderMap {
+        cookie_headers(self.ssid.as_deref())
+    }
+}
+
+/// `GET /?pagina=probleme-search`, the html page with the problems whose
+/// name matches a query
+#[derive(Debug, Clone)]
+pub struct ProblemSearch {
+    query: String,
+    grade: Option<u8>,
+    category: Option<String>,
+    ssid: Option<String>,
+}
+
+impl ProblemSearch {
+    pub fn new(query: impl Into<String>) -> Self {
+        ProblemSearch {
+            query: query.into(),
+            grade: None,
+            category: None,
+            ssid: None,
+        }
+    }
+
+    /// Only the problems for a grade, like 9
+    pub fn grade(mut self, grade: u8) -> Self {
+        self.grade = Some(grade);
+        self
+    }
+
+    /// Only the problems of a category, by the id pbinfo has in its urls
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for ProblemSearch {
+    fn name(&self) -> &'static str {
+        "problem-search"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        let mut params = vec![
+            ("pagina", "probleme-search".to_string()),
+            ("denumire", self.query.clone()),
+        ];
+        if let Some(grade) = self.grade {
+            params.push(("clasa", grade.to_string()));
+        }
+        if let Some(category) = &self.category {
+            params.push(("id_categorie", category.clone()));
+        }
+        reqwest::Url::parse_with_params(&format!("{BASE_URL}/"), &params)
+            .expect("the base url is valid")
+            .to_string()
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(self.ssid.as_deref(), &format!("{BASE_URL}/"))
+    }
+}
+
+/// How many problems pbinfo shows on a page of a category
+pub const PROBLEMS_PER_PAGE: u32 = 20;
+
+/// `GET /probleme/categorii/{category}`, the html page with the problems of
+/// a category, [PROBLEMS_PER_PAGE] at a time
+#[derive(Debug, Clone)]
+pub struct CategoryProblems {
+    category: String,
+    page: u32,
+    ssid: Option<String>,
+}
+
+impl CategoryProblems {
+    /// `category` is the id pbinfo has in its urls
+    pub fn new(category: impl Into<String>) -> Self {
+        CategoryProblems {
+            category: category.into(),
+            page: 1,
+            ssid: None,
+        }
+    }
+
+    /// Which page of the category to get, starting from 1
+    pub fn page(mut self, page: u32) -> Self {
+        self.page = page;
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for CategoryProblems {
+    fn name(&self) -> &'static str {
+        "category-problems"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{BASE_URL}/probleme/categorii/{}?start={}",
+            self.category,
+            self.page.saturating_sub(1) * PROBLEMS_PER_PAGE
+        )
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(self.ssid.as_deref(), &format!("{BASE_URL}/probleme"))
+    }
+}
+
+/// `GET /profil/{username}`, the html page of the profile of a user. Made
+/// with [Profile::by_id] it is `GET /?pagina=profil&id_user={id}`
+#[derive(Debug, Clone)]
+pub struct Profile {
+    username: String,
+    by_id: bool,
+    ssid: Option<String>,
+}
+
+impl Profile {
+    pub fn new(username: impl Into<String>) -> Self {
+        Profile {
+            username: username.into(),
+            by_id: false,
+            ssid: None,
+        }
+    }
+
+    /// The profile of the user with the id `user_id` instead of a username
+    pub fn by_id(user_id: impl Into<String>) -> Self {
+        Profile {
+            username: user_id.into(),
+            by_id: true,
+            ssid: None,
+        }
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for Profile {
+    fn name(&self) -> &'static str {
+        "profile"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        if self.by_id {
+            reqwest::Url::parse_with_params(
+                &format!("{BASE_URL}/"),
+                [("pagina", "profil"), ("id_user", &self.username)],
+            )
+            .expect("the base url is valid")
+            .to_string()
+        } else {
+            page_url(&["profil", &self.username])
+        }
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(self.ssid.as_deref(), &format!("{BASE_URL}/"))
+    }
+}
+
+/// `GET /?pagina=probleme-rezolvate`, the html page with the problems a
+/// user solved and the ones they only attempted
+#[derive(Debug, Clone)]
+pub struct SolvedProblems {
+    user_id: String,
+    ssid: Option<String>,
+}
+
+impl SolvedProblems {
+    pub fn new(user_id: impl Into<String>) -> Self {
+        SolvedProblems {
+            user_id: user_id.into(),
+            ssid: None,
+        }
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for SolvedProblems {
+    fn name(&self) -> &'static str {
+        "solved-problems"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{BASE_URL}/?pagina=probleme-rezolvate&id_user={}",
+            self.user_id
+        )
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(self.ssid.as_deref(), &format!("{BASE_URL}/"))
+    }
+}
+
+/// How many users pbinfo shows on a page of the ranking
+pub const RANKING_PER_PAGE: u32 = 50;
+
+/// `GET /clasament`, the html page with the ranking of the users, either of
+/// everyone or only of a county or a school, [RANKING_PER_PAGE] at a time
+#[derive(Debug, Clone, Default)]
+pub struct Ranking {
+    county: Option<String>,
+    school: Option<String>,
+    page: u32,
+    ssid: Option<String>,
+}
+
+impl Ranking {
+    pub fn new() -> Self {
+        Ranking {
+            page: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Only the users of a county, by the code pbinfo has in its urls
+    pub fn county(mut self, county: impl Into<String>) -> Self {
+        self.county = Some(county.into());
+        self
+    }
+
+    /// Only the users of a school, by the id pbinfo has in its urls
+    pub fn school(mut self, school: impl Into<String>) -> Self {
+        self.school = Some(school.into());
+        self
+    }
+
+    /// Which page of the ranking to get, starting from 1
+    pub fn page(mut self, page: u32) -> Self {
+        self.page = page;
+        self
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for Ranking {
+    fn name(&self) -> &'static str {
+        "ranking"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        let mut params = vec![(
+            "start",
+            (self.page.saturating_sub(1) * RANKING_PER_PAGE).to_string(),
+        )];
+        if let Some(county) = &self.county {
+            params.push(("judet", county.clone()));
+        }
+        if let Some(school) = &self.school {
+            params.push(("id_scoala", school.clone()));
+        }
+        reqwest::Url::parse_with_params(&format!("{BASE_URL}/clasament"), &params)
+            .expect("the base url is valid")
+            .to_string()
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(self.ssid.as_deref(), &format!("{BASE_URL}/"))
+    }
+}
+
+/// `GET /profil/{username}/succese`, the html page with the achievements
+/// (badges) of a user
+#[derive(Debug, Clone)]
+pub struct Achievements {
+    username: String,
+    ssid: Option<String>,
+}
+
+impl Achievements {
+    pub fn new(username: impl Into<String>) -> Self {
+        Achievements {
+            username: username.into(),
+            ssid: None,
+        }
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for Achievements {
+    fn name(&self) -> &'static str {
+        "achievements"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        page_url(&["profil", &self.username, "succese"])
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(self.ssid.as_deref(), &page_url(&["profil", &self.username]))
+    }
+}
+
+/// `GET /?pagina=clasele-mele`, the html page with the classes the logged in
+/// user belongs to
+#[derive(Debug, Clone, Default)]
+pub struct Classes {
+    ssid: Option<String>,
+}
+
+impl Classes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for Classes {
+    fn name(&self) -> &'static str {
+        "classes"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        format!("{BASE_URL}/?pagina=clasele-mele")
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(self.ssid.as_deref(), &format!("{BASE_URL}/"))
+    }
+}
+
+/// `GET /?pagina=clasa&id_clasa={id}`, the html page of a class with the
+/// assignments ("teme") posted in it
+#[derive(Debug, Clone)]
+pub struct ClassPage {
+    class_id: String,
+    ssid: Option<String>,
+}
+
+impl ClassPage {
+    pub fn new(class_id: impl Into<String>) -> Self {
+        ClassPage {
+            class_id: class_id.into(),
+            ssid: None,
+        }
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for ClassPage {
+    fn name(&self) -> &'static str {
+        "class"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        format!("{BASE_URL}/?pagina=clasa&id_clasa={}", self.class_id)
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(
+            self.ssid.as_deref(),
+            &format!("{BASE_URL}/?pagina=clasele-mele"),
+        )
+    }
+}
+
+/// `GET /?pagina=tema&id_tema={id}`, the html page of an assignment with its
+/// deadline and the problems to solve
+#[derive(Debug, Clone)]
+pub struct AssignmentPage {
+    assignment_id: String,
+    ssid: Option<String>,
+}
+
+impl AssignmentPage {
+    pub fn new(assignment_id: impl Into<String>) -> Self {
+        AssignmentPage {
+            assignment_id: assignment_id.into(),
+            ssid: None,
+        }
+    }
+
+    /// Sends the request with the given session cookie
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+}
+
+impl Endpoint for AssignmentPage {
+    fn name(&self) -> &'static str {
+        "assignment"
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        format!("{BASE_URL}/?pagina=tema&id_tema={}", self.assignment_id)
+    }
+
+    fn headers(&self) -> HeaderMap {
+        session_headers(
+            self.ssid.as_deref(),
+            &format!("{BASE_URL}/?pagina=clasele-mele"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_forms_send_an_origin() {
+        let upload = UploadSolution::new("1", "int main() {}", "sursa").ssid("abc");
+        assert_eq!(upload.headers()["Origin"], BASE_URL);
+        assert_eq!(
+            upload.headers()["Referer"],
+            format!("{BASE_URL}/probleme/1")
+        );
+
+        let problem = ProblemPage::new("1").ssid("abc");
+        assert!(!problem.headers().contains_key("Origin"));
+        assert!(problem.headers().contains_key("Referer"));
+
+        let evaluation = EvaluationDetails::new("1000").ssid("abc");
+        let headers = evaluation.headers();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["Cookie"], "SSID=abc");
+    }
+
+    #[test]
+    fn usernames_are_encoded() {
+        assert_eq!(
+            Profile::new("ana maria").url(),
+            format!("{BASE_URL}/profil/ana%20maria")
+        );
+        assert_eq!(
+            Achievements::new("../admin?x=1").url(),
+            format!("{BASE_URL}/profil/..%2Fadmin%3Fx=1/succese")
+        );
+        assert_eq!(
+            Profile::by_id("1&pagina=x").url(),
+            format!("{BASE_URL}/?pagina=profil&id_user=1%26pagina%3Dx")
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! One error type for applications that don't care which part of the crate
+//! failed.
+
+use thiserror::Error;
+
+#[cfg(feature = "bundle")]
+use crate::pbinfo_user::BundleError;
+#[cfg(feature = "tokio")]
+use crate::pbinfo_user::{SolveAndVerifyError, UploadAndWaitError};
+use crate::{
+    pbinfo_user::{
+        ClassError, ExportError, GetScoreError, LoginError, PbinfoUserError, ProblemError,
+        ProfileError, RankingError, RegisterError, SolutionsError, SolveError, UploadError,
+    },
+    telemetry::ContextError,
+    ClientBuildError,
+};
+
+/// Any error of the crate, every module error converts into it with `?`
+#[derive(Error, Debug)]
+pub enum PbinfoError {
+    #[error(transparent)]
+    Login(#[from] LoginError),
+    #[error(transparent)]
+    Upload(#[from] UploadError),
+    #[error(transparent)]
+    Solve(#[from] SolveError),
+    #[error(transparent)]
+    GetScore(#[from] GetScoreError),
+    #[cfg(feature = "tokio")]
+    #[error(transparent)]
+    UploadAndWait(#[from] UploadAndWaitError),
+    #[cfg(feature = "tokio")]
+    #[error(transparent)]
+    SolveAndVerify(#[from] SolveAndVerifyError),
+    #[error(transparent)]
+    Problem(#[from] ProblemError),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error(transparent)]
+    Runner(#[from] crate::runner::RunnerError),
+    #[error(transparent)]
+    Solutions(#[from] SolutionsError),
+    #[error(transparent)]
+    Profile(#[from] ProfileError),
+    #[error(transparent)]
+    Ranking(#[from] RankingError),
+    #[error(transparent)]
+    Class(#[from] ClassError),
+    #[error(transparent)]
+    Register(#[from] RegisterError),
+    #[error(transparent)]
+    Export(#[from] ExportError),
+    #[cfg(feature = "bundle")]
+    #[error(transparent)]
+    Bundle(#[from] BundleError),
+    #[error(transparent)]
+    User(#[from] PbinfoUserError),
+    #[error(transparent)]
+    Client(#[from] ClientBuildError),
+}
+
+/// Drops the context, read it with [ContextError::context] before converting
+/// if it is needed
+impl<E> From<ContextError<E>> for PbinfoError
+where
+    PbinfoError: From<E>,
+{
+    fn from(err: ContextError<E>) -> Self {
+        err.into_inner().into()
+    }
+}
+
+impl PbinfoError {
+    /// A short code for the error like `login.incorrect_credentials`, that
+    /// stays the same between versions (unlike the messages) so applications
+    /// can match on it or show their own messages
+    pub fn code(&self) -> &'static str {
+        match self {
+            PbinfoError::Login(err) => match err {
+                LoginError::NoCookieError => "login.no_cookie",
+                LoginError::HeaderParseError { .. } => "login.header_parse",
+                LoginError::CookieParseError { .. } => "login.cookie_parse",
+                LoginError::RequestSendError { .. } => "login.request_send",
+                LoginError::RequestBuildError { .. } => "login.request_build",
+                LoginError::ResponseParseError { .. } => "login.response_parse",
+                LoginError::JsonParseError { .. } => "login.json_parse",
+                #[allow(deprecated)]
+                LoginError::IncorrectUsernameOrPasswordError => "login.incorrect_credentials",
+                LoginError::IncorrectCredentialsError { .. } => "login.incorrect_credentials",
+                LoginError::InvalidLoginError { .. } => "login.invalid_login",
+                LoginError::TooManyAttempts { .. } => "login.too_many_attempts",
+                LoginError::NoUserIdError => "login.no_user_id",
+                LoginError::FormTokenExpiredError { .. } => "login.form_token_expired",
+                LoginError::TimeoutError { .. } => "login.timeout",
+                LoginError::InvalidSessionError => "login.invalid_session",
+            },
+            PbinfoError::Upload(err) => upload_code(err),
+            PbinfoError::Solve(err) => solve_code(err),
+            PbinfoError::GetScore(err) => score_code(err),
+            #[cfg(feature = "tokio")]
+            PbinfoError::UploadAndWait(err) => match err {
+                UploadAndWaitError::UploadError { err } => upload_code(err),
+                UploadAndWaitError::ScoreError { err, .. } => score_code(err),
+                UploadAndWaitError::DeadlineExceeded { .. } => "upload_and_wait.deadline_exceeded",
+            },
+            #[cfg(feature = "tokio")]
+            PbinfoError::SolveAndVerify(err) => match err {
+                SolveAndVerifyError::SolveError { err } => solve_code(err),
+                SolveAndVerifyError::NothingToVerify { .. } => "solve_and_verify.nothing_to_verify",
+                SolveAndVerifyError::ScoreError { err, .. } => score_code(err),
+                SolveAndVerifyError::BelowThreshold { .. } => "solve_and_verify.below_threshold",
+            },
+            PbinfoError::Problem(err) => match err {
+                ProblemError::RequestError { .. } => "problem.request",
+                ProblemError::NotFoundError { .. } => "problem.not_found",
+                ProblemError::ParseError { .. } => "problem.parse",
+                ProblemError::ListError { .. } => "problem.list",
+            },
+            #[cfg(not(target_arch = "wasm32"))]
+            PbinfoError::Runner(err) => match err {
+                crate::runner::RunnerError::ExamplesError { .. } => "runner.examples",
+                crate::runner::RunnerError::SpawnError { .. } => "runner.spawn",
+                crate::runner::RunnerError::CompileError { .. } => "runner.compile",
+                crate::runner::RunnerError::IoError { .. } => "runner.io",
+            },
+            PbinfoError::Solutions(err) => match err {
+                SolutionsError::ListError { .. } => "solutions.list",
+                SolutionsError::SourceError { .. } => "solutions.source",
+            },
+            PbinfoError::Profile(err) => match err {
+                ProfileError::NotLoggedInError => "profile.not_logged_in",
+                ProfileError::NotFoundError { .. } => "profile.not_found",
+                ProfileError::RequestError { .. } => "profile.request",
+                ProfileError::ParseError { .. } => "profile.parse",
+            },
+            PbinfoError::Ranking(err) => match err {
+                RankingError::RequestError { .. } => "ranking.request",
+            },
+            PbinfoError::Class(err) => match err {
+                ClassError::NotLoggedInError => "class.not_logged_in",
+                ClassError::NotFoundError { .. } => "class.not_found",
+                ClassError::RequestError { .. } => "class.request",
+            },
+            PbinfoError::Register(err) => match err {
+                RegisterError::InvalidUsernameError { .. } => "register.invalid_username",
+                RegisterError::InvalidEmailError { .. } => "register.invalid_email",
+                RegisterError::WeakPasswordError { .. } => "register.weak_password",
+                RegisterError::MissingSchoolError => "register.missing_school",
+                RegisterError::UsernameTakenError { .. } => "register.username_taken",
+                RegisterError::EmailTakenError { .. } => "register.email_taken",
+                RegisterError::RejectedError { .. } => "register.rejected",
+                RegisterError::RequestError { .. } => "register.request",
+            },
+            PbinfoError::Export(err) => match err {
+                ExportError::ListError { .. } => "export.list",
+                ExportError::DownloadError { .. } => "export.download",
+                ExportError::WriteError { .. } => "export.write",
+            },
+            #[cfg(feature = "bundle")]
+            PbinfoError::Bundle(err) => match err {
+                BundleError::ProblemPageError { .. } => "bundle.problem_page",
+                BundleError::NoSolutionError { .. } => "bundle.no_solution",
+                BundleError::DownloadError { .. } => "bundle.download",
+                BundleError::WriteError { .. } => "bundle.write",
+                BundleError::ReadError { .. } => "bundle.read",
+            },
+            PbinfoError::User(err) => match err {
+                PbinfoUserError::NoHomeDirError => "config.no_home_dir",
+                PbinfoUserError::ReadConfigError { .. } => "config.read",
+                PbinfoUserError::WriteError { .. } => "config.write",
+                #[cfg(feature = "config")]
+                PbinfoUserError::TomlParseError { .. } => "config.toml_parse",
+                PbinfoUserError::StoreError { .. } => "config.store",
+                PbinfoUserError::MissingEnvError { .. } => "config.missing_env",
+                PbinfoUserError::MissingProfileError { .. } => "config.missing_profile",
+                PbinfoUserError::InMemoryError => "config.in_memory",
+                PbinfoUserError::InvalidValueError { .. } => "config.invalid_value",
+            },
+            PbinfoError::Client(err) => match err {
+                ClientBuildError::ReqwestError { .. } => "client.build",
+                ClientBuildError::InvalidBaseUrlError { .. } => "client.invalid_base_url",
+                ClientBuildError::InvalidProxyError { .. } => "client.invalid_proxy",
+                ClientBuildError::InvalidRateLimitError { .. } => "client.invalid_rate_limit",
+            },
+        }
+    }
+}
+
+fn upload_code(err: &UploadError) -> &'static str {
+    match err {
+        UploadError::UploadError { .. } => "upload.failed",
+        UploadError::ResponseParseError { .. } => "upload.response_parse",
+        UploadError::NotLoggedInError => "upload.not_logged_in",
+        UploadError::BadStatusCodeError { .. } => "upload.bad_status_code",
+        #[allow(deprecated)]
+        UploadError::CooldownError => "upload.rate_limited",
+        UploadError::RateLimited { .. } => "upload.rate_limited",
+        UploadError::EvaluationPendingError => "upload.evaluation_pending",
+        UploadError::SessionExpired => "upload.session_expired",
+    }
+}
+
+fn solve_code(err: &SolveError) -> &'static str {
+    match err {
+        SolveError::GetSolutionError { .. } => "solve.get_solution",
+        SolveError::UploadError { err, .. } => upload_code(err),
+        SolveError::DeadlineExceeded { .. } => "solve.deadline_exceeded",
+    }
+}
+
+fn score_code(err: &GetScoreError) -> &'static str {
+    match err {
+        GetScoreError::GenericError { .. } => "score.failed",
+        GetScoreError::ParseJsonError { .. } => "score.json_parse",
+        GetScoreError::TimeoutError { .. } => "score.timeout",
+        GetScoreError::SessionExpired => "score.session_expired",
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use crate::{client::PbinfoClient, endpoints::Endpoint, models::SolutionEntry, parse};
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum BodyError {
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error("Error: The session expired, pbinfo answered with the login page!")]
+    SessionExpired,
+}
+
+/// Sends the request of an endpoint. Every request the crate makes goes
+/// through here
+pub(crate) async fn send<E: Endpoint>(
+    client: &PbinfoClient,
+    endpoint: &E,
+) -> Result<reqwest::Response, reqwest::Error> {
+    send_with_timeout(client, endpoint, None).await
+}
+
+/// Same as [send] but gives up after `timeout`
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "request",
+        skip_all,
+        fields(endpoint = endpoint.name(), method = %endpoint.method(), status)
+    )
+)]
+pub(crate) async fn send_with_timeout<E: Endpoint>(
+    client: &PbinfoClient,
+    endpoint: &E,
+    timeout: Option<Duration>,
+) -> Result<reqwest::Response, reqwest::Error> {
+    #[cfg(feature = "tokio")]
+    if let Some(rate_limiter) = &client.rate_limiter {
+        rate_limiter.acquire().await;
+    }
+
+    let started = crate::time::Instant::now();
+
+    let mut request = endpoint.request(&client.http);
+    if let Some(timeout) = timeout {
+        request = request.timeout(timeout);
+    }
+    let result = match request.build() {
+        Ok(mut request) => {
+            client.rebase(&mut request);
+            crate::telemetry::record_request(endpoint, request.url().as_str());
+            client.transport.execute(request).await
+        }
+        Err(err) => {
+            crate::telemetry::record_request(endpoint, &endpoint.url());
+            Err(err)
+        }
+    };
+
+    let elapsed = started.elapsed();
+    let status = result.as_ref().ok().map(|response| response.status());
+
+    #[cfg(feature = "metrics")]
+    crate::metrics::record_request(endpoint.name(), elapsed, result.is_err());
+
+    #[cfg(feature = "tracing")]
+    match &result {
+        Ok(response) => {
+            tracing::Span::current().record("status", response.status().as_u16());
+            tracing::debug!(url = %response.url(), ?elapsed, "request finished");
+        }
+        Err(err) => tracing::warn!(error = %err, ?elapsed, "request failed"),
+    }
+
+    if let Some(hook) = &client.on_request_complete {
+        hook(endpoint.name(), elapsed, status);
+    }
+
+    result
+}
+
+/// Reads the body of a response, decoded with the charset it was sent in
+/// (see [parse::decode])
+pub(crate) async fn text(response: reqwest::Response) -> Result<String, reqwest::Error> {
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.to_string());
+    let body = response.bytes().await?;
+    Ok(parse::decode(&body, content_type.as_deref()))
+}
+
+/// Reads the body of a json endpoint, failing with
+/// [BodyError::SessionExpired] if pbinfo sent the login page instead
+pub(crate) async fn json_body(response: reqwest::Response) -> Result<String, BodyError> {
+    let body = text(response).await?;
+    if parse::is_login_page(&body) {
+        trace_event!(tracing::Level::WARN, "got the login page instead of json");
+        return Err(BodyError::SessionExpired);
+    }
+    Ok(body)
+}
+
+/// Reads a body chunk by chunk as it is downloaded, so parsing can stop
+/// early. In the browser the whole body arrives as a single chunk
+pub(crate) struct BodyChunks {
+    response: Option<reqwest::Response>,
+}
+
+impl BodyChunks {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        BodyChunks {
+            response: Some(response),
+        }
+    }
+
+    pub(crate) async fn next(
+        &mut self,
+    ) -> Result<Option<impl std::ops::Deref<Target = [u8]>>, reqwest::Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        match &mut self.response {
+            Some(response) => response.chunk().await,
+            None => Ok(None),
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        match self.response.take() {
+            Some(response) => response.bytes().await.map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Goes through the solutions of a solution list response as they are
+/// downloaded, without keeping the list around. Returns the total number of
+/// solutions (`numar_total_solutii`)
+pub(crate) async fn for_each_solution(
+    response: reqwest::Response,
+    mut f: impl FnMut(SolutionEntry) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let mut scanner = parse::SolutionListScanner::new();
+    let mut checked_login_page = false;
+    let mut chunks = BodyChunks::new(response);
+    while let Some(chunk) = chunks.next().await? {
+        if !checked_login_page {
+            if let Some(&first) = chunk.trim_ascii_start().first() {
+                if first == b'<' {
+                    return Err(BodyError::SessionExpired.into());
+                }
+                checked_login_page = true;
+            }
+        }
+
+        for solution in scanner.feed(&chunk)? {
+            f(solution)?;
+        }
+    }
+
+    Ok(scanner.finish()?)
+}
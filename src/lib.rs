@@ -1 +1,28 @@
+#[macro_use]
+mod trace;
+
+mod client;
+pub mod endpoints;
+mod error;
+mod http;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod models;
+pub mod parse;
 pub mod pbinfo_user;
+#[cfg(feature = "tokio")]
+mod rate_limit;
+pub mod report;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod runner;
+pub mod telemetry;
+#[cfg(all(test, feature = "tokio"))]
+mod test_support;
+mod time;
+pub mod transport;
+pub mod user_config;
+
+pub use client::{ClientBuildError, PbinfoClient, PbinfoClientBuilder};
+pub use error::PbinfoError;
+pub use pbinfo_user::custom_solve;
+pub use transport::HttpTransport;
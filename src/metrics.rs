@@ -0,0 +1,216 @@
+//! Process wide counters and histograms about what the crate did.
+//!
+//! Everything is recorded in memory, read it with [snapshot] or, with the
+//! `prometheus` feature, in the Prometheus text format with [render_prometheus].
+
+use std::{
+    collections::BTreeMap,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+/// Upper bounds (in seconds) of the buckets of the request duration histogram
+const REQUEST_DURATION_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+/// Upper bounds (in seconds) of the buckets of the evaluation wait histogram
+const EVALUATION_WAIT_BUCKETS: &[f64] = &[1.5, 3.0, 5.0, 10.0, 20.0, 30.0, 60.0, 90.0];
+
+static REGISTRY: LazyLock<Mutex<Snapshot>> = LazyLock::new(|| Mutex::new(Snapshot::default()));
+
+/// A histogram with fixed buckets
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    /// `(upper bound, number of observations <= upper bound)`, the counts
+    /// are cumulative like in Prometheus
+    pub buckets: Vec<(f64, u64)>,
+    /// Sum of all the observations
+    pub sum: f64,
+    /// Number of observations
+    pub count: u64,
+}
+
+impl Histogram {
+    fn with_buckets(bounds: &[f64]) -> Self {
+        Histogram {
+            buckets: bounds.iter().map(|bound| (*bound, 0)).collect(),
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.buckets.iter_mut() {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// A copy of all the metrics at some point in time
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Requests sent, by endpoint name (see [crate::endpoints::Endpoint::name])
+    pub requests: BTreeMap<&'static str, u64>,
+    /// Requests that failed before getting any response, by endpoint name
+    pub failed_requests: BTreeMap<&'static str, u64>,
+    /// Duration of the requests, by endpoint name
+    pub request_duration: BTreeMap<&'static str, Histogram>,
+    /// How many times an operation was retried
+    pub retries: u64,
+    /// How many solutions were submitted successfully
+    pub submissions: u64,
+    /// How long it took for submitted solutions to finish evaluating
+    pub evaluation_wait: Histogram,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Snapshot {
+            requests: BTreeMap::new(),
+            failed_requests: BTreeMap::new(),
+            request_duration: BTreeMap::new(),
+            retries: 0,
+            submissions: 0,
+            evaluation_wait: Histogram::with_buckets(EVALUATION_WAIT_BUCKETS),
+        }
+    }
+}
+
+fn with_registry(f: impl FnOnce(&mut Snapshot)) {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|err| err.into_inner());
+    f(&mut registry);
+}
+
+pub(crate) fn record_request(endpoint: &'static str, duration: Duration, failed: bool) {
+    with_registry(|registry| {
+        *registry.requests.entry(endpoint).or_default() += 1;
+        if failed {
+            *registry.failed_requests.entry(endpoint).or_default() += 1;
+        }
+        registry
+            .request_duration
+            .entry(endpoint)
+            .or_insert_with(|| Histogram::with_buckets(REQUEST_DURATION_BUCKETS))
+            .observe(duration.as_secs_f64());
+    });
+}
+
+pub(crate) fn record_retry() {
+    with_registry(|registry| registry.retries += 1);
+}
+
+pub(crate) fn record_submission() {
+    with_registry(|registry| registry.submissions += 1);
+}
+
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+pub(crate) fn record_evaluation_wait(duration: Duration) {
+    with_registry(|registry| registry.evaluation_wait.observe(duration.as_secs_f64()));
+}
+
+/// Returns a copy of all the metrics recorded until now
+pub fn snapshot() -> Snapshot {
+    REGISTRY
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+}
+
+/// Sets all the metrics back to zero
+pub fn reset() {
+    with_registry(|registry| *registry = Snapshot::default());
+}
+
+#[cfg(feature = "prometheus")]
+fn render_histogram(out: &mut String, name: &str, labels: &str, histogram: &Histogram) {
+    use std::fmt::Write;
+
+    let separator = if labels.is_empty() { "" } else { "," };
+    for (bound, count) in &histogram.buckets {
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels}{separator}le=\"{bound}\"}} {count}"
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {}",
+        histogram.count
+    );
+    let labels = if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{labels}}}")
+    };
+    let _ = writeln!(out, "{name}_sum{labels} {}", histogram.sum);
+    let _ = writeln!(out, "{name}_count{labels} {}", histogram.count);
+}
+
+/// Renders all the metrics in the Prometheus text exposition format, ready
+/// to be served on a `/metrics` endpoint
+#[cfg(feature = "prometheus")]
+pub fn render_prometheus() -> String {
+    use std::fmt::Write;
+
+    let snapshot = snapshot();
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# HELP pbinfo_requests_total Requests sent to pbinfo.");
+    let _ = writeln!(out, "# TYPE pbinfo_requests_total counter");
+    for (endpoint, count) in &snapshot.requests {
+        let _ = writeln!(
+            out,
+            "pbinfo_requests_total{{endpoint=\"{endpoint}\"}} {count}"
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP pbinfo_failed_requests_total Requests that got no response."
+    );
+    let _ = writeln!(out, "# TYPE pbinfo_failed_requests_total counter");
+    for (endpoint, count) in &snapshot.failed_requests {
+        let _ = writeln!(
+            out,
+            "pbinfo_failed_requests_total{{endpoint=\"{endpoint}\"}} {count}"
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP pbinfo_request_duration_seconds Duration of the requests sent to pbinfo."
+    );
+    let _ = writeln!(out, "# TYPE pbinfo_request_duration_seconds histogram");
+    for (endpoint, histogram) in &snapshot.request_duration {
+        render_histogram(
+            &mut out,
+            "pbinfo_request_duration_seconds",
+            &format!("endpoint=\"{endpoint}\""),
+            histogram,
+        );
+    }
+
+    let _ = writeln!(out, "# HELP pbinfo_retries_total Retried operations.");
+    let _ = writeln!(out, "# TYPE pbinfo_retries_total counter");
+    let _ = writeln!(out, "pbinfo_retries_total {}", snapshot.retries);
+
+    let _ = writeln!(out, "# HELP pbinfo_submissions_total Submitted solutions.");
+    let _ = writeln!(out, "# TYPE pbinfo_submissions_total counter");
+    let _ = writeln!(out, "pbinfo_submissions_total {}", snapshot.submissions);
+
+    let _ = writeln!(
+        out,
+        "# HELP pbinfo_evaluation_wait_seconds Time until a submission finished evaluating."
+    );
+    let _ = writeln!(out, "# TYPE pbinfo_evaluation_wait_seconds histogram");
+    render_histogram(
+        &mut out,
+        "pbinfo_evaluation_wait_seconds",
+        "",
+        &snapshot.evaluation_wait,
+    );
+
+    out
+}
@@ -0,0 +1,358 @@
+//! Typed versions of the json responses of the pbinfo endpoints.
+//!
+//! With the `strict-models` feature every model rejects fields it doesn't
+//! know about and deserialization errors name the exact field that failed,
+//! so changes to the json pbinfo sends are noticed right away.
+
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(thiserror::Error, Debug)]
+#[error("Error: Couldn't deserialize a {model} at `{path}`!\nGot error: {err}\nJson was:\n{json}")]
+pub struct ModelError {
+    /// The name of the model that failed to deserialize
+    pub model: &'static str,
+    /// The path of the field that failed (`.` for the whole document)
+    pub path: String,
+    pub err: String,
+    pub json: String,
+}
+
+/// Deserializes `json` into `T`. With `strict-models` the error says which
+/// field of the json was the problem
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, ModelError> {
+    let model = std::any::type_name::<T>()
+        .rsplit("::")
+        .next()
+        .unwrap_or_default();
+
+    #[cfg(feature = "strict-models")]
+    {
+        let deserializer = &mut serde_json::Deserializer::from_str(json);
+        serde_path_to_error::deserialize(deserializer).map_err(|err| ModelError {
+            model,
+            path: err.path().to_string(),
+            err: err.inner().to_string(),
+            json: json.to_string(),
+        })
+    }
+
+    #[cfg(not(feature = "strict-models"))]
+    serde_json::from_str(json).map_err(|err| ModelError {
+        model,
+        path: ".".to_string(),
+        err: err.to_string(),
+        json: json.to_string(),
+    })
+}
+
+/// Response of [crate::endpoints::Login]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct LoginResponse {
+    /// The message shown to the user, like `Formularul a expirat. Încearcă din nou!`
+    #[serde(default)]
+    pub raspuns: String,
+    /// A new form token, sent when the old one expired
+    #[serde(default)]
+    pub form_token: Option<String>,
+}
+
+/// Response of [crate::endpoints::UploadSolution]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct UploadResponse {
+    /// `success` if the source was accepted for evaluation
+    pub stare: String,
+    /// The message shown to the user when the upload failed
+    #[serde(default)]
+    pub raspuns: Option<String>,
+    /// The id of the new solution, pbinfo sends it either as a string or
+    /// as a number
+    #[serde(default)]
+    pub id_solutie: Option<Value>,
+}
+
+impl UploadResponse {
+    /// The id of the new solution as a string
+    pub fn solution_id(&self) -> Option<String> {
+        id_string(self.id_solutie.as_ref()?)
+    }
+}
+
+/// pbinfo sends ids either as strings or as numbers
+fn id_string(id: &Value) -> Option<String> {
+    match id {
+        Value::String(id) => Some(id.clone()),
+        Value::Null => None,
+        id => Some(id.to_string()),
+    }
+}
+
+/// Response of [crate::endpoints::SolutionList]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct SolutionListResponse {
+    /// How many solutions the user sent to the problem in total
+    pub numar_total_solutii: i64,
+    /// The last solutions, as many as were asked for
+    #[serde(default)]
+    pub surse: Vec<SolutionEntry>,
+}
+
+/// One solution of a [SolutionListResponse]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct SolutionEntry {
+    /// The id of the solution, as a string or as a number
+    #[serde(default)]
+    pub id: Option<Value>,
+    /// The id of the problem the solution was sent to, as a string or as a
+    /// number
+    #[serde(default)]
+    pub id_problema: Option<Value>,
+    /// The language of the source, like `cpp` or `c`
+    #[serde(default)]
+    pub limbaj: Option<String>,
+    /// The score as a string, missing while the solution is evaluated
+    #[serde(default)]
+    pub scor: Option<String>,
+    /// The day the solution was uploaded, like `2024-03-15`
+    #[serde(default)]
+    pub data_upload: Option<String>,
+    /// The time of day the solution was uploaded, like `18:04:12`
+    #[serde(default)]
+    pub ora_upload: Option<String>,
+    /// The username of who sent the solution, only in the lists of public
+    /// solutions
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+impl SolutionEntry {
+    /// The id of the solution as a string
+    pub fn solution_id(&self) -> Option<String> {
+        id_string(self.id.as_ref()?)
+    }
+
+    /// The id of the problem as a string
+    pub fn problem_id(&self) -> Option<String> {
+        id_string(self.id_problema.as_ref()?)
+    }
+
+    /// When the solution was uploaded, like `2024-03-15 18:04:12`
+    pub fn submitted_at(&self) -> Option<String> {
+        submitted_at(self.data_upload.as_ref(), self.ora_upload.as_ref())
+    }
+
+    /// When the solution was uploaded, in the time of Romania. `None` if
+    /// pbinfo didn't send the date or it couldn't be parsed
+    #[cfg(feature = "chrono")]
+    pub fn uploaded_at(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        crate::parse::upload_time(self.data_upload.as_deref()?, self.ora_upload.as_deref()).ok()
+    }
+}
+
+fn submitted_at(date: Option<&String>, time: Option<&String>) -> Option<String> {
+    match (date, time) {
+        (Some(date), Some(time)) => Some(format!("{date} {time}")),
+        (Some(date), None) => Some(date.clone()),
+        _ => None,
+    }
+}
+
+/// Response of [crate::endpoints::SolutionSource]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct SolutionSource {
+    /// The source code exactly as it was uploaded
+    pub sursa: String,
+}
+
+/// Response of [crate::endpoints::EvaluationDetails], what pbinfo shows
+/// about the evaluation of a solution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct ScoreReport {
+    /// `pending` or `executing` while the solution is being evaluated
+    #[serde(default)]
+    pub status_sursa: String,
+    /// The score, as a string or as a number
+    #[serde(default)]
+    pub scor: Option<Value>,
+    /// What the compiler printed
+    #[serde(default)]
+    pub mesaj_compilare: Option<String>,
+    /// The results of every test
+    #[serde(default)]
+    pub teste: Vec<TestReport>,
+    /// The language of the source, like `cpp` or `c`
+    #[serde(default)]
+    pub limbaj: Option<String>,
+    /// The day the solution was uploaded, like `2024-03-15`
+    #[serde(default)]
+    pub data_upload: Option<String>,
+    /// The time of day the solution was uploaded, like `18:04:12`
+    #[serde(default)]
+    pub ora_upload: Option<String>,
+    /// The warnings out of [ScoreReport::mesaj_compilare], not sent by
+    /// pbinfo but filled in by [crate::parse::compiler_warnings]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compiler_warnings: Vec<String>,
+}
+
+/// The verdict of a whole evaluation, see [ScoreReport::outcome]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvaluationOutcome {
+    /// 100 points
+    Accepted,
+    /// Some points but not all of them
+    PartialScore(u8),
+    /// The source didn't compile
+    CompileError,
+    /// No points and at least one test crashed
+    RuntimeError,
+    /// No points and at least one test ran out of time
+    TimeLimit,
+    /// pbinfo couldn't evaluate the source
+    InternalError,
+    /// The evaluation isn't done yet
+    Pending,
+}
+
+impl ScoreReport {
+    /// The score out of 100, `None` until the evaluation is done
+    pub fn score(&self) -> Option<u8> {
+        score_value(self.scor.as_ref()?)
+    }
+
+    /// When the solution was uploaded, like `2024-03-15 18:04:12`
+    pub fn submitted_at(&self) -> Option<String> {
+        submitted_at(self.data_upload.as_ref(), self.ora_upload.as_ref())
+    }
+
+    /// When the solution was uploaded, in the time of Romania
+    #[cfg(feature = "chrono")]
+    pub fn uploaded_at(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        crate::parse::upload_time(self.data_upload.as_deref()?, self.ora_upload.as_deref()).ok()
+    }
+
+    /// The results of every test with typed values, in the order pbinfo ran them
+    pub fn test_cases(&self) -> Vec<TestCaseResult> {
+        self.teste
+            .iter()
+            .enumerate()
+            .map(|(index, test)| TestCaseResult {
+                index: index + 1,
+                points: test.score(),
+                time: test.time(),
+                memory_kb: test.memory_kb(),
+                verdict: test.mesaj.clone().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Sums up the evaluation in a single verdict
+    pub fn outcome(&self) -> EvaluationOutcome {
+        if self.status_sursa == "pending" || self.status_sursa == "executing" {
+            return EvaluationOutcome::Pending;
+        }
+
+        let compiled = !self
+            .mesaj_compilare
+            .as_deref()
+            .is_some_and(|message| message.contains("error:"));
+        if !compiled && self.teste.is_empty() {
+            return EvaluationOutcome::CompileError;
+        }
+
+        match self.score() {
+            Some(100) => EvaluationOutcome::Accepted,
+            Some(0) if self.any_test(&["limita de timp", "time limit"]) => {
+                EvaluationOutcome::TimeLimit
+            }
+            Some(0) if self.any_test(&["signal", "semnal", "runtime", "eroare la executie"]) => {
+                EvaluationOutcome::RuntimeError
+            }
+            Some(score) => EvaluationOutcome::PartialScore(score),
+            None => EvaluationOutcome::InternalError,
+        }
+    }
+
+    /// Whether the message of a test contains one of `needles`, ignoring case
+    fn any_test(&self, needles: &[&str]) -> bool {
+        self.teste.iter().any(|test| {
+            test.mesaj.as_deref().is_some_and(|message| {
+                let message = message.to_lowercase();
+                needles.iter().any(|needle| message.contains(needle))
+            })
+        })
+    }
+}
+
+/// One test of a [ScoreReport]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct TestReport {
+    /// The points got on this test, as a string or as a number
+    #[serde(default)]
+    pub scor: Option<Value>,
+    /// The verdict of the test, like `OK` or `Raspuns gresit`
+    #[serde(default)]
+    pub mesaj: Option<String>,
+    /// How long the test ran, in seconds
+    #[serde(default)]
+    pub timp: Option<Value>,
+    /// How much memory the test used, in kb
+    #[serde(default)]
+    pub memorie: Option<Value>,
+}
+
+impl TestReport {
+    /// The points got on this test
+    pub fn score(&self) -> Option<u8> {
+        score_value(self.scor.as_ref()?)
+    }
+
+    /// How long the test ran
+    pub fn time(&self) -> Option<Duration> {
+        let seconds = match self.timp.as_ref()? {
+            Value::Number(seconds) => seconds.as_f64()?,
+            Value::String(seconds) => seconds.trim().parse().ok()?,
+            _ => return None,
+        };
+        Duration::try_from_secs_f64(seconds).ok()
+    }
+
+    /// How much memory the test used, in kb
+    pub fn memory_kb(&self) -> Option<u64> {
+        match self.memorie.as_ref()? {
+            Value::Number(memory) => memory.as_u64(),
+            Value::String(memory) => memory.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// One test of an evaluation, made by [ScoreReport::test_cases]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCaseResult {
+    /// The number of the test, starting from 1
+    pub index: usize,
+    /// The points awarded, `None` if pbinfo didn't send them
+    pub points: Option<u8>,
+    pub time: Option<Duration>,
+    pub memory_kb: Option<u64>,
+    /// What pbinfo says about the test, like `OK` or `Raspuns gresit`
+    pub verdict: String,
+}
+
+fn score_value(score: &Value) -> Option<u8> {
+    match score {
+        Value::Number(score) => score.as_u64().and_then(|score| u8::try_from(score).ok()),
+        Value::String(score) => score.trim().parse().ok(),
+        _ => None,
+    }
+}
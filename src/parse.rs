@@ -0,0 +1,2118 @@
+//! Pure functions that turn what pbinfo sends back into data.
+//!
+//! Nothing here touches the network, so every function can be run against
+//! pages and responses saved from the site.
+
+use encoding_rs::{Encoding, UTF_8};
+use serde_json::Value;
+
+use crate::{
+    models,
+    pbinfo_user::{
+        Achievement, Assignment, AssignmentSummary, CategoryProgress, Class, Difficulty,
+        Evaluation, Example, Problem, ProblemMeta, ProblemProgress, ProblemSummary,
+        ProgressSummary, RankingEntry, ScoreStatus, UserProfile,
+    },
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ParseError {
+    #[error("Error: Couldn't find '{marker}' in the page!\nMaybe the user wasn't logged in or the api of pbinfo changed!")]
+    MarkerNotFound { marker: &'static str },
+    #[error("Error: Couldn't parse the following cookie:\n{cookie}!\nGot error:\n{err}")]
+    CookieError { cookie: String, err: String },
+    #[error("Error: Couldn't parse the following text to a json:\n{json}\nGot error:\n{err}")]
+    JsonError { json: String, err: String },
+    #[error("Error: Couldn't parse the following date: {date}!")]
+    DateError { date: String },
+}
+
+// we are looking for the user in a string that looks something
+// like this:
+// {page html}
+// user_autentificat = {"id":XXXXXX,"user":"...",...};
+// {continuation page html}
+// the object is read like a js object, so different spacing, unquoted keys,
+// single quotes or the id not being the first key don't break it
+const USER_MARKER: &str = "user_autentificat";
+/// How much of the `user_autentificat` object is buffered before giving up
+/// on parsing all of it and only taking the id
+const MAX_USER_OBJECT_LEN: usize = 16 * 1024;
+
+/// The logged in user, as it appears in the `user_autentificat` object
+/// embedded in every pbinfo page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageUser {
+    /// `"0"` if nobody is logged in
+    pub id: String,
+    /// The name shown on the site, if the page had one
+    pub display_name: Option<String>,
+    /// The name used in the urls of the user, like the one of the profile
+    pub username: Option<String>,
+}
+
+/// What comes after a [USER_MARKER]
+enum ObjectStart {
+    /// The object assigned to it starts at this offset
+    At(usize),
+    /// Only spaces or the `=` so far
+    NeedMore,
+    /// It is only read there, like in `if (user_autentificat.id > 0)`
+    NotAssigned,
+}
+
+fn object_start(bytes: &[u8]) -> ObjectStart {
+    let mut assigned = false;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'{' if assigned => return ObjectStart::At(i),
+            b'=' if !assigned => assigned = true,
+            _ if b.is_ascii_whitespace() => (),
+            _ => return ObjectStart::NotAssigned,
+        }
+    }
+    ObjectStart::NeedMore
+}
+
+/// Returns the length of the js object at the start of `bytes`, `None` if
+/// it doesn't end in `bytes`
+fn object_len(bytes: &[u8]) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, &b) in bytes.iter().enumerate() {
+        if let Some(end) = quote {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                _ if b == end => quote = None,
+                _ => (),
+            }
+            continue;
+        }
+        match b {
+            b'"' | b'\'' => quote = Some(b),
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Rewrites a js object literal as json: quotes the bare keys, turns single
+/// quoted strings into double quoted ones and drops the trailing commas
+fn js_to_json(object: &str) -> String {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    let mut json = String::with_capacity(object.len());
+    let mut containers = Vec::new();
+    let mut expect_key = false;
+    let mut chars = object.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                json.push('"');
+                while let Some(inner) = chars.next() {
+                    match inner {
+                        '\\' => match chars.next() {
+                            Some('\'') => json.push('\''),
+                            Some(escaped) => {
+                                json.push('\\');
+                                json.push(escaped);
+                            }
+                            None => (),
+                        },
+                        _ if inner == c => break,
+                        '"' => json.push_str("\\\""),
+                        _ => json.push(inner),
+                    }
+                }
+                json.push('"');
+                expect_key = false;
+            }
+            '{' | '[' => {
+                containers.push(c);
+                expect_key = c == '{';
+                json.push(c);
+            }
+            '}' | ']' => {
+                containers.pop();
+                expect_key = false;
+                json.push(c);
+            }
+            ',' => {
+                let next = chars.clone().find(|c| !c.is_whitespace());
+                if !matches!(next, Some('}' | ']')) {
+                    json.push(c);
+                }
+                expect_key = containers.last() == Some(&'{');
+            }
+            _ if expect_key && is_ident(c) && !c.is_ascii_digit() => {
+                json.push('"');
+                json.push(c);
+                while let Some(next) = chars.next_if(|&next| is_ident(next)) {
+                    json.push(next);
+                }
+                json.push('"');
+                expect_key = false;
+            }
+            _ => {
+                expect_key &= c.is_whitespace();
+                json.push(c);
+            }
+        }
+    }
+    json
+}
+
+/// The last resort when the object can't be parsed at all: the number
+/// after the first `id` key
+fn scan_id(object: &str) -> Option<String> {
+    object.match_indices("id").find_map(|(start, _)| {
+        let before = object[..start].trim_end_matches(['"', '\'']).trim_end();
+        if !before.ends_with(['{', ',']) {
+            return None;
+        }
+        let value = object[start + "id".len()..]
+            .trim_start_matches(['"', '\''])
+            .trim_start()
+            .strip_prefix(':')?
+            .trim_start()
+            .trim_start_matches(['"', '\'']);
+        let end = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        (end > 0).then(|| value[..end].to_string())
+    })
+}
+
+fn page_user_from_object(object: &Value) -> Option<PageUser> {
+    let id = match &object["id"] {
+        Value::Number(id) => id.to_string(),
+        Value::String(id) => id.clone(),
+        _ => return None,
+    };
+
+    let full_name = [&object["prenume"], &object["nume"]]
+        .iter()
+        .filter_map(|name| name.as_str())
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let username = object["user"].as_str().map(|user| user.to_string());
+    let display_name = if full_name.is_empty() {
+        username.clone()
+    } else {
+        Some(full_name)
+    };
+
+    Some(PageUser {
+        id,
+        display_name,
+        username,
+    })
+}
+
+/// Parses the user out of `bytes`, which start with the `user_autentificat`
+/// object. Falls back to the js syntax and then to only the id. `None` if
+/// more bytes are needed
+fn page_user_from_bytes(
+    bytes: &[u8],
+    complete: bool,
+    content_type: Option<&str>,
+) -> Option<PageUser> {
+    let id_only = |object: &str| {
+        scan_id(object).map(|id| PageUser {
+            id,
+            display_name: None,
+            username: None,
+        })
+    };
+
+    match object_len(bytes) {
+        Some(len) => {
+            let object = decode(&bytes[..len], content_type);
+            serde_json::from_str::<Value>(&object)
+                .or_else(|_| serde_json::from_str::<Value>(&js_to_json(&object)))
+                .ok()
+                .and_then(|object| page_user_from_object(&object))
+                .or_else(|| id_only(&object))
+        }
+        None if complete || bytes.len() > MAX_USER_OBJECT_LEN => {
+            let end = bytes.len().min(MAX_USER_OBJECT_LEN);
+            id_only(&decode(&bytes[..end], content_type))
+        }
+        None => None,
+    }
+}
+
+/// Returns the logged in user from the html of any pbinfo page
+pub fn page_user(html: &str) -> Result<PageUser, ParseError> {
+    html.match_indices(USER_MARKER)
+        .find_map(|(start, _)| {
+            let after = &html.as_bytes()[start + USER_MARKER.len()..];
+            match object_start(after) {
+                ObjectStart::At(offset) => page_user_from_bytes(&after[offset..], true, None),
+                _ => None,
+            }
+        })
+        .ok_or(ParseError::MarkerNotFound {
+            marker: USER_MARKER,
+        })
+}
+
+/// Returns the id of the logged in user from the html of any pbinfo page,
+/// `"0"` if nobody is logged in
+pub fn user_id(html: &str) -> Result<String, ParseError> {
+    page_user(html).map(|user| user.id)
+}
+
+/// Whether `body` is the login page pbinfo answers with, instead of json,
+/// when the session expired
+pub fn is_login_page(body: &str) -> bool {
+    body.trim_start().starts_with('<')
+        && (body.contains("name=\"parola\"") || page_user(body).is_ok_and(|user| user.id == "0"))
+}
+
+/// Incremental version of [page_user] for scanning a page as it is downloaded.
+///
+/// Only the bytes that could still be part of the user object are kept
+/// around, so the whole page never has to be in memory
+#[derive(Debug, Default)]
+pub struct PageUserScanner {
+    window: Vec<u8>,
+    content_type: Option<String>,
+}
+
+impl PageUserScanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A scanner for a page sent with the given `Content-Type` header, so
+    /// the user is decoded with the right charset
+    pub fn for_content_type(content_type: Option<&str>) -> Self {
+        PageUserScanner {
+            window: Vec::new(),
+            content_type: content_type.map(|content_type| content_type.to_string()),
+        }
+    }
+
+    /// Feeds the next chunk of the page, returns the user as soon as it
+    /// was seen in full
+    pub fn feed(&mut self, chunk: &[u8]) -> Option<PageUser> {
+        let marker = USER_MARKER.as_bytes();
+        self.window.extend_from_slice(chunk);
+
+        loop {
+            let Some(start) = self
+                .window
+                .windows(marker.len())
+                .position(|window| window == marker)
+            else {
+                let keep = marker.len() - 1;
+                if self.window.len() > keep {
+                    self.window.drain(..self.window.len() - keep);
+                }
+                return None;
+            };
+            self.window.drain(..start);
+
+            let after = &self.window[marker.len()..];
+            match object_start(after) {
+                ObjectStart::At(offset) => {
+                    return page_user_from_bytes(
+                        &after[offset..],
+                        false,
+                        self.content_type.as_deref(),
+                    )
+                }
+                ObjectStart::NeedMore => return None,
+                // look for the next one
+                ObjectStart::NotAssigned => drop(self.window.drain(..marker.len())),
+            }
+        }
+    }
+
+    /// Call once the page ended, returns whatever could still be parsed
+    pub fn finish(self) -> Result<PageUser, ParseError> {
+        let user = self
+            .window
+            .strip_prefix(USER_MARKER.as_bytes())
+            .and_then(|after| match object_start(after) {
+                ObjectStart::At(offset) => {
+                    page_user_from_bytes(&after[offset..], true, self.content_type.as_deref())
+                }
+                _ => None,
+            });
+        user.ok_or(ParseError::MarkerNotFound {
+            marker: USER_MARKER,
+        })
+    }
+}
+
+/// How far into a page a `<meta charset>` is looked for
+const META_CHARSET_WINDOW: usize = 1024;
+
+/// Returns the charset a response was sent in, from the `Content-Type`
+/// header or, if it doesn't say, from the `<meta>` tags of the page
+fn charset<'a>(content_type: Option<&'a str>, body: &'a [u8]) -> Option<&'a [u8]> {
+    let from_header = content_type.and_then(|content_type| {
+        content_type
+            .split(';')
+            .find_map(|param| param.trim().strip_prefix("charset="))
+            .map(|charset| charset.trim_matches(|c| c == '"' || c == '\'').as_bytes())
+    });
+    if from_header.is_some() {
+        return from_header;
+    }
+
+    // covers both <meta charset="..."> and
+    // <meta http-equiv="Content-Type" content="text/html; charset=...">
+    let head = &body[..body.len().min(META_CHARSET_WINDOW)];
+    let marker = b"charset=";
+    let start = head
+        .windows(marker.len())
+        .position(|window| window.eq_ignore_ascii_case(marker))?
+        + marker.len();
+    let value = &head[start..];
+    let value = value.strip_prefix(b"\"").unwrap_or(value);
+    let value = value.strip_prefix(b"'").unwrap_or(value);
+    let end = value
+        .iter()
+        .position(|&b| matches!(b, b'"' | b'\'' | b';' | b'/' | b'>') || b.is_ascii_whitespace())
+        .unwrap_or(value.len());
+    Some(&value[..end])
+}
+
+/// Decodes a response body using the charset from its `Content-Type` header
+/// or its `<meta>` tags, utf-8 if neither names one
+pub fn decode(body: &[u8], content_type: Option<&str>) -> String {
+    let encoding = charset(content_type, body)
+        .and_then(Encoding::for_label)
+        .unwrap_or(UTF_8);
+    encoding.decode(body).0.into_owned()
+}
+
+/// Returns the value of the SSID cookie from a `set-cookie` header like
+/// `SSID=abc; path=/`
+pub fn ssid_cookie(set_cookie: &str) -> Result<String, ParseError> {
+    let cookie = set_cookie.split(';').next().unwrap_or_default();
+
+    cookie
+        .split('=')
+        .nth(1)
+        .map(|ssid| ssid.to_string())
+        .ok_or_else(|| ParseError::CookieError {
+            cookie: cookie.to_string(),
+            err: "Couldn't find anything after the '=' sign!".to_string(),
+        })
+}
+
+/// Returns the token the forms of a page have to be posted with, from the
+/// hidden `form_token` input of any pbinfo page
+pub fn form_token(html: &str) -> Result<String, ParseError> {
+    // we are looking for the token in a string that looks something
+    // like this:
+    // <input type="hidden" name="form_token" value="XXXXXX">
+    let marker = "name=\"form_token\"";
+    let (_, after) = html
+        .split_once(marker)
+        .ok_or(ParseError::MarkerNotFound { marker })?;
+
+    let value_marker = "value=\"";
+    let (_, value) = after
+        .split_once(value_marker)
+        .ok_or(ParseError::MarkerNotFound {
+            marker: value_marker,
+        })?;
+
+    Ok(value.chars().take_while(|&c| c != '"').collect())
+}
+
+/// Returns the encoded name of the form field sources have to be uploaded
+/// in, from the html of a problem page
+pub fn source_field(html: &str) -> Result<String, ParseError> {
+    // we are looking for the token in a string that looks something
+    // like this:
+    // {page html}
+    // $('#eedd451d5e1eb7dfd9c6e3a0e918f02cc2a87d03').val(Editor.getValue());
+    // {continuation page html}
+    let marker = ".val(Editor.getValue())";
+    let (before, _) = html
+        .split_once(marker)
+        .ok_or(ParseError::MarkerNotFound { marker })?;
+
+    let encoded_sursa_rev: String = before
+        .chars()
+        .rev()
+        .skip_while(|&c| c != '\'')
+        .skip(1)
+        .take_while(|&c| c != '#')
+        .collect();
+
+    Ok(encoded_sursa_rev.chars().rev().collect())
+}
+
+const SOLUTION_LIST_MARKER: &str = "\"surse\":";
+const SOLUTION_TOTAL_MARKER: &str = "\"numar_total_solutii\":";
+
+fn find(haystack: &[u8], needle: &str) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle.as_bytes())
+}
+
+/// Where a [SolutionListScanner] is, relative to the `surse` list
+#[derive(Debug, Default, PartialEq, Eq)]
+enum ListState {
+    #[default]
+    Before,
+    Inside,
+    After,
+}
+
+/// Incremental parser for the json of [crate::endpoints::SolutionList].
+///
+/// Hands out the solutions one at a time as the response is downloaded, so
+/// a list of hundreds of solutions never has to be in memory all at once
+#[derive(Debug, Default)]
+pub struct SolutionListScanner {
+    buffer: Vec<u8>,
+    /// Everything outside of the `surse` list, which is where the total is
+    outside: Vec<u8>,
+    state: ListState,
+}
+
+impl SolutionListScanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the next chunk of the response, returns the solutions that
+    /// were completed by it
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<models::SolutionEntry>, ParseError> {
+        self.buffer.extend_from_slice(chunk);
+        let mut entries = Vec::new();
+
+        loop {
+            match self.state {
+                ListState::Before => {
+                    let Some(start) = find(&self.buffer, SOLUTION_LIST_MARKER) else {
+                        break;
+                    };
+                    let after = start + SOLUTION_LIST_MARKER.len();
+                    let Some(open) = self.buffer[after..]
+                        .iter()
+                        .position(|b| !b.is_ascii_whitespace())
+                    else {
+                        break;
+                    };
+                    if self.buffer[after + open] != b'[' {
+                        return Err(self.json_error("surse wasn't a list"));
+                    }
+                    self.outside.extend(self.buffer.drain(..after + open + 1));
+                    self.state = ListState::Inside;
+                }
+                ListState::Inside => {
+                    let Some(next) = self
+                        .buffer
+                        .iter()
+                        .position(|&b| !b.is_ascii_whitespace() && b != b',')
+                    else {
+                        self.buffer.clear();
+                        break;
+                    };
+                    self.buffer.drain(..next);
+
+                    match self.buffer[0] {
+                        b']' => self.state = ListState::After,
+                        b'{' => {
+                            let Some(len) = object_len(&self.buffer) else {
+                                break;
+                            };
+                            let object: Vec<u8> = self.buffer.drain(..len).collect();
+                            let json = String::from_utf8_lossy(&object);
+                            entries.push(models::from_json(&json).map_err(|err| {
+                                ParseError::JsonError {
+                                    json: json.to_string(),
+                                    err: err.to_string(),
+                                }
+                            })?);
+                        }
+                        _ => return Err(self.json_error("a solution wasn't an object")),
+                    }
+                }
+                ListState::After => {
+                    self.outside.append(&mut self.buffer);
+                    break;
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Call once the response ended, returns how many solutions the user
+    /// sent in total (`numar_total_solutii`)
+    pub fn finish(mut self) -> Result<i64, ParseError> {
+        self.outside.append(&mut self.buffer);
+
+        let start =
+            find(&self.outside, SOLUTION_TOTAL_MARKER).ok_or(ParseError::MarkerNotFound {
+                marker: SOLUTION_TOTAL_MARKER,
+            })? + SOLUTION_TOTAL_MARKER.len();
+        let total: String = String::from_utf8_lossy(&self.outside[start..])
+            .trim_start()
+            .chars()
+            .take_while(|&c| c == '-' || c.is_ascii_digit())
+            .collect();
+
+        total
+            .parse()
+            .map_err(|_| self.json_error("numar_total_solutii wasn't a number"))
+    }
+
+    fn json_error(&self, err: &str) -> ParseError {
+        ParseError::JsonError {
+            json: String::from_utf8_lossy(&[self.outside.as_slice(), &self.buffer].concat())
+                .into_owned(),
+            err: err.to_string(),
+        }
+    }
+}
+
+/// Returns the text of every `<pre>` block of a problem page, which is
+/// where the statement keeps its examples (input and output files)
+pub fn problem_examples(html: &str) -> Vec<String> {
+    html.split("<pre")
+        .skip(1)
+        .filter_map(|block| {
+            let (_, block) = block.split_once('>')?;
+            let (block, _) = block.split_once("</pre>")?;
+            Some(strip_tags(block))
+        })
+        .collect()
+}
+
+/// Pairs up the `<pre>` blocks of `html` into examples, the input file
+/// always comes right before the output file
+pub fn examples(html: &str) -> Vec<Example> {
+    problem_examples(html)
+        .chunks_exact(2)
+        .map(|pair| Example {
+            input: pair[0].trim().to_string(),
+            output: pair[1].trim().to_string(),
+        })
+        .collect()
+}
+
+const STATEMENT_MARKER: &str = "id=\"enunt\"";
+
+/// Parses the page of a problem into its statement. The statement is split
+/// on its headings (`Cerința`, `Date de intrare`, `Date de ieșire`,
+/// `Restricții și precizări` and `Exemplu`), missing ones are left empty
+pub fn problem(problem_id: &str, html: &str) -> Result<Problem, ParseError> {
+    let (_, statement) = html
+        .split_once(STATEMENT_MARKER)
+        .ok_or(ParseError::MarkerNotFound {
+            marker: STATEMENT_MARKER,
+        })?;
+    let statement = statement.split_once('>').map_or("", |(_, html)| html);
+    let statement = statement
+        .split_once("</article>")
+        .map_or(statement, |(html, _)| html);
+
+    let mut problem = Problem {
+        id: problem_id.to_string(),
+        title: problem_title(html).ok_or(ParseError::MarkerNotFound { marker: "<title>" })?,
+        statement: String::new(),
+        input_format: String::new(),
+        output_format: String::new(),
+        constraints: String::new(),
+        examples: Vec::new(),
+        html: statement.trim().to_string(),
+    };
+    for (heading, content) in sections(statement) {
+        let heading = plain(&heading);
+        if heading.starts_with("cerint") {
+            problem.statement = block_text(content);
+        } else if heading.starts_with("date de intrare") {
+            problem.input_format = block_text(content);
+        } else if heading.starts_with("date de iesire") {
+            problem.output_format = block_text(content);
+        } else if heading.starts_with("restrict") {
+            problem.constraints = block_text(content);
+        } else if heading.starts_with("exempl") {
+            problem.examples.extend(examples(content));
+        }
+    }
+    if problem.examples.is_empty() {
+        problem.examples = examples(statement);
+    }
+    Ok(problem)
+}
+
+const META_MARKER: &str = "Clasa";
+
+/// Parses the table above the statement of a problem page, the one with the
+/// grade, the category, the difficulty and who posted the problem
+pub fn problem_meta(problem_id: &str, html: &str) -> Result<ProblemMeta, ParseError> {
+    let header = html
+        .split_once(STATEMENT_MARKER)
+        .map_or(html, |(header, _)| header);
+
+    let mut meta = ProblemMeta {
+        id: problem_id.to_string(),
+        grade: None,
+        category: None,
+        difficulty: None,
+        author: None,
+        posted_by: None,
+        posted_on: None,
+    };
+    let mut found = false;
+    for table in header.split("<table").skip(1) {
+        let table = table
+            .split_once("</table>")
+            .map_or(table, |(table, _)| table);
+        let labels = cells(table, "th");
+        let values = cells(table, "td");
+        for (label, value) in labels.iter().zip(values) {
+            let label = plain(label);
+            if value.is_empty() {
+                continue;
+            }
+            if label.starts_with("clasa") {
+                found = true;
+                meta.grade = first_number(&value);
+            } else if label.starts_with("categori") || label.starts_with("capitol") {
+                meta.category = Some(value);
+            } else if label.starts_with("dificultate") {
+                meta.difficulty = Difficulty::from_label(&value);
+            } else if label.starts_with("autor") {
+                meta.author = Some(value);
+            } else if label.starts_with("postata la") || label.starts_with("data") {
+                meta.posted_on = Some(value);
+            } else if label.starts_with("postat") {
+                meta.posted_by = Some(value);
+            }
+        }
+    }
+
+    if !found {
+        return Err(ParseError::MarkerNotFound {
+            marker: META_MARKER,
+        });
+    }
+    Ok(meta)
+}
+
+const PROBLEM_LINK_MARKER: &str = "href=\"/probleme/";
+
+/// Parses the problems out of a page that lists them in a table, like the
+/// search results. The grade and the difficulty are read from the columns
+/// named `Clasa` and `Dificultate`
+pub fn problem_list(html: &str) -> Vec<ProblemSummary> {
+    let mut problems = Vec::new();
+    for table in html.split("<table").skip(1) {
+        let table = table
+            .split_once("</table>")
+            .map_or(table, |(table, _)| table);
+        let labels: Vec<String> = cells(table, "th")
+            .iter()
+            .map(|label| plain(label))
+            .collect();
+        let column = |name: &str| labels.iter().position(|label| label.starts_with(name));
+        let (grade, difficulty) = (column("clasa"), column("dificultate"));
+
+        for row in table.split("<tr").skip(1) {
+            let Some((_, link)) = row.split_once(PROBLEM_LINK_MARKER) else {
+                continue;
+            };
+            let id: String = link.chars().take_while(char::is_ascii_digit).collect();
+            let Some((_, name)) = link.split_once('>') else {
+                continue;
+            };
+            let name = name.split_once("</a>").map_or(name, |(name, _)| name);
+            if id.is_empty() {
+                continue;
+            }
+
+            let values = cells(row, "td");
+            let value = |column: Option<usize>| column.and_then(|column| values.get(column));
+            problems.push(ProblemSummary {
+                id,
+                name: block_text(name),
+                grade: value(grade).and_then(|grade| first_number(grade)),
+                difficulty: value(difficulty).and_then(|label| Difficulty::from_label(label)),
+            });
+        }
+    }
+    problems
+}
+
+/// How many problems a listing says it has in total, from a text like
+/// `123 probleme`
+pub fn problem_count(html: &str) -> Option<u64> {
+    let text = strip_tags(html);
+    text.match_indices(" probleme").find_map(|(end, _)| {
+        let count: String = text[..end]
+            .chars()
+            .rev()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        count.chars().rev().collect::<String>().parse().ok()
+    })
+}
+
+/// Parses the profile page of a user. The values are found after their
+/// labels (`Școala`, `Județ`, `Probleme rezolvate`, `Puncte`), whether they
+/// are on the same line after a `:` or on the next line
+pub fn profile(username: &str, html: &str) -> UserProfile {
+    let body = html
+        .split_once("<body")
+        .and_then(|(_, body)| body.split_once('>'))
+        .map_or(html, |(_, body)| body);
+    let lines: Vec<String> = block_text(
+        &body
+            .replace("</td>", "</td>\n")
+            .replace("</div>", "</div>\n"),
+    )
+    .lines()
+    .map(|line| line.to_string())
+    .collect();
+    let value = |labels: &[&str]| {
+        lines.iter().enumerate().find_map(|(i, line)| {
+            let plain_line = plain(line);
+            if !labels.iter().any(|label| plain_line.starts_with(label)) {
+                return None;
+            }
+            match line.split_once(':') {
+                Some((_, value)) if !value.trim().is_empty() => Some(value.trim().to_string()),
+                _ => lines.get(i + 1).cloned(),
+            }
+        })
+    };
+
+    let display_name = body
+        .split_once("<h1")
+        .and_then(|(_, heading)| heading.split_once('>'))
+        .and_then(|(_, heading)| heading.split_once("</h1>"))
+        .map(|(heading, _)| block_text(heading).replace('\n', " "))
+        .filter(|name| !name.is_empty());
+    UserProfile {
+        username: username.to_string(),
+        display_name,
+        school: value(&["scoala", "unitatea"]),
+        county: value(&["judet"]),
+        solved_problems: value(&["probleme rezolvate"]).and_then(|count| first_number(&count)),
+        success_points: value(&["puncte"]).and_then(|points| first_number(&points)),
+    }
+}
+
+/// Parses the solved problems page of a user into the ids of the problems
+/// with 100 points and the ids of the ones only attempted. pbinfo marks the
+/// links to the solved ones with a `success` class
+pub fn solved_problems(html: &str) -> ProblemProgress {
+    let mut progress = ProblemProgress::default();
+    for tag in html.split("<a ").skip(1) {
+        let Some((tag, _)) = tag.split_once('>') else {
+            continue;
+        };
+        let Some((_, link)) = tag.split_once(PROBLEM_LINK_MARKER) else {
+            continue;
+        };
+        let id: String = link.chars().take_while(char::is_ascii_digit).collect();
+        if id.is_empty() || progress.solved.contains(&id) || progress.attempted.contains(&id) {
+            continue;
+        }
+        let solved = attribute(tag, "class").is_some_and(|class| class.contains("success"));
+        if solved {
+            progress.solved.push(id);
+        } else {
+            progress.attempted.push(id);
+        }
+    }
+    progress
+}
+
+/// Reads the progress bars of a profile page. Every bar has a label (a
+/// grade like `Clasa a IX-a` or a chapter) followed by a count like
+/// `52 / 312` or `52 din 312`
+pub fn progress_summary(html: &str) -> ProgressSummary {
+    let body = html
+        .split_once("<body")
+        .and_then(|(_, body)| body.split_once('>'))
+        .map_or(html, |(_, body)| body);
+    let text = block_text(
+        &body
+            .replace("</td>", "</td>\n")
+            .replace("</div>", "</div>\n")
+            .replace("</span>", "</span>\n"),
+    );
+
+    let mut summary = ProgressSummary::default();
+    let mut label: Option<&str> = None;
+    for line in text.lines() {
+        let count = line
+            .split_once('/')
+            .or_else(|| line.split_once(" din "))
+            .and_then(|(solved, total)| {
+                let solved = solved.trim().rsplit(' ').next()?.parse().ok()?;
+                let total = total.trim().split(' ').next()?.parse().ok()?;
+                Some((solved, total))
+            });
+        match (count, label) {
+            (Some((solved, total)), Some(name)) => {
+                summary.categories.push(CategoryProgress {
+                    name: name.to_string(),
+                    grade: grade(name),
+                    solved,
+                    total,
+                });
+                label = None;
+            }
+            (Some(_), None) => (),
+            (None, _) if line.ends_with('%') => (),
+            (None, _) => label = Some(line),
+        }
+    }
+    summary
+}
+
+/// The grade in a label like `Clasa a 9-a` or `Clasa a IX-a`
+fn grade(label: &str) -> Option<u8> {
+    let label = plain(label);
+    let grade = label.strip_prefix("clasa")?.trim().trim_start_matches("a ");
+    let grade = grade.split(['-', ' ']).next()?;
+    grade.parse().ok().or(match grade {
+        "v" => Some(5),
+        "vi" => Some(6),
+        "vii" => Some(7),
+        "viii" => Some(8),
+        "ix" => Some(9),
+        "x" => Some(10),
+        "xi" => Some(11),
+        "xii" => Some(12),
+        _ => None,
+    })
+}
+
+const PROFILE_LINK_MARKER: &str = "href=\"/profil/";
+
+/// Parses the users out of a ranking page. The columns are found by their
+/// names (`Loc`, `Nume`, `Școala`, `Probleme`, `Puncte`) and the username
+/// out of the link to the profile
+pub fn ranking(html: &str) -> Vec<RankingEntry> {
+    let mut entries = Vec::new();
+    for table in html.split("<table").skip(1) {
+        let table = table
+            .split_once("</table>")
+            .map_or(table, |(table, _)| table);
+        let labels: Vec<String> = cells(table, "th")
+            .iter()
+            .map(|label| plain(label))
+            .collect();
+        let column = |names: &[&str]| {
+            labels
+                .iter()
+                .position(|label| names.iter().any(|name| label.starts_with(name)))
+        };
+        let position = column(&["loc", "#", "pozitie"]);
+        let name = column(&["nume", "utilizator"]);
+        let school = column(&["scoala", "unitate"]);
+        let solved = column(&["probleme", "rezolvate"]);
+        let points = column(&["puncte", "scor"]);
+        if name.is_none() {
+            continue;
+        }
+
+        for row in table.split("<tr").skip(1) {
+            let values = cells(row, "td");
+            if values.is_empty() {
+                continue;
+            }
+            let value = |column: Option<usize>| {
+                column
+                    .and_then(|column| values.get(column))
+                    .filter(|value| !value.is_empty())
+            };
+            let username = row.split_once(PROFILE_LINK_MARKER).map(|(_, link)| {
+                link.chars()
+                    .take_while(|&c| c != '"' && c != '/' && c != '?')
+                    .collect::<String>()
+            });
+            entries.push(RankingEntry {
+                position: value(position)
+                    .and_then(|position| first_number(position))
+                    .unwrap_or(entries.len() as u32 + 1),
+                name: value(name).cloned().unwrap_or_default(),
+                username,
+                school: value(school).cloned(),
+                solved: value(solved).and_then(|solved| first_number(solved)),
+                points: value(points).and_then(|points| first_number(points)),
+            });
+        }
+    }
+    entries
+}
+
+const ACHIEVEMENT_MARKER: &str = "class=\"succes";
+
+/// Parses the badges out of an achievements page. Every badge is an element
+/// with a `succes` class that has its name in a heading (or `<strong>`, or
+/// the `title` of its image), the description and the day it was earned
+pub fn achievements(html: &str) -> Vec<Achievement> {
+    html.split(ACHIEVEMENT_MARKER)
+        .skip(1)
+        .filter_map(|block| {
+            let (_, block) = block.split_once('>')?;
+            // the description is what comes after the heading with the name
+            let (name, rest) = ["<h4", "<h5", "<h3", "<strong", "<b>"]
+                .iter()
+                .find_map(|tag| {
+                    let (_, name) = block.split_once(tag)?;
+                    let (_, name) = name.split_once('>')?;
+                    let (name, rest) = name.split_once("</")?;
+                    let (_, rest) = rest.split_once('>')?;
+                    Some((block_text(name), rest))
+                })
+                .or_else(|| {
+                    let (_, image) = block.split_once("<img")?;
+                    let name = attribute(image, "title").or_else(|| attribute(image, "alt"))?;
+                    Some((decode_entities(name), block))
+                })
+                .filter(|(name, _)| !name.is_empty())?;
+
+            let text = block_text(rest);
+            let earned_on = text
+                .split_whitespace()
+                .find(|word| looks_like_date(word))
+                .map(|date| date.to_string());
+            let description = text
+                .lines()
+                .filter(|line| {
+                    *line != name && earned_on.as_deref().is_none_or(|date| !line.contains(date))
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            Some(Achievement {
+                name,
+                description,
+                earned_on,
+            })
+        })
+        .collect()
+}
+
+const CLASS_LINK_MARKER: &str = "id_clasa=";
+const ASSIGNMENT_LINK_MARKER: &str = "id_tema=";
+
+/// The id in a link like `?pagina=clasa&id_clasa=12` and the text of the
+/// link, for every link with `marker` in it
+fn id_links<'a>(html: &'a str, marker: &str) -> impl Iterator<Item = (String, String)> + 'a {
+    let marker = marker.to_string();
+    html.split("<a ").skip(1).filter_map(move |tag| {
+        let (attributes, text) = tag.split_once('>')?;
+        let (_, id) = attributes.split_once(marker.as_str())?;
+        let id: String = id.chars().take_while(char::is_ascii_digit).collect();
+        let text = text.split_once("</a>").map_or(text, |(text, _)| text);
+        let text = block_text(text).replace('\n', " ");
+        (!id.is_empty() && !text.is_empty()).then_some((id, text))
+    })
+}
+
+/// Parses the classes out of the classes page, from the links to them
+pub fn classes(html: &str) -> Vec<Class> {
+    let mut classes: Vec<Class> = Vec::new();
+    for (id, name) in id_links(html, CLASS_LINK_MARKER) {
+        if !classes.iter().any(|class| class.id == id) {
+            classes.push(Class { id, name });
+        }
+    }
+    classes
+}
+
+/// Parses the assignments out of the page of a class. Every row with a link
+/// to an assignment is one, the deadline is read from the column named
+/// `Termen`
+pub fn assignments(class_id: &str, html: &str) -> Vec<AssignmentSummary> {
+    let mut assignments: Vec<AssignmentSummary> = Vec::new();
+    for table in html.split("<table").skip(1) {
+        let table = table
+            .split_once("</table>")
+            .map_or(table, |(table, _)| table);
+        let labels: Vec<String> = cells(table, "th")
+            .iter()
+            .map(|label| plain(label))
+            .collect();
+        let deadline = labels.iter().position(|label| label.starts_with("termen"));
+
+        for row in table.split("<tr").skip(1) {
+            let Some((id, title)) = id_links(row, ASSIGNMENT_LINK_MARKER).next() else {
+                continue;
+            };
+            if assignments.iter().any(|assignment| assignment.id == id) {
+                continue;
+            }
+            let values = cells(row, "td");
+            assignments.push(AssignmentSummary {
+                id,
+                class_id: class_id.to_string(),
+                title,
+                deadline: deadline
+                    .and_then(|deadline| values.get(deadline))
+                    .filter(|deadline| !deadline.is_empty())
+                    .cloned(),
+            });
+        }
+    }
+    assignments
+}
+
+/// Parses the page of an assignment. The title is the first heading, the
+/// deadline is found after the `Termen` label and the problems are read
+/// like in [problem_list]
+pub fn assignment(assignment_id: &str, html: &str) -> Assignment {
+    let title = html
+        .split_once("<h1")
+        .and_then(|(_, heading)| heading.split_once('>'))
+        .and_then(|(_, heading)| heading.split_once("</h1>"))
+        .map(|(heading, _)| block_text(heading).replace('\n', " "))
+        .unwrap_or_default();
+    let text = block_text(
+        &html
+            .replace("</div>", "</div>\n")
+            .replace("</h1>", "</h1>\n"),
+    );
+    let deadline = text.lines().find_map(|line| {
+        let (label, value) = line.split_once(':')?;
+        let value = value.trim();
+        (plain(label).starts_with("termen") && !value.is_empty()).then(|| value.to_string())
+    });
+    Assignment {
+        id: assignment_id.to_string(),
+        title,
+        deadline,
+        problems: problem_list(html),
+    }
+}
+
+/// Whether `word` is a day like `15.03.2024`, `15/03/2024` or `2024-03-15`
+fn looks_like_date(word: &str) -> bool {
+    let parts: Vec<&str> = word.split(['.', '/', '-']).collect();
+    let digits = |part: &str, len: std::ops::RangeInclusive<usize>| {
+        len.contains(&part.len()) && part.chars().all(|c| c.is_ascii_digit())
+    };
+    match parts.as_slice() {
+        [year, month, day] if year.len() == 4 => {
+            digits(year, 4..=4) && digits(month, 1..=2) && digits(day, 1..=2)
+        }
+        [day, month, year] => digits(day, 1..=2) && digits(month, 1..=2) && digits(year, 4..=4),
+        _ => false,
+    }
+}
+
+/// The first number in `text`, for cells like `9` or `clasa a 9-a`
+fn first_number<T: std::str::FromStr>(text: &str) -> Option<T> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .find(|number| !number.is_empty())?
+        .parse()
+        .ok()
+}
+
+/// The text of every `<tag>` cell of a table
+fn cells(table: &str, tag: &str) -> Vec<String> {
+    let close = format!("</{tag}>");
+    table
+        .split(&format!("<{tag}"))
+        .skip(1)
+        .filter(|cell| cell.starts_with(|c: char| c == '>' || c.is_whitespace()))
+        .filter_map(|cell| {
+            let (_, cell) = cell.split_once('>')?;
+            let cell = cell.split_once(&close).map_or(cell, |(cell, _)| cell);
+            Some(block_text(cell).replace('\n', " "))
+        })
+        .collect()
+}
+
+/// The name of the problem, from the heading of the page or else from the
+/// `<title>`
+fn problem_title(html: &str) -> Option<String> {
+    if let Some((_, heading)) = html.split_once("<h1 class=\"text-primary\"") {
+        let (heading, _) = heading.split_once("</h1>")?;
+        let (_, heading) = heading.split_once('>')?;
+        return Some(block_text(heading));
+    }
+    let (_, title) = html.split_once("<title>")?;
+    let (title, _) = title.split_once("</title>")?;
+    let title = strip_tags(title);
+    Some(
+        title
+            .split(" | ")
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    )
+}
+
+/// Splits `html` on its `<h1>`, `<h2>` and `<h3>` headings into the text of
+/// every heading and the html up to the next one
+fn sections(html: &str) -> Vec<(String, &str)> {
+    let mut headings = Vec::new();
+    let mut from = 0;
+    while let Some(start) = html[from..].find("<h").map(|start| from + start) {
+        from = start + 2;
+        let level = match html.as_bytes().get(start + 2) {
+            Some(level @ b'1'..=b'3') => *level as char,
+            _ => continue,
+        };
+        let close = format!("</h{level}>");
+        let Some(end) = html[start..]
+            .find(&close)
+            .map(|end| start + end + close.len())
+        else {
+            continue;
+        };
+        headings.push((start, end, block_text(&html[start..end])));
+        from = end;
+    }
+
+    (0..headings.len())
+        .map(|i| {
+            let (_, end, _) = headings[i];
+            let next = headings.get(i + 1).map_or(html.len(), |heading| heading.0);
+            (headings[i].2.clone(), &html[end..next])
+        })
+        .collect()
+}
+
+/// Lowercases `text` and drops the romanian diacritics, for comparing headings
+fn plain(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'ă' | 'â' => 'a',
+            'î' => 'i',
+            'ș' | 'ş' => 's',
+            'ț' | 'ţ' => 't',
+            c => c,
+        })
+        .collect()
+}
+
+/// The text of a piece of html, one line for every paragraph or line break
+fn block_text(html: &str) -> String {
+    let html = html
+        .replace("<br>", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+        .replace("</p>", "</p>\n")
+        .replace("</li>", "</li>\n");
+    strip_tags(&html)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Removes the html tags out of `html` and decodes the common entities
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+    decode_entities(&text)
+}
+
+/// Decodes the common html entities
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// The value of the attribute `name` of an html tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let (_, value) = tag.split_once(&format!("{name}=\""))?;
+    value.split_once('"').map(|(value, _)| value)
+}
+
+/// The url an attribute of a tag points to, with the links relative to
+/// pbinfo made absolute
+fn link(tag: &str, name: &str) -> String {
+    match attribute(tag, name).unwrap_or_default() {
+        link if link.starts_with('/') && !link.starts_with("//") => {
+            format!("{}{link}", crate::endpoints::BASE_URL)
+        }
+        link => link.to_string(),
+    }
+}
+
+/// Makes sure `markdown` ends with an empty line, unless it is empty
+fn blank_line(markdown: &mut String) {
+    while markdown.ends_with(' ') {
+        markdown.pop();
+    }
+    if !markdown.is_empty() {
+        while !markdown.ends_with("\n\n") {
+            markdown.push('\n');
+        }
+    }
+}
+
+/// Converts the html of a statement into markdown. Headings become `##`,
+/// `<pre>` blocks become fenced code blocks and the tags markdown has no
+/// syntax for (like `<sub>`) are kept as html
+pub fn markdown(html: &str) -> String {
+    let mut markdown = String::with_capacity(html.len());
+    let mut lists: Vec<bool> = Vec::new();
+    let mut links: Vec<String> = Vec::new();
+    let mut in_pre = false;
+    let mut skipping: Option<String> = None;
+
+    let mut rest = html;
+    while !rest.is_empty() {
+        let (text, after) = rest.split_once('<').unwrap_or((rest, ""));
+        if skipping.is_none() {
+            let text = decode_entities(text);
+            if in_pre {
+                markdown.push_str(&text);
+            } else {
+                // like in a browser, every run of whitespace is a single space
+                let mut space = false;
+                for c in text.chars() {
+                    if c.is_whitespace() {
+                        space = true;
+                        continue;
+                    }
+                    if space && !(markdown.is_empty() || markdown.ends_with([' ', '\n'])) {
+                        markdown.push(' ');
+                    }
+                    space = false;
+                    markdown.push(c);
+                }
+                if space && !(markdown.is_empty() || markdown.ends_with([' ', '\n'])) {
+                    markdown.push(' ');
+                }
+            }
+        }
+        let Some((tag, after)) = after.split_once('>') else {
+            break;
+        };
+        rest = after;
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if let Some(skipped) = &skipping {
+            if closing && *skipped == name {
+                skipping = None;
+            }
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            ("script" | "style", false) => skipping = Some(name),
+            ("p" | "div" | "table", _) => blank_line(&mut markdown),
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                blank_line(&mut markdown);
+                let level = name[1..].parse::<usize>().unwrap_or(1).clamp(1, 5);
+                markdown.push_str(&"#".repeat(level + 1));
+                markdown.push(' ');
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => blank_line(&mut markdown),
+            ("br", _) => markdown.push('\n'),
+            ("strong" | "b", _) => markdown.push_str("**"),
+            ("em" | "i", _) => markdown.push('*'),
+            ("code", _) if !in_pre => markdown.push('`'),
+            ("pre", false) => {
+                blank_line(&mut markdown);
+                markdown.push_str("```\n");
+                in_pre = true;
+            }
+            ("pre", true) => {
+                if !markdown.ends_with('\n') {
+                    markdown.push('\n');
+                }
+                markdown.push_str("```");
+                blank_line(&mut markdown);
+                in_pre = false;
+            }
+            ("ul" | "ol", false) => {
+                if lists.is_empty() {
+                    blank_line(&mut markdown);
+                }
+                lists.push(name == "ol");
+            }
+            ("ul" | "ol", true) => {
+                lists.pop();
+                if lists.is_empty() {
+                    blank_line(&mut markdown);
+                }
+            }
+            ("li", false) => {
+                if !markdown.is_empty() && !markdown.ends_with('\n') {
+                    markdown.push('\n');
+                }
+                markdown.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                markdown.push_str(if lists.last() == Some(&true) {
+                    "1. "
+                } else {
+                    "- "
+                });
+            }
+            ("a", false) => {
+                links.push(link(tag, "href"));
+                markdown.push('[');
+            }
+            ("a", true) => {
+                let href = links.pop().unwrap_or_default();
+                markdown.push_str(&format!("]({href})"));
+            }
+            ("img", _) => markdown.push_str(&format!(
+                "![{}]({})",
+                attribute(tag, "alt").unwrap_or_default(),
+                link(tag, "src")
+            )),
+            ("tr", true) => markdown.push('\n'),
+            ("td" | "th", true) => markdown.push_str(" | "),
+            ("sub" | "sup", _) => markdown.push_str(&format!("<{tag}>")),
+            _ => (),
+        }
+    }
+
+    let mut markdown = markdown
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n");
+    while markdown.contains("\n\n\n") {
+        markdown = markdown.replace("\n\n\n", "\n\n");
+    }
+    markdown.trim().to_string()
+}
+
+/// Interprets the json of the evaluation details of a solution
+pub fn score_status(json: &str) -> Result<ScoreStatus, ParseError> {
+    let table: Value = serde_json::from_str(json).map_err(|err| ParseError::JsonError {
+        json: json.to_string(),
+        err: err.to_string(),
+    })?;
+
+    if table["status_sursa"] == "executing" || table["status_sursa"] == "pending" {
+        return Ok(ScoreStatus::StillExecuting);
+    }
+
+    let report = score_report(&table)?;
+    let evaluation = Box::new(Evaluation { report, raw: table });
+    if evaluation.report.outcome() == models::EvaluationOutcome::CompileError {
+        return Ok(ScoreStatus::CompileError {
+            message: evaluation
+                .report
+                .mesaj_compilare
+                .clone()
+                .unwrap_or_default(),
+            evaluation,
+        });
+    }
+
+    Ok(ScoreStatus::DoneExecuting { evaluation })
+}
+
+/// Types the evaluation details of a solution, filling in the compiler
+/// warnings
+pub fn score_report(table: &Value) -> Result<models::ScoreReport, ParseError> {
+    let json = table.to_string();
+    let mut report: models::ScoreReport =
+        models::from_json(&json).map_err(|err| ParseError::JsonError {
+            err: err.to_string(),
+            json,
+        })?;
+    if let Some(compiler_output) = &report.mesaj_compilare {
+        report.compiler_warnings = compiler_warnings(compiler_output);
+    }
+    Ok(report)
+}
+
+/// Returns the warnings out of the compiler output of an evaluation, one
+/// per `file:line:col: warning: ...` line
+pub fn compiler_warnings(compiler_output: &str) -> Vec<String> {
+    compiler_output
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| line.contains("warning:"))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// The months as pbinfo writes them in the long dates, like `15 martie 2024`
+#[cfg(feature = "chrono")]
+const MONTHS: [&str; 12] = [
+    "ianuarie",
+    "februarie",
+    "martie",
+    "aprilie",
+    "mai",
+    "iunie",
+    "iulie",
+    "august",
+    "septembrie",
+    "octombrie",
+    "noiembrie",
+    "decembrie",
+];
+
+/// Parses a day written as `2024-03-15`, `15.03.2024`, `15/03/2024` or
+/// `15 martie 2024`
+#[cfg(feature = "chrono")]
+pub(crate) fn date(date: &str) -> Option<chrono::NaiveDate> {
+    use chrono::NaiveDate;
+
+    let date = date.trim();
+    for format in ["%Y-%m-%d", "%d.%m.%Y", "%d/%m/%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(date, format) {
+            return Some(date);
+        }
+    }
+
+    let mut parts = date.split_whitespace();
+    let day = parts.next()?.parse().ok()?;
+    let month = parts.next()?.to_lowercase();
+    let month = MONTHS.iter().position(|name| *name == month)? as u32 + 1;
+    let year = parts.next()?.parse().ok()?;
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// The offset of Romania (EET, EEST in the summer) at a local time. The
+/// summer time starts on the last sunday of march at 03:00 and ends on the
+/// last sunday of october at 04:00
+#[cfg(feature = "chrono")]
+fn romanian_offset(local: chrono::NaiveDateTime) -> chrono::FixedOffset {
+    use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+
+    let last_sunday = |month: u32| {
+        let mut day = NaiveDate::from_ymd_opt(local.year(), month, 31)
+            .expect("march and october have 31 days");
+        while day.weekday() != Weekday::Sun {
+            day = day.pred_opt().expect("not the first day ever");
+        }
+        day
+    };
+    let summer_start = last_sunday(3).and_time(NaiveTime::from_hms_opt(3, 0, 0).unwrap());
+    let summer_end = last_sunday(10).and_time(NaiveTime::from_hms_opt(4, 0, 0).unwrap());
+
+    let hours = if local >= summer_start && local < summer_end {
+        3
+    } else {
+        2
+    };
+    chrono::FixedOffset::east_opt(hours * 3600).expect("the offset is in range")
+}
+
+/// Parses the day and the time of day pbinfo shows for an upload into a
+/// date with the offset of Romania (where the times of pbinfo are from)
+#[cfg(feature = "chrono")]
+pub fn upload_time(
+    day: &str,
+    time: Option<&str>,
+) -> Result<chrono::DateTime<chrono::FixedOffset>, ParseError> {
+    use chrono::{NaiveTime, TimeZone};
+
+    let error = || ParseError::DateError {
+        date: format!("{day} {}", time.unwrap_or_default())
+            .trim()
+            .to_string(),
+    };
+
+    let day = date(day).ok_or_else(error)?;
+    let time = match time.map(str::trim) {
+        Some(time) => NaiveTime::parse_from_str(time, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+            .map_err(|_| error())?,
+        None => NaiveTime::MIN,
+    };
+    let local = day.and_time(time);
+
+    romanian_offset(local)
+        .from_local_datetime(&local)
+        .single()
+        .ok_or_else(error)
+}
+
+/// Returns how long pbinfo asks to wait in a message like `Mai așteaptă 25
+/// de secunde`, `None` if the message has no duration in it. The duration
+/// is the number right before the first unit, other numbers like the count
+/// of sources in `Ai trimis 3 soluții` are skipped
+pub fn retry_after(message: &str) -> Option<std::time::Duration> {
+    let message = message.to_lowercase();
+    let (unit_start, unit_secs) = [("minut", 60), ("secund", 1), ("second", 1)]
+        .into_iter()
+        .filter_map(|(unit, secs)| Some((message.find(unit)?, secs)))
+        .min_by_key(|&(unit_start, _)| unit_start)?;
+
+    let before = message[..unit_start].trim_end();
+    let before = before.strip_suffix(" de").unwrap_or(before).trim_end();
+    let amount = before.trim_end_matches(|c: char| c.is_ascii_digit());
+    let amount: u64 = before[amount.len()..].parse().ok()?;
+    amount
+        .checked_mul(unit_secs)
+        .map(std::time::Duration::from_secs)
+}
+
+/// Parses the json pbinfo answers with after an upload
+pub fn upload_response(json: &str) -> Result<models::UploadResponse, ParseError> {
+    models::from_json(json).map_err(|err| ParseError::JsonError {
+        json: json.to_string(),
+        err: err.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOGGED_IN: &str = include_str!("../tests/fixtures/homepage_logged_in.html");
+    const LOGGED_OUT: &str = include_str!("../tests/fixtures/homepage_logged_out.html");
+    const REORDERED_USER: &str = include_str!("../tests/fixtures/homepage_reordered_user.html");
+    const PROBLEM_PAGE: &str = include_str!("../tests/fixtures/problem_page.html");
+    const SCORE_DONE: &str = include_str!("../tests/fixtures/score_done.json");
+    const SCORE_COMPILE_ERROR: &str = include_str!("../tests/fixtures/score_compile_error.json");
+    const SCORE_PENDING: &str = include_str!("../tests/fixtures/score_pending.json");
+    const PROFILE: &str = include_str!("../tests/fixtures/profile.html");
+    const RANKING: &str = include_str!("../tests/fixtures/ranking.html");
+    const ACHIEVEMENTS: &str = include_str!("../tests/fixtures/achievements.html");
+    const CLASSES: &str = include_str!("../tests/fixtures/classes.html");
+    const CLASS_PAGE: &str = include_str!("../tests/fixtures/class_page.html");
+    const ASSIGNMENT: &str = include_str!("../tests/fixtures/assignment.html");
+    const SEARCH_RESULTS: &str = include_str!("../tests/fixtures/search_results.html");
+
+    /// Feeds `html` to a [PageUserScanner] `chunk_len` bytes at a time
+    fn scan(html: &str, chunk_len: usize) -> Result<PageUser, ParseError> {
+        let mut scanner = PageUserScanner::new();
+        for chunk in html.as_bytes().chunks(chunk_len) {
+            if let Some(user) = scanner.feed(chunk) {
+                return Ok(user);
+            }
+        }
+        scanner.finish()
+    }
+
+    #[test]
+    fn page_user_logged_in() {
+        let user = page_user(LOGGED_IN).unwrap();
+        assert_eq!(user.id, "123456");
+        assert_eq!(user.username.as_deref(), Some("ionpopescu"));
+        assert_eq!(user.display_name.as_deref(), Some("Ion Popescu"));
+    }
+
+    #[test]
+    fn page_user_logged_out() {
+        assert_eq!(page_user(LOGGED_OUT).unwrap().id, "0");
+        assert!(is_login_page(LOGGED_OUT));
+        assert!(!is_login_page(LOGGED_IN));
+    }
+
+    #[test]
+    fn page_user_reordered_object() {
+        let user = page_user(REORDERED_USER).unwrap();
+        assert_eq!(user.id, "98765");
+        assert_eq!(user.username.as_deref(), Some("mariaionescu"));
+        assert_eq!(user.display_name.as_deref(), Some("Maria D'Ionescu"));
+    }
+
+    #[test]
+    fn page_user_missing() {
+        assert!(page_user("<html><body>nimic</body></html>").is_err());
+    }
+
+    #[test]
+    fn scanner_matches_page_user_for_every_chunk_size() {
+        for html in [LOGGED_IN, LOGGED_OUT, REORDERED_USER] {
+            let expected = page_user(html).unwrap();
+            for chunk_len in [1, 2, 3, 7, 16, 64, 1024, html.len()] {
+                assert_eq!(
+                    scan(html, chunk_len).unwrap(),
+                    expected,
+                    "chunk_len {chunk_len}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn scanner_splits_the_marker() {
+        let split = LOGGED_IN.find(USER_MARKER).unwrap() + 5;
+        let (start, rest) = LOGGED_IN.as_bytes().split_at(split);
+        let mut scanner = PageUserScanner::new();
+        assert!(scanner.feed(start).is_none());
+        assert_eq!(scanner.feed(rest).unwrap().id, "123456");
+    }
+
+    #[test]
+    fn js_to_json_reads_js_objects() {
+        let json = js_to_json("{id: 5, user: 'a\\'b \"c\"', list: [1, true,],}");
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["id"], 5);
+        assert_eq!(value["user"], "a'b \"c\"");
+        assert_eq!(value["list"], serde_json::json!([1, true]));
+    }
+
+    #[test]
+    fn scan_id_falls_back_on_broken_objects() {
+        assert_eq!(
+            scan_id("{\"user\":\"x\", \"id\" : \"77\", bad: ...").as_deref(),
+            Some("77")
+        );
+        assert_eq!(scan_id("{\"id_user\":5, uid: 6}"), None);
+    }
+
+    #[test]
+    fn ssid_cookie_reads_the_value() {
+        assert_eq!(
+            ssid_cookie("SSID=abc123; path=/; HttpOnly").unwrap(),
+            "abc123"
+        );
+        assert_eq!(ssid_cookie("SSID=abc123").unwrap(), "abc123");
+        assert!(ssid_cookie("SSID; path=/").is_err());
+    }
+
+    #[test]
+    fn form_token_reads_the_hidden_input() {
+        assert_eq!(
+            form_token(LOGGED_IN).unwrap(),
+            "9f2c1e4b7a8d3f6e0c5b2a1d9e8f7c6b5a4d3e2f"
+        );
+        assert_eq!(
+            form_token(LOGGED_OUT).unwrap(),
+            "0a1b2c3d4e5f60718293a4b5c6d7e8f901234567"
+        );
+        assert!(form_token(PROBLEM_PAGE).is_err());
+    }
+
+    #[test]
+    fn source_field_reads_the_encoded_name() {
+        assert_eq!(
+            source_field(PROBLEM_PAGE).unwrap(),
+            "eedd451d5e1eb7dfd9c6e3a0e918f02cc2a87d03"
+        );
+        assert!(source_field(LOGGED_IN).is_err());
+    }
+
+    #[test]
+    fn problem_reads_the_statement() {
+        let parsed = problem("1", PROBLEM_PAGE).unwrap();
+        assert_eq!(parsed.id, "1");
+        assert_eq!(parsed.title, "sum");
+        assert!(parsed.statement.contains("Calculați suma lor"));
+        assert!(parsed.input_format.contains("citește de la tastatură"));
+        assert!(parsed.output_format.contains("suma celor două numere"));
+        assert!(parsed.constraints.contains("1.000.000.000"));
+        assert_eq!(
+            parsed.examples,
+            vec![Example {
+                input: "3 5".to_string(),
+                output: "8".to_string(),
+            }]
+        );
+        assert!(parsed.html.starts_with("<h1>Cerința</h1>"));
+        assert!(problem("1", LOGGED_IN).is_err());
+    }
+
+    #[test]
+    fn score_status_done() {
+        let ScoreStatus::DoneExecuting { evaluation } = score_status(SCORE_DONE).unwrap() else {
+            panic!("expected a finished evaluation");
+        };
+        assert_eq!(evaluation.report.score(), Some(60));
+        assert_eq!(evaluation.report.teste.len(), 5);
+        assert_eq!(evaluation.report.compiler_warnings.len(), 1);
+    }
+
+    #[test]
+    fn score_status_compile_error() {
+        let ScoreStatus::CompileError { message, .. } = score_status(SCORE_COMPILE_ERROR).unwrap()
+        else {
+            panic!("expected a compile error");
+        };
+        assert!(message.contains("'cout' was not declared"));
+    }
+
+    #[test]
+    fn score_status_pending() {
+        assert!(matches!(
+            score_status(SCORE_PENDING).unwrap(),
+            ScoreStatus::StillExecuting
+        ));
+        assert!(score_status("not json").is_err());
+    }
+
+    #[test]
+    fn retry_after_reads_the_number_before_the_unit() {
+        let secs = |message| retry_after(message).map(|wait| wait.as_secs());
+        assert_eq!(secs("Mai așteaptă 25 de secunde"), Some(25));
+        assert_eq!(
+            secs("Ai trimis 3 soluții, mai așteaptă 25 de secunde"),
+            Some(25)
+        );
+        assert_eq!(secs("Mai așteaptă 2 minute!"), Some(120));
+        assert_eq!(secs("Please wait 10 seconds"), Some(10));
+        assert_eq!(secs("Ai trimis 3 soluții, mai așteaptă puțin"), None);
+        assert_eq!(secs("Mai așteaptă câteva secunde"), None);
+        assert_eq!(secs("Mai așteaptă 99999999999999999999 de minute"), None);
+        assert_eq!(secs("Mai așteaptă 999999999999999999 de minute"), None);
+    }
+
+    #[test]
+    fn score_report_outcome() {
+        let report = |json: &str| score_report(&serde_json::from_str(json).unwrap()).unwrap();
+        let outcome = |json: &str| report(json).outcome();
+
+        assert_eq!(
+            outcome(SCORE_DONE),
+            models::EvaluationOutcome::PartialScore(60)
+        );
+        assert_eq!(
+            outcome(SCORE_COMPILE_ERROR),
+            models::EvaluationOutcome::CompileError
+        );
+        assert_eq!(outcome(SCORE_PENDING), models::EvaluationOutcome::Pending);
+        assert_eq!(
+            outcome(
+                r#"{"status_sursa":"done","scor":"100","teste":[{"scor":"100","mesaj":"OK"}]}"#
+            ),
+            models::EvaluationOutcome::Accepted
+        );
+        assert_eq!(
+            outcome(
+                r#"{"status_sursa":"done","scor":"0","teste":[{"scor":"0","mesaj":"Limita de timp depasita"}]}"#
+            ),
+            models::EvaluationOutcome::TimeLimit
+        );
+        assert_eq!(
+            outcome(
+                r#"{"status_sursa":"done","scor":"0","teste":[{"scor":"0","mesaj":"Killed by signal 11"}]}"#
+            ),
+            models::EvaluationOutcome::RuntimeError
+        );
+        assert_eq!(
+            outcome(r#"{"status_sursa":"done","teste":[]}"#),
+            models::EvaluationOutcome::InternalError
+        );
+    }
+
+    #[test]
+    fn compiler_warnings_keeps_only_the_warnings() {
+        let output = "main.cpp: In function 'int main()':\n\
+            \u{20}  main.cpp:5:9: warning: unused variable 'x' [-Wunused-variable]\n\
+            main.cpp:7:1: error: expected ';' before '}' token\n\
+            main.cpp:9:5: warning: no return statement [-Wreturn-type]";
+        assert_eq!(
+            compiler_warnings(output),
+            [
+                "main.cpp:5:9: warning: unused variable 'x' [-Wunused-variable]",
+                "main.cpp:9:5: warning: no return statement [-Wreturn-type]",
+            ]
+        );
+        assert!(compiler_warnings("").is_empty());
+    }
+
+    #[test]
+    fn markdown_converts_a_statement() {
+        let html = "<h1>Cerința</h1>\n<p>Se dau   <strong>două</strong> numere <em>a</em> și \
+            <code>b</code>, vezi <a href=\"/probleme/2\">diferenta</a>.</p>\n\
+            <ul><li>x<sub>1</sub> &lt; 10</li><li>pas</li></ul>\n\
+            <pre>3 5\n</pre><script>alert(1)</script><p>Gata<br>acum</p>";
+        assert_eq!(
+            markdown(html),
+            "## Cerința\n\n\
+             Se dau **două** numere *a* și `b`, vezi [diferenta](https://www.pbinfo.ro/probleme/2).\n\n\
+             - x<sub>1</sub> < 10\n\
+             - pas\n\n\
+             ```\n3 5\n```\n\n\
+             Gata\nacum"
+        );
+        assert_eq!(markdown(""), "");
+    }
+
+    #[test]
+    fn problem_meta_reads_the_table_above_the_statement() {
+        let meta = problem_meta("1", PROBLEM_PAGE).unwrap();
+        assert_eq!(meta.grade, Some(9));
+        assert_eq!(meta.category.as_deref(), Some("Operatori"));
+        assert_eq!(meta.difficulty, Some(Difficulty::Easy));
+        assert_eq!(meta.author.as_deref(), Some("Candale Silviu"));
+        assert_eq!(meta.posted_by, None);
+        assert!(problem_meta("1", LOGGED_IN).is_err());
+    }
+
+    #[test]
+    fn difficulty_from_label() {
+        assert_eq!(Difficulty::from_label("ușoară"), Some(Difficulty::Easy));
+        assert_eq!(Difficulty::from_label("uşoară"), Some(Difficulty::Easy));
+        assert_eq!(Difficulty::from_label(" Usoara "), Some(Difficulty::Easy));
+        assert_eq!(Difficulty::from_label("medie"), Some(Difficulty::Medium));
+        assert_eq!(Difficulty::from_label("dificilă"), Some(Difficulty::Hard));
+        assert_eq!(Difficulty::from_label("concurs"), Some(Difficulty::Contest));
+        assert_eq!(Difficulty::from_label("imposibilă"), None);
+    }
+
+    #[test]
+    fn problem_list_reads_the_columns_by_name() {
+        let problems = problem_list(SEARCH_RESULTS);
+        assert_eq!(
+            problems,
+            [
+                ProblemSummary {
+                    id: "1".to_string(),
+                    name: "sum".to_string(),
+                    grade: Some(9),
+                    difficulty: Some(Difficulty::Easy),
+                },
+                ProblemSummary {
+                    id: "2".to_string(),
+                    name: "diferenta & produs".to_string(),
+                    grade: Some(10),
+                    difficulty: Some(Difficulty::Hard),
+                },
+                ProblemSummary {
+                    id: "3".to_string(),
+                    name: "concurs".to_string(),
+                    grade: None,
+                    difficulty: Some(Difficulty::Contest),
+                },
+            ]
+        );
+        assert_eq!(problem_count(SEARCH_RESULTS), Some(3));
+        assert!(problem_list(LOGGED_IN).is_empty());
+    }
+
+    #[test]
+    fn profile_reads_the_labeled_values() {
+        let profile = profile("ionpopescu", PROFILE);
+        assert_eq!(profile.username, "ionpopescu");
+        assert_eq!(profile.display_name.as_deref(), Some("Ion Popescu"));
+        assert_eq!(
+            profile.school.as_deref(),
+            Some("Colegiul Național „Mihai Eminescu”")
+        );
+        assert_eq!(profile.county.as_deref(), Some("Suceava"));
+        assert_eq!(profile.solved_problems, Some(312));
+        assert_eq!(profile.success_points, Some(1540));
+    }
+
+    #[test]
+    fn solved_problems_and_progress_of_a_profile() {
+        let progress = solved_problems(PROFILE);
+        assert_eq!(progress.solved, ["1"]);
+        assert_eq!(progress.attempted, ["2"]);
+
+        let summary = progress_summary(PROFILE);
+        let categories: Vec<(&str, Option<u8>, u32, u32)> = summary
+            .categories
+            .iter()
+            .map(|category| {
+                (
+                    category.name.as_str(),
+                    category.grade,
+                    category.solved,
+                    category.total,
+                )
+            })
+            .collect();
+        assert_eq!(
+            categories,
+            [
+                ("Clasa a IX-a", Some(9), 52, 312),
+                ("Clasa a 10-a", Some(10), 8, 150),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranking_reads_the_columns_by_name() {
+        let entries = ranking(RANKING);
+        assert_eq!(
+            entries,
+            [
+                RankingEntry {
+                    position: 1,
+                    name: "Ion Popescu".to_string(),
+                    username: Some("ionpopescu".to_string()),
+                    school: Some("Colegiul Național „Mihai Eminescu”".to_string()),
+                    solved: Some(312),
+                    points: Some(1540),
+                },
+                RankingEntry {
+                    position: 2,
+                    name: "Maria Ionescu".to_string(),
+                    username: Some("maria.ionescu".to_string()),
+                    school: None,
+                    solved: Some(250),
+                    points: Some(1200),
+                },
+            ]
+        );
+        assert!(ranking(PROBLEM_PAGE).is_empty());
+    }
+
+    #[test]
+    fn achievements_read_the_badges() {
+        let achievements = achievements(ACHIEVEMENTS);
+        assert_eq!(
+            achievements,
+            [
+                Achievement {
+                    name: "Primul pas".to_string(),
+                    description: "Ai rezolvat prima problemă.".to_string(),
+                    earned_on: Some("15.03.2024".to_string()),
+                },
+                Achievement {
+                    name: "Maratonist".to_string(),
+                    description: "Ai rezolvat 100 de probleme.".to_string(),
+                    earned_on: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn classes_and_their_assignments() {
+        let classes = classes(CLASSES);
+        assert_eq!(
+            classes,
+            [
+                Class {
+                    id: "12".to_string(),
+                    name: "9A Informatică".to_string(),
+                },
+                Class {
+                    id: "34".to_string(),
+                    name: "10B".to_string(),
+                },
+            ]
+        );
+
+        let assignments = assignments("12", CLASS_PAGE);
+        assert_eq!(
+            assignments,
+            [
+                AssignmentSummary {
+                    id: "101".to_string(),
+                    class_id: "12".to_string(),
+                    title: "Operatori".to_string(),
+                    deadline: Some("20.03.2024".to_string()),
+                },
+                AssignmentSummary {
+                    id: "102".to_string(),
+                    class_id: "12".to_string(),
+                    title: "Structura alternativă".to_string(),
+                    deadline: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn assignment_reads_the_deadline_and_the_problems() {
+        let assignment = assignment("101", ASSIGNMENT);
+        assert_eq!(assignment.title, "Operatori");
+        assert_eq!(assignment.deadline.as_deref(), Some("20.03.2024 23:59"));
+        let problems: Vec<(&str, Option<u8>, Option<Difficulty>)> = assignment
+            .problems
+            .iter()
+            .map(|problem| (problem.id.as_str(), problem.grade, problem.difficulty))
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                ("1", Some(9), Some(Difficulty::Easy)),
+                ("2", Some(9), Some(Difficulty::Medium)),
+            ]
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn upload_time_follows_the_summer_time_of_romania() {
+        let offset = |day, time| {
+            upload_time(day, Some(time))
+                .unwrap()
+                .offset()
+                .local_minus_utc()
+                / 3600
+        };
+        // 2024 switched on the 31st of march and the 27th of october
+        assert_eq!(offset("2024-03-31", "02:59:59"), 2);
+        assert_eq!(offset("2024-03-31", "04:00:00"), 3);
+        assert_eq!(offset("2024-07-01", "12:00"), 3);
+        assert_eq!(offset("2024-10-27", "03:59:59"), 3);
+        assert_eq!(offset("2024-10-27", "04:00:00"), 2);
+        assert_eq!(offset("15 decembrie 2024", "12:00"), 2);
+
+        let time = upload_time("15.03.2024", Some("18:04:12")).unwrap();
+        assert_eq!(time.to_rfc3339(), "2024-03-15T18:04:12+02:00");
+        assert_eq!(
+            upload_time("2024-03-15", None).unwrap().to_rfc3339(),
+            "2024-03-15T00:00:00+02:00"
+        );
+        assert!(upload_time("15 brumarel 2024", None).is_err());
+        assert!(upload_time("2024-03-15", Some("25:00")).is_err());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn js_to_json_never_panics(object in "\\PC*") {
+            js_to_json(&object);
+        }
+
+        #[test]
+        fn retry_after_never_panics(message in "\\PC*") {
+            retry_after(&message);
+        }
+
+        #[test]
+        fn retry_after_never_panics_on_durations(
+            amount in "[0-9]{1,30}",
+            unit in "(de )?(secunde|minute|minut|second)",
+        ) {
+            retry_after(&format!("Mai așteaptă {amount} {unit}"));
+        }
+
+        #[test]
+        fn charset_and_decode_never_panic(
+            body in proptest::collection::vec(proptest::num::u8::ANY, 0..2048),
+            content_type in proptest::option::of("\\PC*"),
+        ) {
+            charset(content_type.as_deref(), &body);
+            decode(&body, content_type.as_deref());
+        }
+
+        #[test]
+        fn decode_never_panics_on_meta_charsets(label in "\\PC{0,16}", tail in "\\PC*") {
+            let body = format!("<meta charset=\"{label}\">{tail}");
+            decode(body.as_bytes(), None);
+        }
+    }
+}
@@ -0,0 +1,760 @@
+//! Solving many problems in one go.
+//!
+//! pbinfo evaluates one solution of a user at a time and punishes uploading
+//! too often, while reads (checking scores) are cheap but shouldn't be spammed
+//! either. The scheduler here uploads whenever pbinfo would accept a new
+//! solution and polls the pending evaluations in between.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::pbinfo_user::{
+    deadline::{self, Deadline},
+    score::{get_score, Evaluation, ScoreStatus},
+    solve::{prepare_solution, PreparedSolution, SolveOptions, SolveOutcome},
+    sources::SolutionSource,
+    upload::{upload_with, UploadOptions},
+    PbinfoUser, UploadError,
+};
+use crate::telemetry::{ContextError, ErrorContext, ReportFuture};
+
+/// How [PbinfoUser::upload_many] sends its uploads
+#[derive(Debug, Clone)]
+pub struct UploadManyOptions {
+    pub upload: UploadOptions,
+    /// How many uploads can be in flight at the same time. Defaults to 1
+    pub concurrency: usize,
+    /// The minimum time between the start of two uploads. Defaults to 5 seconds
+    pub delay: Duration,
+}
+
+impl Default for UploadManyOptions {
+    fn default() -> Self {
+        UploadManyOptions {
+            upload: UploadOptions::default(),
+            concurrency: 1,
+            delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// How [PbinfoUser::solve_many] schedules its requests
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub solve: SolveOptions,
+    /// How many solutions can be waiting for their evaluation at the same
+    /// time. pbinfo only evaluates one at a time, so defaults to 1. 0 is
+    /// treated as 1
+    pub max_pending_evaluations: usize,
+    /// The minimum time between two uploads. Defaults to 5 seconds
+    pub upload_interval: Duration,
+    /// How long to wait before uploading again after pbinfo said too many
+    /// solutions were uploaded. Defaults to 30 seconds
+    pub cooldown: Duration,
+    /// The minimum time between two reads (checking a score, looking up a
+    /// solution). Defaults to 300 milliseconds
+    pub read_interval: Duration,
+    /// How often a pending evaluation is checked. Defaults to 1500 milliseconds
+    pub poll_interval: Duration,
+    /// How long an evaluation can take before giving up on it. Defaults to
+    /// 90 seconds
+    pub max_evaluation_wait: Duration,
+    /// How many times a problem is put back in the queue after pbinfo refused
+    /// the upload because of the rate limit. Defaults to 3
+    pub max_upload_attempts: u32,
+    /// How many uploads pbinfo can accept in any hour, the batch pauses once the
+    /// limit is reached and goes on when the oldest upload is an hour old.
+    /// `None` (the default) for no limit
+    pub max_uploads_per_hour: Option<u32>,
+    /// How long the whole batch can take, the problems that aren't done by
+    /// then end up as [BatchOutcome::DeadlineExceeded]
+    pub timeout: Option<Duration>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            solve: SolveOptions::default(),
+            max_pending_evaluations: 1,
+            upload_interval: Duration::from_secs(5),
+            cooldown: Duration::from_secs(30),
+            read_interval: Duration::from_millis(300),
+            poll_interval: Duration::from_millis(1500),
+            max_evaluation_wait: Duration::from_secs(90),
+            max_upload_attempts: 3,
+            max_uploads_per_hour: None,
+            timeout: None,
+        }
+    }
+}
+
+/// What happened to one problem of a batch
+#[derive(Debug, Clone)]
+pub enum BatchOutcome {
+    /// The user already had 100 points, nothing was uploaded
+    Skipped {
+        existing_sol_id: Option<String>,
+        submitted_at: Option<String>,
+    },
+    /// The solution was uploaded and evaluated
+    Evaluated {
+        solution_id: String,
+        evaluation: Box<Evaluation>,
+    },
+    /// Something went wrong, `solution_id` is set if it happened after the
+    /// upload
+    Failed {
+        solution_id: Option<String>,
+        error: String,
+    },
+    /// The batch ran out of time before this problem was done
+    DeadlineExceeded { solution_id: Option<String> },
+}
+
+/// One problem of a [BatchReport]
+#[derive(Debug, Clone)]
+pub struct BatchEntry {
+    pub problem_id: String,
+    pub outcome: BatchOutcome,
+}
+
+/// Returned by [PbinfoUser::solve_many], one entry per problem in the order
+/// they finished
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub entries: Vec<BatchEntry>,
+}
+
+/// What [PbinfoUser::solve_many] returns, the successes, skips and failures
+/// of every problem
+pub type SolveReport = BatchReport;
+
+impl BatchReport {
+    /// The entries whose solution was uploaded and evaluated, whatever the
+    /// score
+    pub fn succeeded(&self) -> impl Iterator<Item = &BatchEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.outcome, BatchOutcome::Evaluated { .. }))
+    }
+
+    /// The entries that already had 100 points
+    pub fn skipped(&self) -> impl Iterator<Item = &BatchEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.outcome, BatchOutcome::Skipped { .. }))
+    }
+
+    /// How many problems ended up with 100 points, skipped ones included
+    pub fn perfect(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| match &entry.outcome {
+                BatchOutcome::Skipped { .. } => true,
+                BatchOutcome::Evaluated { evaluation, .. } => {
+                    evaluation.report.score() == Some(100)
+                }
+                BatchOutcome::Failed { .. } | BatchOutcome::DeadlineExceeded { .. } => false,
+            })
+            .count()
+    }
+
+    /// The entries that failed
+    pub fn failed(&self) -> impl Iterator<Item = &BatchEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.outcome, BatchOutcome::Failed { .. }))
+    }
+}
+
+struct Queued {
+    problem_id: String,
+    upload_attempts: u32,
+}
+
+struct Pending {
+    problem_id: String,
+    solution_id: String,
+    uploaded_at: Instant,
+    next_poll: Instant,
+}
+
+struct Scheduler<'a> {
+    pbinfo_user: &'a PbinfoUser,
+    options: &'a BatchOptions,
+    custom_solutions: Option<&'a dyn SolutionSource>,
+    queue: VecDeque<Queued>,
+    pending: Vec<Pending>,
+    /// When the uploads of the last hour were sent, for
+    /// [BatchOptions::max_uploads_per_hour]
+    uploads: VecDeque<Instant>,
+    next_upload: Instant,
+    next_read: Instant,
+    deadline: Option<Deadline>,
+    report: BatchReport,
+}
+
+impl Scheduler<'_> {
+    fn finish(&mut self, problem_id: String, outcome: BatchOutcome) {
+        trace_event!(tracing::Level::INFO, problem_id, outcome = ?outcome, "problem done");
+        self.report.entries.push(BatchEntry {
+            problem_id,
+            outcome,
+        });
+    }
+
+    /// Waits until the read rate limit allows another request
+    async fn wait_read(&mut self) {
+        tokio::time::sleep_until(self.next_read.into()).await;
+        self.next_read = Instant::now() + self.options.read_interval;
+    }
+
+    /// Whether another solution can wait for its evaluation. At least one
+    /// always can, or a batch with a limit of 0 would never upload anything
+    fn has_evaluation_slot(&self) -> bool {
+        self.pending.len() < self.options.max_pending_evaluations.max(1)
+    }
+
+    fn can_upload(&self, now: Instant) -> bool {
+        !self.queue.is_empty() && self.has_evaluation_slot() && now >= self.next_upload
+    }
+
+    async fn upload_next(&mut self) {
+        let Some(mut queued) = self.queue.pop_front() else {
+            return;
+        };
+
+        self.wait_read().await;
+        let prepared = deadline::within(
+            self.deadline,
+            prepare_solution(
+                &queued.problem_id,
+                self.pbinfo_user,
+                self.custom_solutions,
+                &self.options.solve,
+            ),
+        )
+        .await;
+        let Ok(prepared) = prepared else {
+            return self.queue.push_front(queued);
+        };
+        let source = match prepared {
+            Ok(PreparedSolution::Source(source)) => source,
+            Ok(PreparedSolution::Skip(SolveOutcome::Skipped {
+                existing_sol_id,
+                submitted_at,
+            })) => {
+                return self.finish(
+                    queued.problem_id,
+                    BatchOutcome::Skipped {
+                        existing_sol_id,
+                        submitted_at,
+                    },
+                )
+            }
+            Ok(PreparedSolution::Skip(SolveOutcome::Submitted { solution_id })) => {
+                return self.queue_poll(queued.problem_id, solution_id)
+            }
+            Err(err) => {
+                return self.finish(
+                    queued.problem_id,
+                    BatchOutcome::Failed {
+                        solution_id: None,
+                        error: err.to_string(),
+                    },
+                )
+            }
+        };
+
+        let upload_options = self.options.solve.upload_options();
+        let result = deadline::within(
+            self.deadline,
+            upload_with(
+                &queued.problem_id,
+                &source,
+                &upload_options,
+                self.pbinfo_user,
+            ),
+        )
+        .await;
+        let Ok(result) = result else {
+            // pbinfo may or may not have gotten the source, there is no id either way
+            return self.finish(
+                queued.problem_id,
+                BatchOutcome::DeadlineExceeded { solution_id: None },
+            );
+        };
+        let now = Instant::now();
+        self.next_upload = now + self.options.upload_interval;
+        match result {
+            Ok(solution_id) => {
+                // only what pbinfo took counts towards the hourly limit
+                self.record_upload(now);
+                self.queue_poll(queued.problem_id, solution_id)
+            }
+            Err(err @ (UploadError::RateLimited { .. } | UploadError::EvaluationPendingError))
+                if queued.upload_attempts + 1 < self.options.max_upload_attempts =>
+            {
+                trace_event!(
+                    tracing::Level::INFO,
+                    problem_id = queued.problem_id,
+                    "pbinfo refused the upload, trying again later"
+                );
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_retry();
+                self.next_upload = now
+                    + match err {
+                        UploadError::RateLimited {
+                            retry_after: Some(retry_after),
+                        } => retry_after,
+                        _ => self.options.cooldown,
+                    };
+                queued.upload_attempts += 1;
+                self.queue.push_front(queued);
+            }
+            Err(err) => self.finish(
+                queued.problem_id,
+                BatchOutcome::Failed {
+                    solution_id: None,
+                    error: err.to_string(),
+                },
+            ),
+        }
+    }
+
+    /// Pushes the next upload past the hourly limit, if it was reached
+    fn record_upload(&mut self, now: Instant) {
+        const HOUR: Duration = Duration::from_secs(60 * 60);
+        let Some(max) = self.options.max_uploads_per_hour else {
+            return;
+        };
+        self.uploads.push_back(now);
+        while self
+            .uploads
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= HOUR)
+        {
+            self.uploads.pop_front();
+        }
+        if self.uploads.len() >= max.max(1) as usize {
+            if let Some(oldest) = self.uploads.front() {
+                trace_event!(
+                    tracing::Level::INFO,
+                    "hourly upload limit reached, pausing the batch"
+                );
+                self.next_upload = self.next_upload.max(*oldest + HOUR);
+            }
+        }
+    }
+
+    fn queue_poll(&mut self, problem_id: String, solution_id: String) {
+        let now = Instant::now();
+        self.pending.push(Pending {
+            problem_id,
+            solution_id,
+            uploaded_at: now,
+            next_poll: now + self.options.poll_interval,
+        });
+    }
+
+    /// Checks every pending evaluation that is due, one after the other
+    async fn poll_due(&mut self, now: Instant) {
+        let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|pending| pending.next_poll <= now);
+        self.pending = waiting;
+
+        for mut pending in due {
+            self.wait_read().await;
+            let Ok(score) = deadline::within(
+                self.deadline,
+                get_score(&pending.solution_id, self.pbinfo_user),
+            )
+            .await
+            else {
+                self.pending.push(pending);
+                continue;
+            };
+            match score {
+                Ok(
+                    ScoreStatus::DoneExecuting { evaluation }
+                    | ScoreStatus::CompileError { evaluation, .. },
+                ) => {
+                    #[cfg(feature = "metrics")]
+                    crate::metrics::record_evaluation_wait(pending.uploaded_at.elapsed());
+                    self.finish(
+                        pending.problem_id,
+                        BatchOutcome::Evaluated {
+                            solution_id: pending.solution_id,
+                            evaluation,
+                        },
+                    );
+                }
+                Ok(ScoreStatus::StillExecuting)
+                    if pending.uploaded_at.elapsed() < self.options.max_evaluation_wait =>
+                {
+                    pending.next_poll = Instant::now() + self.options.poll_interval;
+                    self.pending.push(pending);
+                }
+                Ok(ScoreStatus::StillExecuting) => self.finish(
+                    pending.problem_id,
+                    BatchOutcome::Failed {
+                        solution_id: Some(pending.solution_id),
+                        error: "Error: The evaluation took too long!".to_string(),
+                    },
+                ),
+                Err(err) => self.finish(
+                    pending.problem_id,
+                    BatchOutcome::Failed {
+                        solution_id: Some(pending.solution_id),
+                        error: err.to_string(),
+                    },
+                ),
+            }
+        }
+    }
+
+    /// When something will be ready to do next
+    fn next_wake(&self) -> Instant {
+        let next_poll = self.pending.iter().map(|pending| pending.next_poll).min();
+        let next_upload =
+            (!self.queue.is_empty() && self.has_evaluation_slot()).then_some(self.next_upload);
+        next_poll
+            .into_iter()
+            .chain(next_upload)
+            .chain(self.deadline.map(|deadline| deadline.at()))
+            .min()
+            .unwrap_or_else(Instant::now)
+    }
+
+    /// Gives up on everything that isn't done yet
+    fn expire(&mut self) {
+        trace_event!(tracing::Level::WARN, "batch deadline exceeded");
+        for queued in std::mem::take(&mut self.queue) {
+            self.finish(
+                queued.problem_id,
+                BatchOutcome::DeadlineExceeded { solution_id: None },
+            );
+        }
+        for pending in std::mem::take(&mut self.pending) {
+            self.finish(
+                pending.problem_id,
+                BatchOutcome::DeadlineExceeded {
+                    solution_id: Some(pending.solution_id),
+                },
+            );
+        }
+    }
+
+    async fn run(mut self) -> BatchReport {
+        while !self.queue.is_empty() || !self.pending.is_empty() {
+            if self
+                .deadline
+                .is_some_and(|deadline| deadline.remaining().is_none())
+            {
+                self.expire();
+                break;
+            }
+
+            let now = Instant::now();
+            // uploads first, they are what everything else waits on
+            if self.can_upload(now) {
+                self.upload_next().await;
+                continue;
+            }
+            if self.pending.iter().any(|pending| pending.next_poll <= now) {
+                self.poll_due(now).await;
+                continue;
+            }
+            tokio::time::sleep_until(self.next_wake().into()).await;
+        }
+        self.report
+    }
+}
+
+/// Solves all the problems, uploading as fast as pbinfo allows and waiting
+/// for every evaluation
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip(pbinfo_user, custom_solutions, options))
+)]
+pub async fn solve_many(
+    problem_ids: &[&str],
+    custom_solutions: Option<&dyn SolutionSource>,
+    options: &BatchOptions,
+    pbinfo_user: &PbinfoUser,
+) -> BatchReport {
+    let now = Instant::now();
+    Scheduler {
+        pbinfo_user,
+        options,
+        custom_solutions,
+        queue: problem_ids
+            .iter()
+            .map(|problem_id| Queued {
+                problem_id: problem_id.to_string(),
+                upload_attempts: 0,
+            })
+            .collect(),
+        pending: Vec::new(),
+        uploads: VecDeque::new(),
+        next_upload: now,
+        next_read: now,
+        deadline: Deadline::new(options.timeout),
+        report: BatchReport::default(),
+    }
+    .run()
+    .await
+}
+
+/// Uploads every `(problem id, source)` pair, at most `options.concurrency`
+/// at a time and `options.delay` apart. Returns the solution id or the error
+/// of every upload in the order of `uploads`, each error with the context
+/// of its own upload
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub async fn upload_many(
+    uploads: Vec<(String, String)>,
+    options: &UploadManyOptions,
+    pbinfo_user: &PbinfoUser,
+) -> Vec<(String, Result<String, ContextError<UploadError>>)> {
+    let permits = tokio::sync::Semaphore::new(options.concurrency.max(1));
+    let next_start = tokio::sync::Mutex::new(tokio::time::Instant::now());
+
+    let uploads = uploads.into_iter().map(|(problem_id, source)| {
+        let permits = &permits;
+        let next_start = &next_start;
+        async move {
+            let _permit = permits
+                .acquire()
+                .await
+                .expect("the semaphore is never closed");
+            {
+                // holding the lock while sleeping keeps the starts in line
+                let mut next_start = next_start.lock().await;
+                tokio::time::sleep_until(*next_start).await;
+                *next_start = tokio::time::Instant::now() + options.delay;
+            }
+            let result = upload_with(&problem_id, &source, &options.upload, pbinfo_user)
+                .report(|| ErrorContext::new("upload_many").problem_id(&problem_id))
+                .await;
+            (problem_id, result)
+        }
+    });
+
+    futures_util::future::join_all(uploads).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use super::*;
+    use crate::test_support::{self, SentRequest, SCORE_DONE, SCORE_PENDING};
+
+    const UPLOAD_PATH: &str = "/ajx-module/php-solutie-incarcare.php";
+    const EVALUATION_PATH: &str = "/ajx-module/ajx-solutie-detalii-evaluare.php";
+
+    /// Options that don't make a test wait
+    fn fast_options() -> BatchOptions {
+        BatchOptions {
+            solve: SolveOptions {
+                skip_if_perfect: false,
+                ..Default::default()
+            },
+            upload_interval: Duration::ZERO,
+            read_interval: Duration::ZERO,
+            poll_interval: Duration::from_millis(1),
+            timeout: None,
+            ..Default::default()
+        }
+    }
+
+    fn solutions(problem_ids: &[&str]) -> HashMap<String, String> {
+        problem_ids
+            .iter()
+            .map(|problem_id| (problem_id.to_string(), "int main() {}".to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn no_pending_evaluations_allowed_still_uploads() {
+        let (client, sent) =
+            test_support::client(|request| test_support::accepting_uploads(request, "1000"));
+        let pbinfo_user = PbinfoUser::builder().client(client).build();
+        let options = BatchOptions {
+            max_pending_evaluations: 0,
+            ..fast_options()
+        };
+
+        let report = tokio::time::timeout(
+            Duration::from_secs(10),
+            solve_many(
+                &["1", "2"],
+                Some(&solutions(&["1", "2"])),
+                &options,
+                &pbinfo_user,
+            ),
+        )
+        .await
+        .expect("the batch never finished");
+        assert_eq!(report.succeeded().count(), 2);
+        assert_eq!(
+            sent.requests()
+                .iter()
+                .filter(|request| request.path() == "/ajx-module/php-solutie-incarcare.php")
+                .count(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_uploads_dont_count_towards_the_hourly_limit() {
+        let refused = AtomicBool::new(false);
+        let (client, _) = test_support::client(move |request| {
+            let is_upload = request.path() == "/ajx-module/php-solutie-incarcare.php";
+            if is_upload && !refused.swap(true, Ordering::SeqCst) {
+                return test_support::ok(
+                    r#"{"stare":"error","raspuns":"Așteaptă evaluarea surselor deja trimise"}"#,
+                );
+            }
+            test_support::accepting_uploads(request, "1000")
+        });
+        let pbinfo_user = PbinfoUser::builder().client(client).build();
+        let options = BatchOptions {
+            max_uploads_per_hour: Some(1),
+            cooldown: Duration::ZERO,
+            ..fast_options()
+        };
+
+        let report = tokio::time::timeout(
+            Duration::from_secs(10),
+            solve_many(&["1"], Some(&solutions(&["1"])), &options, &pbinfo_user),
+        )
+        .await
+        .expect("the refused upload used up the hourly limit");
+        assert_eq!(report.succeeded().count(), 1);
+    }
+
+    /// What [evaluating] saw of the uploads
+    #[derive(Default)]
+    struct Uploads {
+        count: u32,
+        /// The solutions pbinfo said are done
+        done: HashSet<String>,
+        /// Set if a solution was uploaded before the one before it was done
+        uploaded_while_pending: bool,
+    }
+
+    /// pbinfo numbering the uploads from 1000, with every evaluation still
+    /// pending the first time it is checked. The upload number `refused` is
+    /// refused
+    fn evaluating(
+        uploads: &Mutex<Uploads>,
+        polled: &Mutex<HashSet<String>>,
+        refused: u32,
+        request: &SentRequest,
+    ) -> ::http::Response<String> {
+        let path = request.path();
+        let mut uploads = uploads.lock().unwrap();
+        if path == UPLOAD_PATH {
+            uploads.count += 1;
+            if uploads.count == refused {
+                return test_support::ok(r#"{"stare":"error","raspuns":"Nu"}"#);
+            }
+            let id = 999 + uploads.count;
+            if id > 1000 && !uploads.done.contains(&(id - 1).to_string()) {
+                uploads.uploaded_while_pending = true;
+            }
+            return test_support::ok(format!("{{\"stare\":\"success\",\"id_solutie\":\"{id}\"}}"));
+        }
+        if let Some((_, id)) = path
+            .strip_prefix(EVALUATION_PATH)
+            .and_then(|query| query.rsplit_once("id="))
+        {
+            if !polled.lock().unwrap().insert(id.to_string()) {
+                uploads.done.insert(id.to_string());
+                return test_support::ok(SCORE_DONE);
+            }
+            return test_support::ok(SCORE_PENDING);
+        }
+        test_support::accepting_uploads(request, "")
+    }
+
+    async fn solve_three(refused: u32) -> (BatchReport, Uploads) {
+        let uploads = Arc::new(Mutex::new(Uploads::default()));
+        let polled = Mutex::new(HashSet::new());
+        let (client, _) = test_support::client({
+            let uploads = uploads.clone();
+            move |request| evaluating(&uploads, &polled, refused, request)
+        });
+        let pbinfo_user = PbinfoUser::builder().client(client).build();
+
+        let report = tokio::time::timeout(
+            Duration::from_secs(10),
+            solve_many(
+                &["1", "2", "3"],
+                Some(&solutions(&["1", "2", "3"])),
+                &fast_options(),
+                &pbinfo_user,
+            ),
+        )
+        .await
+        .expect("the batch never finished");
+        let uploads = std::mem::take(&mut *uploads.lock().unwrap());
+        (report, uploads)
+    }
+
+    #[tokio::test]
+    async fn uploads_wait_for_the_pending_evaluation() {
+        let (report, uploads) = solve_three(0).await;
+        assert_eq!(report.succeeded().count(), 3);
+        assert_eq!(uploads.count, 3);
+        assert!(!uploads.uploaded_while_pending);
+    }
+
+    #[tokio::test]
+    async fn refused_uploads_are_failures_in_the_report() {
+        let (report, _) = solve_three(2).await;
+        assert_eq!(report.entries.len(), 3);
+        assert_eq!(report.succeeded().count(), 2);
+        let failed: Vec<_> = report.failed().collect();
+        assert_eq!(failed.len(), 1);
+        assert!(matches!(
+            failed[0].outcome,
+            BatchOutcome::Failed {
+                solution_id: None,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn upload_many_keeps_every_upload_in_order() {
+        let (client, _) =
+            test_support::client(|request| test_support::accepting_uploads(request, "1000"));
+        let pbinfo_user = PbinfoUser::builder().client(client).build();
+        let options = UploadManyOptions {
+            concurrency: 3,
+            delay: Duration::ZERO,
+            ..Default::default()
+        };
+        let uploads = [("2", "first"), ("1", "second"), ("2", "third")]
+            .map(|(problem_id, source)| (problem_id.to_string(), source.to_string()));
+
+        let results = upload_many(uploads.to_vec(), &options, &pbinfo_user).await;
+        let problem_ids: Vec<&str> = results
+            .iter()
+            .map(|(problem_id, _)| problem_id.as_str())
+            .collect();
+        assert_eq!(problem_ids, ["2", "1", "2"]);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+    }
+}
@@ -0,0 +1,77 @@
+//! Synchronous versions of the main methods of [PbinfoUser], for scripts and
+//! build tools that don't run an async runtime.
+//!
+//! Like `reqwest::blocking`, the async methods are driven on a runtime owned
+//! by the crate, so these must not be called from inside an async runtime.
+
+use std::{future::Future, sync::LazyLock};
+
+use crate::telemetry::ContextError;
+
+use super::{
+    Evaluation, GetScoreError, LoginError, PbinfoUser, ScoreStatus, SessionInfo, SolveError,
+    SolveOutcome, TopSolutionResponseType, UploadError,
+};
+
+static RUNTIME: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("pbinfo-blocking")
+        .enable_all()
+        .build()
+        .expect("couldn't start the runtime of the blocking api")
+});
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    RUNTIME.block_on(future)
+}
+
+impl PbinfoUser {
+    /// Same as [PbinfoUser::login]
+    pub fn login_blocking(&mut self) -> Result<SessionInfo, ContextError<LoginError>> {
+        block_on(self.login())
+    }
+
+    /// Same as [PbinfoUser::is_logged_in]
+    pub fn is_logged_in_blocking(&self) -> Result<bool, ContextError<LoginError>> {
+        block_on(self.is_logged_in())
+    }
+
+    /// Same as [PbinfoUser::upload]
+    pub fn upload_blocking(
+        &self,
+        problem_id: &str,
+        source: &str,
+    ) -> Result<String, ContextError<UploadError>> {
+        block_on(self.upload(problem_id, source))
+    }
+
+    /// Same as [PbinfoUser::solve]
+    pub fn solve_blocking(
+        &self,
+        problem_id: &str,
+    ) -> Result<SolveOutcome, ContextError<SolveError>> {
+        block_on(self.solve(problem_id))
+    }
+
+    /// Same as [PbinfoUser::get_score]
+    pub fn get_score_blocking(
+        &self,
+        sol_id: &str,
+    ) -> Result<ScoreStatus, ContextError<GetScoreError>> {
+        block_on(self.get_score(sol_id))
+    }
+
+    /// Same as [PbinfoUser::pool_score]
+    pub fn pool_score_blocking(
+        &self,
+        sol_id: &str,
+    ) -> Result<Evaluation, ContextError<GetScoreError>> {
+        block_on(self.pool_score(sol_id))
+    }
+
+    /// Same as [PbinfoUser::get_top_score]
+    pub fn get_top_score_blocking(&self, problem_id: &str) -> TopSolutionResponseType {
+        block_on(self.get_top_score(problem_id))
+    }
+}
@@ -0,0 +1,284 @@
+//! Zip bundles of a solved problem: the statement, its examples, the best
+//! source of the user and its score report.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    endpoints, http,
+    models::SolutionEntry,
+    parse,
+    pbinfo_user::{export::get_source, PbinfoUser},
+};
+
+const MANIFEST_FILE_NAME: &str = "bundle.json";
+const STATEMENT_FILE_NAME: &str = "statement.html";
+const REPORT_FILE_NAME: &str = "report.json";
+
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("Error: Couldn't get the page of the problem {problem_id}!\nGot error:\n{err}")]
+    ProblemPageError { problem_id: String, err: String },
+    #[error("Error: The user didn't send any solution to the problem {problem_id}!")]
+    NoSolutionError { problem_id: String },
+    #[error("Error: Couldn't download the best solution!\nGot error:\n{err}")]
+    DownloadError { err: String },
+    #[error("Error: Couldn't write the bundle {file}!\nGot error:\n{err}")]
+    WriteError { file: PathBuf, err: String },
+    #[error("Error: Couldn't read the bundle {file}!\nGot error:\n{err}")]
+    ReadError { file: PathBuf, err: String },
+}
+
+/// The `bundle.json` at the root of a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub problem_id: String,
+    pub solution_id: String,
+    /// The name of the source inside the bundle
+    pub source_file: String,
+    pub score: Option<String>,
+    pub submitted_at: Option<String>,
+    /// The names of the example files inside the bundle
+    pub examples: Vec<String>,
+}
+
+fn score_of(solution: &SolutionEntry) -> i64 {
+    solution
+        .scor
+        .as_deref()
+        .and_then(|score| score.parse().ok())
+        .unwrap_or(-1)
+}
+
+/// Returns the solution with the highest score of the user for a problem
+async fn get_best_solution(
+    problem_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Option<SolutionEntry>, Box<dyn std::error::Error>> {
+    let endpoint =
+        endpoints::SolutionList::new(problem_id, pbinfo_user.user_id()).ssid(pbinfo_user.ssid());
+    let total =
+        http::for_each_solution(
+            http::send(&pbinfo_user.client, &endpoint).await?,
+            |_| Ok(()),
+        )
+        .await?;
+
+    let endpoint = endpoints::SolutionList::new(problem_id, pbinfo_user.user_id())
+        .count(u32::try_from(total)?)
+        .ssid(pbinfo_user.ssid());
+    let mut best: Option<SolutionEntry> = None;
+    http::for_each_solution(
+        http::send(&pbinfo_user.client, &endpoint).await?,
+        |solution| {
+            if best
+                .as_ref()
+                .is_none_or(|best| score_of(&solution) > score_of(best))
+            {
+                best = Some(solution);
+            }
+            Ok(())
+        },
+    )
+    .await?;
+    Ok(best)
+}
+
+async fn get_report(
+    solution_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let endpoint = endpoints::EvaluationDetails::new(solution_id)
+        .force_reload(false)
+        .ssid(pbinfo_user.ssid());
+    let response = http::send(&pbinfo_user.client, &endpoint).await?;
+    Ok(http::json_body(response).await?)
+}
+
+fn write_zip(path: &Path, files: &[(String, String)]) -> Result<(), zip::result::ZipError> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    for (name, contents) in files {
+        zip.start_file(name.as_str(), SimpleFileOptions::default())?;
+        zip.write_all(contents.as_bytes())?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Writes a zip with the statement, the examples, the best source of the
+/// user and its score report to `path`
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn export_bundle(
+    problem_id: &str,
+    path: &Path,
+    pbinfo_user: &PbinfoUser,
+) -> Result<BundleManifest, BundleError> {
+    let page_error = |err: String| BundleError::ProblemPageError {
+        problem_id: problem_id.to_string(),
+        err,
+    };
+    let endpoint = endpoints::ProblemPage::new(problem_id).ssid(pbinfo_user.ssid());
+    let response = http::send(&pbinfo_user.client, &endpoint)
+        .await
+        .map_err(|err| page_error(err.to_string()))?;
+    let statement = http::text(response)
+        .await
+        .map_err(|err| page_error(err.to_string()))?;
+
+    let download_error = |err: Box<dyn std::error::Error>| BundleError::DownloadError {
+        err: err.to_string(),
+    };
+    let best = get_best_solution(problem_id, pbinfo_user)
+        .await
+        .map_err(download_error)?
+        .ok_or_else(|| BundleError::NoSolutionError {
+            problem_id: problem_id.to_string(),
+        })?;
+    let solution_id = best
+        .solution_id()
+        .ok_or_else(|| download_error("the solution had no id".into()))?;
+    let source = get_source(&solution_id, pbinfo_user)
+        .await
+        .map_err(download_error)?;
+    let report = get_report(&solution_id, pbinfo_user)
+        .await
+        .map_err(download_error)?;
+
+    let source_file = format!("{solution_id}.{}", best.limbaj.as_deref().unwrap_or("cpp"));
+    let mut files = vec![
+        (STATEMENT_FILE_NAME.to_string(), statement.clone()),
+        (source_file.clone(), source),
+        (REPORT_FILE_NAME.to_string(), report),
+    ];
+    let mut examples = Vec::new();
+    for (i, example) in parse::problem_examples(&statement).into_iter().enumerate() {
+        let name = format!("examples/{}.txt", i + 1);
+        examples.push(name.clone());
+        files.push((name, example));
+    }
+
+    let manifest = BundleManifest {
+        problem_id: problem_id.to_string(),
+        solution_id,
+        source_file,
+        score: best.scor.clone(),
+        submitted_at: best.submitted_at(),
+        examples,
+    };
+    files.push((
+        MANIFEST_FILE_NAME.to_string(),
+        serde_json::to_string_pretty(&manifest).unwrap(),
+    ));
+
+    write_zip(path, &files).map_err(|err| BundleError::WriteError {
+        file: path.to_path_buf(),
+        err: err.to_string(),
+    })?;
+    Ok(manifest)
+}
+
+/// Solutions read out of bundles, to be used as custom solutions by
+/// [PbinfoUser::bundle_solve].
+///
+/// Reads both the bundles of [PbinfoUser::export_bundle] and plain zips of
+/// sources named after their problem, like `1234.cpp` or `1234/main.cpp`, as
+/// shared by a class
+#[derive(Debug, Clone, Default)]
+pub struct BundleSolutions {
+    solutions: HashMap<String, String>,
+}
+
+/// The problem id a source in a plain zip is for
+fn problem_id_of(name: &str) -> Option<&str> {
+    let first = name.split('/').next()?;
+    let id = first.split('.').next()?;
+    (!id.is_empty() && id.chars().all(|c| c.is_ascii_digit())).then_some(id)
+}
+
+fn read_file(zip: &mut ZipArchive<File>, name: &str) -> Result<String, zip::result::ZipError> {
+    let mut contents = String::new();
+    zip.by_name(name)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+impl BundleSolutions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the solutions out of all the zips at `paths`, later zips win
+    pub fn open<P: AsRef<Path>>(paths: &[P]) -> Result<Self, BundleError> {
+        let mut solutions = Self::new();
+        for path in paths {
+            solutions.add(path.as_ref())?;
+        }
+        Ok(solutions)
+    }
+
+    /// Reads the solutions out of the zip at `path`
+    pub fn add(&mut self, path: &Path) -> Result<&mut Self, BundleError> {
+        let read_error = |err: zip::result::ZipError| BundleError::ReadError {
+            file: path.to_path_buf(),
+            err: err.to_string(),
+        };
+        let mut zip = ZipArchive::new(File::open(path).map_err(|err| read_error(err.into()))?)
+            .map_err(read_error)?;
+
+        if let Ok(manifest) = read_file(&mut zip, MANIFEST_FILE_NAME) {
+            let manifest: BundleManifest =
+                serde_json::from_str(&manifest).map_err(|err| BundleError::ReadError {
+                    file: path.to_path_buf(),
+                    err: err.to_string(),
+                })?;
+            let source = read_file(&mut zip, &manifest.source_file).map_err(read_error)?;
+            self.solutions.insert(manifest.problem_id, source);
+            return Ok(self);
+        }
+
+        let names = zip
+            .file_names()
+            .map(|name| name.map(|name| name.to_string()))
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(read_error)?;
+        for name in names {
+            let Some(problem_id) = problem_id_of(&name) else {
+                continue;
+            };
+            if name.ends_with('/') {
+                continue;
+            }
+            let source = read_file(&mut zip, &name).map_err(read_error)?;
+            self.solutions.insert(problem_id.to_string(), source);
+        }
+        Ok(self)
+    }
+
+    /// The ids of the problems there is a solution for
+    pub fn problem_ids(&self) -> impl Iterator<Item = &str> {
+        self.solutions.keys().map(|problem_id| problem_id.as_str())
+    }
+
+    /// The solutions by problem id, as [PbinfoUser::custom_solve] takes them
+    pub fn solutions(&self) -> &HashMap<String, String> {
+        &self.solutions
+    }
+
+    /// The solutions as a json object of problem id to source
+    pub fn to_value(&self) -> Value {
+        Value::Object(
+            self.solutions
+                .iter()
+                .map(|(problem_id, source)| (problem_id.clone(), Value::String(source.clone())))
+                .collect(),
+        )
+    }
+}
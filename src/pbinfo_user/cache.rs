@@ -0,0 +1,147 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::time::SystemTime;
+
+/// Problem statements and metadata saved on disk, so looking up the same
+/// problem again doesn't hit the network. Set it with
+/// [crate::pbinfo_user::PbinfoUserBuilder::problem_cache].
+///
+/// Every entry is a json file under the directory of the cache and is
+/// fetched again once it is older than the ttl. Problems rarely change, so
+/// a ttl of days is fine
+#[derive(Debug, Clone)]
+pub struct ProblemCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    fetched_at: SystemTime,
+    value: T,
+}
+
+impl ProblemCache {
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        ProblemCache {
+            dir: dir.into(),
+            ttl,
+        }
+    }
+
+    /// A cache in the cache dir of the crate, like
+    /// `~/.cache/pbinfo-api/problems`. [ProblemCache::clear] removes only
+    /// that directory, the other caches of the crate are kept
+    #[cfg(feature = "config")]
+    pub fn in_cache_dir(ttl: Duration) -> Result<Self, super::PbinfoUserError> {
+        Ok(Self::new(
+            crate::user_config::cache_dir()?.join("problems"),
+            ttl,
+        ))
+    }
+
+    /// The directory the entries are saved in
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+
+    /// Where an entry is saved, `None` for ids that aren't a number (they
+    /// aren't problems and could point outside of the cache)
+    fn path(&self, kind: &str, problem_id: &str) -> Option<PathBuf> {
+        let is_number =
+            !problem_id.is_empty() && problem_id.bytes().all(|byte| byte.is_ascii_digit());
+        is_number.then(|| self.dir.join(kind).join(format!("{problem_id}.json")))
+    }
+
+    /// The saved value, `None` if there is none or it is too old
+    pub(crate) fn load<T: DeserializeOwned>(&self, kind: &str, problem_id: &str) -> Option<T> {
+        let json = fs::read_to_string(self.path(kind, problem_id)?).ok()?;
+        let entry: Entry<T> = serde_json::from_str(&json).ok()?;
+        let age = SystemTime::now()
+            .duration_since(entry.fetched_at)
+            .unwrap_or_default();
+        (age <= self.ttl).then_some(entry.value)
+    }
+
+    /// Saves a value, a cache that can't be written to is just skipped
+    pub(crate) fn store<T: Serialize>(&self, kind: &str, problem_id: &str, value: &T) {
+        let Some(path) = self.path(kind, problem_id) else {
+            return;
+        };
+        let entry = Entry {
+            fetched_at: SystemTime::now(),
+            value,
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, serde_json::to_string(&entry).unwrap()));
+        if let Err(_err) = result {
+            trace_event!(tracing::Level::WARN, error = %_err, "couldn't write to the problem cache");
+        }
+    }
+
+    /// Forgets everything saved about a problem
+    pub fn invalidate(&self, problem_id: &str) {
+        for path in [PROBLEM, PROBLEM_META]
+            .into_iter()
+            .filter_map(|kind| self.path(kind, problem_id))
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// Forgets everything
+    pub fn clear(&self) -> std::io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The kinds of entries, each one in its own directory
+pub(crate) const PROBLEM: &str = "problems";
+pub(crate) const PROBLEM_META: &str = "meta";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A cache in a fresh directory under the temp dir
+    fn temp_cache(name: &str) -> ProblemCache {
+        let dir =
+            std::env::temp_dir().join(format!("pbinfo-api-cache-{name}-{}", std::process::id()));
+        let cache = ProblemCache::new(dir, Duration::from_secs(60));
+        cache.clear().unwrap();
+        cache
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let cache = temp_cache("round-trip");
+        cache.store(PROBLEM, "1234", &"sum".to_string());
+        assert_eq!(
+            cache.load::<String>(PROBLEM, "1234").as_deref(),
+            Some("sum")
+        );
+
+        cache.invalidate("1234");
+        assert_eq!(cache.load::<String>(PROBLEM, "1234"), None);
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn ids_that_arent_numbers_skip_the_cache() {
+        let cache = temp_cache("bad-ids");
+        for problem_id in ["", "../escaped", "/tmp/x", "12a"] {
+            cache.store(PROBLEM, problem_id, &"sum".to_string());
+            assert_eq!(cache.load::<String>(PROBLEM, problem_id), None);
+            cache.invalidate(problem_id);
+        }
+        assert!(!cache.dir().join("escaped.json").exists());
+        assert!(!cache.dir().exists());
+    }
+}
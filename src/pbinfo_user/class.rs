@@ -0,0 +1,135 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    endpoints::{self, Endpoint},
+    http, parse,
+    pbinfo_user::{PbinfoUser, ProblemSummary},
+};
+
+#[derive(Error, Debug)]
+pub enum ClassError {
+    #[error("Error: The user isn't logged in, only the classes of a logged in user can be seen!")]
+    NotLoggedInError,
+    #[error("Error: The class or assignment {id} doesn't exist or the user isn't in it!")]
+    NotFoundError { id: String },
+    #[error("Error: Couldn't get the page of the classes!\nGot error:\n{err}")]
+    RequestError { err: String },
+}
+
+/// A class the user belongs to, returned by [PbinfoUser::list_classes]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Class {
+    pub id: String,
+    pub name: String,
+}
+
+/// An assignment in the list of a class, returned by
+/// [PbinfoUser::list_assignments]. Get its problems with
+/// [PbinfoUser::get_assignment]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssignmentSummary {
+    pub id: String,
+    pub class_id: String,
+    pub title: String,
+    /// Until when solutions count, as pbinfo writes it
+    pub deadline: Option<String>,
+}
+
+impl AssignmentSummary {
+    /// The day of the deadline
+    #[cfg(feature = "chrono")]
+    pub fn deadline_date(&self) -> Option<chrono::NaiveDate> {
+        deadline_date(self.deadline.as_deref()?)
+    }
+}
+
+/// An assignment ("tema") with the problems to solve, returned by
+/// [PbinfoUser::get_assignment]. The ids of the problems work with
+/// [PbinfoUser::upload] and [PbinfoUser::get_top_score] like any other, set
+/// [UploadOptions::assignment](crate::pbinfo_user::UploadOptions::assignment)
+/// for the solutions to count for the assignment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Assignment {
+    pub id: String,
+    pub title: String,
+    /// Until when solutions count, as pbinfo writes it
+    pub deadline: Option<String>,
+    pub problems: Vec<ProblemSummary>,
+}
+
+impl Assignment {
+    /// The day of the deadline
+    #[cfg(feature = "chrono")]
+    pub fn deadline_date(&self) -> Option<chrono::NaiveDate> {
+        deadline_date(self.deadline.as_deref()?)
+    }
+
+    /// The ids of the problems, for [PbinfoUser::solve_many] and the like
+    pub fn problem_ids(&self) -> Vec<&str> {
+        self.problems
+            .iter()
+            .map(|problem| problem.id.as_str())
+            .collect()
+    }
+}
+
+/// Deadlines can have the hour after the day, like `15.03.2024 23:59`
+#[cfg(feature = "chrono")]
+fn deadline_date(deadline: &str) -> Option<chrono::NaiveDate> {
+    parse::date(deadline).or_else(|| parse::date(deadline.split_whitespace().next()?))
+}
+
+/// Downloads a page only a logged in member of the class can see
+async fn get_page<E: Endpoint>(
+    endpoint: &E,
+    id: Option<&str>,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, ClassError> {
+    let request_error = |err: String| ClassError::RequestError { err };
+    let response = http::send(&pbinfo_user.client, endpoint)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+    match (response.status(), id) {
+        (StatusCode::NOT_FOUND | StatusCode::FORBIDDEN, Some(id)) => {
+            return Err(ClassError::NotFoundError { id: id.to_string() })
+        }
+        (StatusCode::OK, _) => (),
+        (status, _) => return Err(request_error(format!("Got the status code {status}"))),
+    }
+    let html = http::text(response)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+    if parse::is_login_page(&html) {
+        return Err(ClassError::NotLoggedInError);
+    }
+    Ok(html)
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn list_classes(pbinfo_user: &PbinfoUser) -> Result<Vec<Class>, ClassError> {
+    let endpoint = endpoints::Classes::new().ssid(pbinfo_user.ssid());
+    let html = get_page(&endpoint, None, pbinfo_user).await?;
+    Ok(parse::classes(&html))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn list_assignments(
+    class_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Vec<AssignmentSummary>, ClassError> {
+    let endpoint = endpoints::ClassPage::new(class_id).ssid(pbinfo_user.ssid());
+    let html = get_page(&endpoint, Some(class_id), pbinfo_user).await?;
+    Ok(parse::assignments(class_id, &html))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn get_assignment(
+    assignment_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Assignment, ClassError> {
+    let endpoint = endpoints::AssignmentPage::new(assignment_id).ssid(pbinfo_user.ssid());
+    let html = get_page(&endpoint, Some(assignment_id), pbinfo_user).await?;
+    Ok(parse::assignment(assignment_id, &html))
+}
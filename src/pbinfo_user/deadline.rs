@@ -0,0 +1,71 @@
+use std::{future::Future, time::Duration};
+
+use crate::time::Instant;
+
+/// The point in time an operation has to be done by
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Deadline {
+    at: Instant,
+    timeout: Duration,
+}
+
+impl Deadline {
+    /// `None` without a timeout or with one too far away to represent
+    pub(crate) fn new(timeout: Option<Duration>) -> Option<Self> {
+        let timeout = timeout?;
+        Some(Deadline {
+            at: Instant::now().checked_add(timeout)?,
+            timeout,
+        })
+    }
+
+    /// The timeout the deadline was made with
+    pub(crate) fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// The time left, `None` once the deadline passed
+    pub(crate) fn remaining(&self) -> Option<Duration> {
+        self.at
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    pub(crate) fn at(&self) -> Instant {
+        self.at
+    }
+}
+
+/// Runs `future` until the deadline, failing with the timeout of the
+/// deadline if it passes first. Deadlines are only enforced with the `tokio`
+/// feature
+pub(crate) async fn within<F: Future>(
+    deadline: Option<Deadline>,
+    future: F,
+) -> Result<F::Output, Duration> {
+    #[cfg(feature = "tokio")]
+    if let Some(deadline) = deadline {
+        return tokio::time::timeout_at(deadline.at.into(), future)
+            .await
+            .map_err(|_| deadline.timeout);
+    }
+    #[cfg(not(feature = "tokio"))]
+    let _ = deadline;
+
+    Ok(future.await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_timeouts_are_no_deadline() {
+        assert!(Deadline::new(Some(Duration::MAX)).is_none());
+        assert!(Deadline::new(None).is_none());
+
+        let deadline = Deadline::new(Some(Duration::from_secs(60))).unwrap();
+        assert!(deadline.remaining().is_some());
+    }
+}
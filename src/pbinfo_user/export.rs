@@ -0,0 +1,184 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    endpoints, http,
+    models::{self, SolutionEntry, SolutionSource},
+    pbinfo_user::PbinfoUser,
+};
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Error: Couldn't get the list of solutions!\nGot error:\n{err}")]
+    ListError { err: String },
+    #[error(
+        "Error: Couldn't download the source of the solution {solution_id}!\nGot error:\n{err}"
+    )]
+    DownloadError { solution_id: String, err: String },
+    #[error("Error: Couldn't write the file {file}!\nGot error:\n{error}")]
+    WriteError {
+        file: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+/// A source written by [PbinfoUser::export_my_sources], one entry of the
+/// manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedSource {
+    pub problem_id: String,
+    pub solution_id: String,
+    /// The score as pbinfo sent it
+    pub score: Option<String>,
+    pub submitted_at: Option<String>,
+    /// Where the source was written, relative to the export dir
+    pub path: PathBuf,
+}
+
+async fn get_all_solutions(
+    pbinfo_user: &PbinfoUser,
+) -> Result<Vec<SolutionEntry>, Box<dyn std::error::Error>> {
+    let endpoint = endpoints::UserSolutionList::new(pbinfo_user.user_id()).ssid(pbinfo_user.ssid());
+    let total =
+        http::for_each_solution(
+            http::send(&pbinfo_user.client, &endpoint).await?,
+            |_| Ok(()),
+        )
+        .await?;
+
+    let endpoint = endpoints::UserSolutionList::new(pbinfo_user.user_id())
+        .count(u32::try_from(total)?)
+        .ssid(pbinfo_user.ssid());
+    let mut solutions = Vec::new();
+    http::for_each_solution(
+        http::send(&pbinfo_user.client, &endpoint).await?,
+        |solution| {
+            solutions.push(solution);
+            Ok(())
+        },
+    )
+    .await?;
+    Ok(solutions)
+}
+
+pub(crate) async fn get_source(
+    solution_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let endpoint = endpoints::SolutionSource::new(solution_id).ssid(pbinfo_user.ssid());
+    let response = http::send(&pbinfo_user.client, &endpoint).await?;
+    let json = http::json_body(response).await?;
+    Ok(models::from_json::<SolutionSource>(&json)?.sursa)
+}
+
+/// Whether a name pbinfo sent can be used in a path without pointing outside
+/// of the export dir, same rule as [crate::pbinfo_user::LocalDir::path]
+fn is_safe_component(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn write(file: &Path, contents: &str) -> Result<(), ExportError> {
+    if let Some(parent_dir) = file.parent() {
+        fs::create_dir_all(parent_dir).map_err(|error| ExportError::WriteError {
+            file: parent_dir.to_path_buf(),
+            error,
+        })?;
+    }
+    fs::write(file, contents).map_err(|error| ExportError::WriteError {
+        file: file.to_path_buf(),
+        error,
+    })
+}
+
+/// Downloads every source the user ever sent into `dir/{problem id}/{solution id}.{language}`
+/// and writes a `manifest.json` listing all of them. Solutions with ids or
+/// languages that aren't plain names are skipped
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn export_my_sources(
+    dir: &Path,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Vec<ExportedSource>, ExportError> {
+    let solutions = get_all_solutions(pbinfo_user)
+        .await
+        .map_err(|err| ExportError::ListError {
+            err: err.to_string(),
+        })?;
+    trace_event!(
+        tracing::Level::INFO,
+        solutions = solutions.len(),
+        "exporting sources"
+    );
+
+    let mut manifest = Vec::with_capacity(solutions.len());
+    for solution in solutions {
+        let Some(solution_id) = solution.solution_id() else {
+            continue;
+        };
+        let problem_id = solution
+            .problem_id()
+            .unwrap_or_else(|| "unknown".to_string());
+        let extension = solution.limbaj.as_deref().unwrap_or("cpp");
+        if ![problem_id.as_str(), solution_id.as_str(), extension]
+            .iter()
+            .all(|name| is_safe_component(name))
+        {
+            trace_event!(
+                tracing::Level::WARN,
+                problem_id,
+                solution_id,
+                extension,
+                "skipping a solution that would be written outside of the export dir"
+            );
+            continue;
+        }
+
+        let source = get_source(&solution_id, pbinfo_user).await.map_err(|err| {
+            ExportError::DownloadError {
+                solution_id: solution_id.clone(),
+                err: err.to_string(),
+            }
+        })?;
+
+        let path = Path::new(&problem_id).join(format!("{solution_id}.{extension}"));
+        write(&dir.join(&path), &source)?;
+
+        manifest.push(ExportedSource {
+            problem_id,
+            solution_id,
+            score: solution.scor.clone(),
+            submitted_at: solution.submitted_at(),
+            path,
+        });
+    }
+
+    write(
+        &dir.join(MANIFEST_FILE_NAME),
+        &serde_json::to_string_pretty(&manifest).unwrap(),
+    )?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_components_stay_in_the_export_dir() {
+        for name in ["1234", "cpp", "a-b_C9"] {
+            assert!(is_safe_component(name), "{name}");
+        }
+        for name in ["", "..", "../..", "a/b", "a\\b", "/etc", "c.pp", "1 2"] {
+            assert!(!is_safe_component(name), "{name}");
+        }
+    }
+}
@@ -1,9 +1,16 @@
-use std::collections::HashMap;
+use std::time::Duration;
 
 use reqwest::{header::InvalidHeaderValue, Response};
 use thiserror::Error;
 
-use crate::pbinfo_user::PbinfoUser;
+use crate::{
+    endpoints::{self, Endpoint},
+    http::{self, BodyChunks},
+    models::{self, LoginResponse},
+    parse::{self, PageUser, ParseError},
+    pbinfo_user::{deadline::Deadline, PbinfoUser},
+    time::SystemTime,
+};
 
 #[derive(Error, Debug)]
 pub enum LoginError {
@@ -21,10 +28,188 @@ pub enum LoginError {
     ResponseParseError { err: String },
     #[error("Error: Couldn't parse the following text to a json:\n{json}\nGot error:\n{err}")]
     JsonParseError { json: String, err: String },
+    #[deprecated(
+        note = "pbinfo rejecting the credentials is now LoginError::IncorrectCredentialsError"
+    )]
     #[error("Error: Utilizator / parola incorecte!")]
     IncorrectUsernameOrPasswordError,
+    #[error("Error: Utilizator / parola incorecte!\npbinfo didn't accept the {kind} `{login}` with this password!{}", kind.other_hint())]
+    IncorrectCredentialsError { login: String, kind: LoginKind },
+    #[error("Error: `{login}` is neither an email nor a pbinfo username!")]
+    InvalidLoginError { login: String },
+    #[error("Error: Tried logging in too many times, pbinfo blocked the login for a while!")]
+    TooManyAttempts {
+        /// How long pbinfo asked to wait, if it said
+        retry_after: Option<Duration>,
+    },
     #[error("Error: There was no user id found in the body of pbinfo!")]
     NoUserIdError,
+    #[error("Error: The login form kept expiring, gave up after {attempts} attempts!")]
+    FormTokenExpiredError { attempts: u32 },
+    #[error("Error: Logging in took longer than {timeout:?}!")]
+    TimeoutError { timeout: Duration },
+    #[error("Error: The SSID cookie doesn't belong to a logged in session!\nLog in on pbinfo again and copy the new cookie!")]
+    InvalidSessionError,
+}
+
+const FORM_EXPIRED: &str = "Formularul a expirat. Încearcă din nou!";
+const INCORRECT_CREDENTIALS: &str = "Utilizator/parola incorecte!";
+
+/// What a user logs in with, pbinfo accepts both. See [PbinfoUser::login_kind]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoginKind {
+    Email,
+    Username,
+}
+
+impl LoginKind {
+    /// Tells an email from a username, `None` if `login` is neither. Emails
+    /// need something before the `@` and a dot in the domain, usernames
+    /// only have letters, digits, `_`, `.` and `-`
+    pub fn detect(login: &str) -> Option<Self> {
+        let login = login.trim();
+        if login.is_empty() || login.contains(char::is_whitespace) {
+            return None;
+        }
+        if let Some((local, domain)) = login.split_once('@') {
+            let valid_domain = domain.contains('.')
+                && !domain.contains('@')
+                && domain.split('.').all(|part| !part.is_empty());
+            return (!local.is_empty() && valid_domain).then_some(LoginKind::Email);
+        }
+        login
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'))
+            .then_some(LoginKind::Username)
+    }
+
+    /// Points at the other kind when pbinfo rejects this one, the account
+    /// might have been made with it
+    fn other_hint(&self) -> &'static str {
+        match self {
+            LoginKind::Email => "\nIf the email isn't the one of the account, try logging in with the username instead!",
+            LoginKind::Username => "\nIf the username is misspelled, try logging in with the email of the account instead!",
+        }
+    }
+}
+
+impl std::fmt::Display for LoginKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LoginKind::Email => "email",
+            LoginKind::Username => "username",
+        })
+    }
+}
+
+/// How long to wait when pbinfo blocks the login without saying for how long
+#[cfg(feature = "tokio")]
+const DEFAULT_LOCKOUT_WAIT: Duration = Duration::from_secs(60);
+
+/// Whether pbinfo answered that the login is blocked after too many attempts,
+/// like `Prea multe încercări de autentificare. Mai așteaptă 5 minute`
+fn is_lockout(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["prea multe", "prea des", "blocat"]
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// How [PbinfoUser::login_with] behaves
+#[derive(Debug, Clone)]
+pub struct LoginOptions {
+    /// How many times the login is retried with the new form token after
+    /// pbinfo says the form expired. Defaults to 1
+    pub max_form_token_retries: u32,
+    /// Throw away the saved session and log in from scratch, even if the
+    /// session is still logged in
+    pub force_fresh_session: bool,
+    /// How long the whole login can take, including retries. `None` waits
+    /// for as long as the requests do
+    pub timeout: Option<Duration>,
+    /// How many times to sleep for as long as pbinfo asks and try again
+    /// after a [LoginError::TooManyAttempts]. The waiting counts towards the
+    /// timeout. Defaults to 0
+    #[cfg(feature = "tokio")]
+    pub lockout_retries: u32,
+}
+
+impl Default for LoginOptions {
+    fn default() -> Self {
+        LoginOptions {
+            max_form_token_retries: 1,
+            force_fresh_session: false,
+            timeout: None,
+            #[cfg(feature = "tokio")]
+            lockout_retries: 0,
+        }
+    }
+}
+
+/// The time left until the deadline, fails once it passed
+fn remaining(deadline: Option<Deadline>) -> Result<Option<Duration>, LoginError> {
+    let Some(deadline) = deadline else {
+        return Ok(None);
+    };
+    match deadline.remaining() {
+        Some(remaining) => Ok(Some(remaining)),
+        None => Err(LoginError::TimeoutError {
+            timeout: deadline.timeout(),
+        }),
+    }
+}
+
+async fn send<E: Endpoint>(
+    pbinfo_user: &PbinfoUser,
+    endpoint: &E,
+    deadline: Option<Deadline>,
+) -> Result<Response, LoginError> {
+    http::send_with_timeout(&pbinfo_user.client, endpoint, remaining(deadline)?)
+        .await
+        .map_err(|err| match deadline {
+            Some(deadline) if err.is_timeout() => LoginError::TimeoutError {
+                timeout: deadline.timeout(),
+            },
+            _ => LoginError::RequestSendError {
+                url: endpoint.url(),
+                err: err.to_string(),
+            },
+        })
+}
+
+/// Who is logged in, returned by [PbinfoUser::login]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionInfo {
+    pub user_id: String,
+    /// The name pbinfo shows for the user, if the page had one
+    pub display_name: Option<String>,
+    /// When the session was created, `None` if it was created by someone
+    /// else (like a session loaded from the config of an older version)
+    pub session_started_at: Option<SystemTime>,
+    /// `true` if the saved session was still logged in and no login
+    /// request was sent
+    pub was_cached: bool,
+}
+
+/// What [PbinfoUser::login] returns, the user id and name to greet the user
+/// with and whether the session was reused
+pub type LoginOutcome = SessionInfo;
+
+impl SessionInfo {
+    fn new(pbinfo_user: &PbinfoUser, user: PageUser, was_cached: bool) -> Self {
+        SessionInfo {
+            user_id: user.id,
+            display_name: user.display_name,
+            session_started_at: pbinfo_user.session().session_started_at,
+            was_cached,
+        }
+    }
+
+    /// Whether the session was already logged in, so no credentials were
+    /// sent. Same as [SessionInfo::was_cached]
+    pub fn already_logged_in(&self) -> bool {
+        self.was_cached
+    }
 }
 
 impl From<InvalidHeaderValue> for LoginError {
@@ -39,173 +224,495 @@ fn try_get_ssid(response: &reqwest::Response) -> Result<String, LoginError> {
     let new_ssid_header = response
         .headers()
         .get("set-cookie")
-        .ok_or_else(|| LoginError::NoCookieError)?
+        .ok_or(LoginError::NoCookieError)?
         .to_str()
         .map_err(|err| LoginError::HeaderParseError {
-            err: format!(
-                "Couldn't make a string out of the HeaderValue, got error: {}",
-                err.to_string()
-            ),
+            err: format!("Couldn't make a string out of the HeaderValue, got error: {err}"),
         })?;
 
-    let new_ssid_cookie =
-        new_ssid_header
-            .split(";")
-            .next()
-            .ok_or_else(|| LoginError::HeaderParseError {
-                err: format!(
-                    "Couldn't find anything after the first ';' in the header:\n{new_ssid_header}"
-                ),
-            })?;
-
-    new_ssid_cookie
-        .split("=")
-        .nth(1)
-        .ok_or_else(|| LoginError::CookieParseError {
-            cookie: new_ssid_cookie.to_string(),
-            err: "Couldn't find anything after the '=' sign!".to_string(),
-        })
-        .map(|x| x.to_string())
+    parse::ssid_cookie(new_ssid_header).map_err(|err| match err {
+        ParseError::CookieError { cookie, err } => LoginError::CookieParseError { cookie, err },
+        err => LoginError::HeaderParseError {
+            err: err.to_string(),
+        },
+    })
 }
 
-async fn get_login_response(pbinfo_user: &mut PbinfoUser) -> Result<Response, LoginError> {
-    let client: reqwest::Client =
-        reqwest::Client::builder()
-            .build()
-            .map_err(|err| LoginError::RequestBuildError {
-                err: err.to_string(),
-            })?;
-
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert("Origin", "https://www.pbinfo.ro".parse()?);
-    headers.insert("Referer", "https://www.pbinfo.ro/".parse()?);
-    headers.insert("Cookie", format!("SSID={}", pbinfo_user.ssid).parse()?);
-
-    // 'Content-Type: application/x-www-form-urlencoded; charset=UTF-8'
-
-    let mut form_data = HashMap::new();
-    form_data.insert("user", pbinfo_user.email.as_str());
-    form_data.insert("parola", pbinfo_user.password.as_str());
-    form_data.insert("form_token", pbinfo_user.form_token.as_str());
-
-    let login_url = "https://www.pbinfo.ro/ajx-module/php-login.php";
-    let response = client
-        .request(reqwest::Method::POST, login_url)
-        .headers(headers)
-        .form(&form_data)
-        .send()
-        .await
-        .map_err(|err| LoginError::RequestSendError {
-            url: login_url.to_string(),
-            err: err.to_string(),
-        })?;
-    Ok(response)
+async fn get_login_response(
+    pbinfo_user: &mut PbinfoUser,
+    deadline: Option<Deadline>,
+) -> Result<Response, LoginError> {
+    let endpoint = endpoints::Login::new(&pbinfo_user.email, &pbinfo_user.password)
+        .form_token(pbinfo_user.form_token())
+        .ssid(pbinfo_user.ssid());
+
+    send(pbinfo_user, &endpoint, deadline).await
 }
 
-async fn get_login_response_body(
-    response: reqwest::Response,
-) -> Result<serde_json::Value, LoginError> {
-    let text = response
-        .text()
+async fn get_login_response_body(response: reqwest::Response) -> Result<LoginResponse, LoginError> {
+    let text = http::text(response)
         .await
         .map_err(|err| LoginError::ResponseParseError {
             err: err.to_string(),
         })?;
 
-    let table: serde_json::Value =
-        serde_json::from_str(&text).map_err(|err| LoginError::JsonParseError {
-            json: text,
-            err: err.to_string(),
-        })?;
+    models::from_json(&text).map_err(|err| LoginError::JsonParseError {
+        json: text,
+        err: err.to_string(),
+    })
+}
 
-    Ok(table)
+fn is_logged_in_user(user: &PageUser) -> bool {
+    user.id != "0" && !user.id.is_empty()
 }
 
-/// Returns the user id for a user. This must be scraped out of the
+/// Returns the logged in user. This must be scraped out of the
 /// source html with a bit of rust magic
-async fn get_user_id(pbinfo_user: &mut PbinfoUser) -> Result<String, LoginError> {
-    let client: reqwest::Client =
-        reqwest::Client::builder()
-            .build()
-            .map_err(|err| LoginError::RequestBuildError {
-                err: err.to_string(),
-            })?;
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, ret, err))]
+pub(super) async fn get_page_user(
+    pbinfo_user: &PbinfoUser,
+    deadline: Option<Deadline>,
+) -> Result<PageUser, LoginError> {
+    get_homepage(pbinfo_user, deadline, false)
+        .await
+        .map(|(user, _)| user)
+}
 
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert("Origin", "https://www.pbinfo.ro".parse()?);
-    headers.insert("Referer", "https://www.pbinfo.ro/".parse()?);
-    headers.insert("Cookie", format!("SSID={}", pbinfo_user.ssid).parse()?);
+/// Returns the user of the homepage and, with `with_form_token`, the form
+/// token of the same page if the session turned out to be logged out, so
+/// logging in needs a single download of the homepage
+async fn get_homepage(
+    pbinfo_user: &PbinfoUser,
+    deadline: Option<Deadline>,
+    with_form_token: bool,
+) -> Result<(PageUser, Option<String>), LoginError> {
+    let parse_error = |err: String| LoginError::ResponseParseError { err };
+    let endpoint = endpoints::Homepage::new().ssid(pbinfo_user.ssid());
 
-    let url = "https://www.pbinfo.ro".to_string();
+    let response = send(pbinfo_user, &endpoint, deadline).await?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(str::to_string);
 
-    let response = client
-        .request(reqwest::Method::GET, url.as_str())
-        .headers(headers)
-        .send()
+    // the id is near the top of the page, so stop downloading as soon
+    // as it shows up, unless the form token further down is needed too
+    let mut scanner = parse::PageUserScanner::for_content_type(content_type.as_deref());
+    let mut user = None;
+    let mut body = Vec::new();
+    let mut chunks = BodyChunks::new(response);
+    while let Some(chunk) = chunks
+        .next()
         .await
-        .map_err(|e| LoginError::RequestSendError {
-            url: url,
-            err: e.to_string(),
-        })?;
+        .map_err(|err| parse_error(err.to_string()))?
+    {
+        if with_form_token {
+            body.extend_from_slice(&chunk);
+        }
+        if user.is_none() {
+            user = scanner.feed(&chunk);
+        }
+        if user
+            .as_ref()
+            .is_some_and(|user| !with_form_token || is_logged_in_user(user))
+        {
+            break;
+        }
+    }
 
-    let body = response
-        .text()
+    let user = match user {
+        Some(user) => user,
+        None => scanner
+            .finish()
+            .map_err(|err| parse_error(err.to_string()))?,
+    };
+    if !with_form_token || is_logged_in_user(&user) {
+        return Ok((user, None));
+    }
+    let html = parse::decode(&body, content_type.as_deref());
+    match parse::form_token(&html) {
+        Ok(form_token) => Ok((user, Some(form_token))),
+        Err(_err) => {
+            trace_event!(
+                tracing::Level::WARN,
+                error = %_err,
+                "couldn't get a form token, using the saved one"
+            );
+            Ok((user, None))
+        }
+    }
+}
+
+/// Logs in a copy of the user and moves its new session into the user, for
+/// retrying a request after the session expired. The user is behind a
+/// shared reference, so later requests and [PbinfoUser::save_config] see
+/// the new session too
+pub(super) async fn relogin(pbinfo_user: &PbinfoUser) -> Result<(), LoginError> {
+    trace_event!(
+        tracing::Level::INFO,
+        "the session expired, logging in again"
+    );
+    let mut user = PbinfoUser::builder()
+        .email(&pbinfo_user.email)
+        .password(&pbinfo_user.password)
+        .client(pbinfo_user.client.clone())
+        .auto_relogin(false)
+        .build();
+    user.problem_cache = pbinfo_user.problem_cache.clone();
+    user.in_memory = pbinfo_user.in_memory;
+    let options = LoginOptions {
+        force_fresh_session: true,
+        ..Default::default()
+    };
+    login(&mut user, &options).await?;
+    pbinfo_user.adopt_session(user.session());
+    Ok(())
+}
+
+/// A user for an SSID cookie taken out of a browser, after checking that
+/// the session is logged in
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+pub async fn from_ssid(ssid: &str) -> Result<PbinfoUser, LoginError> {
+    // there is no password to log in again with
+    let mut pbinfo_user = PbinfoUser::builder().ssid(ssid).auto_relogin(false).build();
+    let user = get_page_user(&pbinfo_user, None).await?;
+    if user.id == "0" || user.id.is_empty() {
+        return Err(LoginError::InvalidSessionError);
+    }
+    pbinfo_user.session_mut().user_id = user.id;
+    if let Some(username) = user.username {
+        pbinfo_user.email = username;
+    }
+    Ok(pbinfo_user)
+}
+
+/// Ends the session on pbinfo and forgets it, in the saved config too
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+pub async fn logout(pbinfo_user: &mut PbinfoUser) -> Result<(), LoginError> {
+    let endpoint = endpoints::Logout::new().ssid(pbinfo_user.ssid());
+    // the session is forgotten even if pbinfo couldn't be reached
+    let result = send(pbinfo_user, &endpoint, None).await.map(|_| ());
+    pbinfo_user.reset_session();
+    #[cfg(feature = "config")]
+    pbinfo_user.forget_saved_session();
+    result
+}
+
+/// Whether the session of the user is logged in, without logging in
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+pub async fn is_logged_in(pbinfo_user: &PbinfoUser) -> Result<bool, LoginError> {
+    let user = get_page_user(pbinfo_user, None).await?;
+    Ok(user.id != "0" && !user.id.is_empty())
+}
+
+/// Fetches the form token pbinfo expects with the next form post, so the
+/// login doesn't bounce off an expired form
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+pub async fn refresh_form_token(pbinfo_user: &mut PbinfoUser) -> Result<String, LoginError> {
+    refresh_form_token_helper(pbinfo_user, None).await
+}
+
+async fn refresh_form_token_helper(
+    pbinfo_user: &mut PbinfoUser,
+    deadline: Option<Deadline>,
+) -> Result<String, LoginError> {
+    let form_token = fetch_form_token(pbinfo_user, deadline).await?;
+    pbinfo_user.session_mut().form_token = form_token.clone();
+    Ok(form_token)
+}
+
+pub(super) async fn fetch_form_token(
+    pbinfo_user: &PbinfoUser,
+    deadline: Option<Deadline>,
+) -> Result<String, LoginError> {
+    let endpoint = endpoints::Homepage::new().ssid(pbinfo_user.ssid());
+    let response = send(pbinfo_user, &endpoint, deadline).await?;
+    let html = http::text(response)
         .await
         .map_err(|err| LoginError::ResponseParseError {
             err: err.to_string(),
         })?;
 
-    // we are looking for the user id in a string that looks something
-    // like this:
-    // {page html}
-    // user_autentificat = {"id":XXXXXX,
-    // {continuation page html}
-    let marker = "user_autentificat = {\"id\":";
-    let before =
-        body.split(marker)
-            .skip(1)
-            .next()
-            .ok_or_else(|| LoginError::ResponseParseError {
-                err: "Didn't find anything after user_autentificat = {\"id\":".to_string(),
-            })?;
-
-    let user_id: String = before.chars().take_while(|&c| c != ',').collect();
+    parse::form_token(&html).map_err(|err| LoginError::ResponseParseError {
+        err: err.to_string(),
+    })
+}
 
-    Ok(user_id)
+/// Stores the new session and returns who it belongs to
+async fn start_session(
+    pbinfo_user: &mut PbinfoUser,
+    ssid: String,
+    deadline: Option<Deadline>,
+) -> Result<SessionInfo, LoginError> {
+    pbinfo_user.session_mut().ssid = ssid;
+    pbinfo_user.session_mut().session_started_at = Some(SystemTime::now());
+    let user = get_page_user(pbinfo_user, deadline).await?;
+    pbinfo_user.session_mut().user_id = user.id.clone();
+    Ok(SessionInfo::new(pbinfo_user, user, false))
 }
 
 /// Makes sure a user is logged in, if not logs in the user with the
 /// provided credentials
-pub async fn login(pbinfo_user: &mut PbinfoUser) -> Result<(), LoginError> {
-    let user_id = get_user_id(pbinfo_user).await?;
-    if user_id != "0" && user_id != "" {
-        return Ok(());
-    }
-    pbinfo_user.user_id = user_id;
-
-    let response = get_login_response(pbinfo_user).await?;
-    let maybe_ssid = try_get_ssid(&response);
-
-    let val = get_login_response_body(response).await?;
-    if val["raspuns"] == "Formularul a expirat. Încearcă din nou!" {
-        pbinfo_user.form_token = val["form_token"]
-            .to_string()
-            .trim_start_matches("\"")
-            .trim_end_matches("\"")
-            .to_string();
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+pub async fn login(
+    pbinfo_user: &mut PbinfoUser,
+    options: &LoginOptions,
+) -> Result<SessionInfo, LoginError> {
+    let deadline = Deadline::new(options.timeout);
+
+    // if the page has no token the random one is sent and the expired
+    // form retry takes care of it
+    if options.force_fresh_session {
+        pbinfo_user.reset_session();
+        if let Err(_err) = refresh_form_token_helper(pbinfo_user, deadline).await {
+            trace_event!(
+                tracing::Level::WARN,
+                error = %_err,
+                "couldn't get a form token, using the saved one"
+            );
+        }
     } else {
-        pbinfo_user.ssid = maybe_ssid?;
-        pbinfo_user.user_id = get_user_id(pbinfo_user).await?;
-        return Ok(());
+        // the user and the form token come out of the same homepage
+        let (user, form_token) = get_homepage(pbinfo_user, deadline, true).await?;
+        pbinfo_user.session_mut().user_id = user.id.clone();
+        if is_logged_in_user(&user) {
+            trace_event!(
+                tracing::Level::INFO,
+                user_id = user.id,
+                "session already logged in"
+            );
+            return Ok(SessionInfo::new(pbinfo_user, user, true));
+        }
+        if let Some(form_token) = form_token {
+            pbinfo_user.session_mut().form_token = form_token;
+        }
     }
 
-    let response = get_login_response(pbinfo_user).await?;
-    let maybe_ssid = try_get_ssid(&response);
-    let val = get_login_response_body(response).await?;
-    if val["raspuns"] == "Utilizator/parola incorecte!" {
-        return Err(LoginError::IncorrectUsernameOrPasswordError);
+    submit_login_waiting(pbinfo_user, options, deadline).await
+}
+
+/// Same as [submit_login], but waits out the lockout of pbinfo if `options`
+/// allow it
+async fn submit_login_waiting(
+    pbinfo_user: &mut PbinfoUser,
+    options: &LoginOptions,
+    deadline: Option<Deadline>,
+) -> Result<SessionInfo, LoginError> {
+    #[cfg(not(feature = "tokio"))]
+    return submit_login(pbinfo_user, options, deadline).await;
+
+    #[cfg(feature = "tokio")]
+    let mut retries = 0;
+    #[cfg(feature = "tokio")]
+    loop {
+        match submit_login(pbinfo_user, options, deadline).await {
+            Err(LoginError::TooManyAttempts { retry_after })
+                if retries < options.lockout_retries =>
+            {
+                let wait = retry_after.unwrap_or(DEFAULT_LOCKOUT_WAIT);
+                if remaining(deadline)?.is_some_and(|remaining| remaining < wait) {
+                    return Err(LoginError::TooManyAttempts { retry_after });
+                }
+                trace_event!(
+                    tracing::Level::INFO,
+                    ?wait,
+                    "login blocked, waiting before trying again"
+                );
+                retries += 1;
+                tokio::time::sleep(wait).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Checks the session and gets the user id and a new form token out of a
+/// single download of the homepage, logging in only if the session turned
+/// out to be logged out
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+pub async fn prepare(pbinfo_user: &mut PbinfoUser) -> Result<SessionInfo, LoginError> {
+    let (user, form_token) = get_homepage(pbinfo_user, None, true).await?;
+    pbinfo_user.session_mut().user_id = user.id.clone();
+    if is_logged_in_user(&user) {
+        return Ok(SessionInfo::new(pbinfo_user, user, true));
+    }
+    if let Some(form_token) = form_token {
+        pbinfo_user.session_mut().form_token = form_token;
+    }
+
+    submit_login(pbinfo_user, &LoginOptions::default(), None).await
+}
+
+/// Posts the credentials, retrying as long as the form expires
+async fn submit_login(
+    pbinfo_user: &mut PbinfoUser,
+    options: &LoginOptions,
+    deadline: Option<Deadline>,
+) -> Result<SessionInfo, LoginError> {
+    let kind =
+        LoginKind::detect(&pbinfo_user.email).ok_or_else(|| LoginError::InvalidLoginError {
+            login: pbinfo_user.email.clone(),
+        })?;
+    let mut retries = 0;
+    loop {
+        let response = get_login_response(pbinfo_user, deadline).await?;
+        let maybe_ssid = try_get_ssid(&response);
+        let val = get_login_response_body(response).await?;
+
+        match val.raspuns.as_str() {
+            FORM_EXPIRED if retries < options.max_form_token_retries => {
+                trace_event!(
+                    tracing::Level::INFO,
+                    "login form expired, retrying with the new form token"
+                );
+                retries += 1;
+                pbinfo_user.session_mut().form_token = val.form_token.unwrap_or_default();
+            }
+            FORM_EXPIRED => {
+                return Err(LoginError::FormTokenExpiredError {
+                    attempts: retries + 1,
+                })
+            }
+            message if is_lockout(message) => {
+                trace_event!(tracing::Level::WARN, "pbinfo blocked the login");
+                return Err(LoginError::TooManyAttempts {
+                    retry_after: parse::retry_after(message),
+                });
+            }
+            INCORRECT_CREDENTIALS => {
+                trace_event!(tracing::Level::WARN, "pbinfo rejected the credentials");
+                return Err(LoginError::IncorrectCredentialsError {
+                    login: pbinfo_user.email.clone(),
+                    kind,
+                });
+            }
+            _ => return start_session(pbinfo_user, maybe_ssid?, deadline).await,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::test_support::{self, LOGGED_IN, LOGGED_OUT};
+
+    const LOGIN_PATH: &str = "/ajx-module/php-login.php";
+
+    fn logged_out_user(client: crate::PbinfoClient) -> PbinfoUser {
+        PbinfoUser::builder()
+            .email("ion@example.com")
+            .password("parola")
+            .ssid("old-ssid")
+            .client(client)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn start_session_sets_when_the_session_started() {
+        let (client, sent) = test_support::client(|_| test_support::ok(LOGGED_IN));
+        let mut pbinfo_user = PbinfoUser::builder().client(client).build();
+
+        let info = start_session(&mut pbinfo_user, "new-ssid".to_string(), None)
+            .await
+            .unwrap();
+        assert!(info.session_started_at.is_some());
+        assert_eq!(info.user_id, "123456");
+        assert_eq!(pbinfo_user.ssid(), "new-ssid");
+        assert_eq!(
+            pbinfo_user.session().session_started_at,
+            info.session_started_at
+        );
+
+        let requests = sent.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, reqwest::Method::GET);
+        assert_eq!(requests[0].path(), "/");
+        let cookie = requests[0].headers.get(reqwest::header::COOKIE).unwrap();
+        assert!(cookie.to_str().unwrap().contains("new-ssid"));
+    }
+
+    #[tokio::test]
+    async fn expired_forms_are_posted_again_with_the_new_token() {
+        let expired = AtomicU32::new(2);
+        let (client, sent) = test_support::client(move |request| {
+            if request.path() != LOGIN_PATH {
+                return match test_support::has_session(request, "new-ssid") {
+                    true => test_support::ok(LOGGED_IN),
+                    false => test_support::ok(LOGGED_OUT),
+                };
+            }
+            match expired.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            }) {
+                Ok(_) => test_support::login_answer(FORM_EXPIRED, None),
+                Err(_) => test_support::login_answer("", Some("new-ssid")),
+            }
+        });
+        let mut pbinfo_user = logged_out_user(client);
+        let options = LoginOptions {
+            max_form_token_retries: 2,
+            ..Default::default()
+        };
+
+        let info = login(&mut pbinfo_user, &options).await.unwrap();
+        assert!(!info.was_cached);
+        assert_eq!(info.user_id, "123456");
+        assert_eq!(pbinfo_user.ssid(), "new-ssid");
+        assert_eq!(pbinfo_user.form_token(), "new-token");
+        let logins = sent
+            .requests()
+            .iter()
+            .filter(|request| request.path() == LOGIN_PATH)
+            .count();
+        assert_eq!(logins, 3);
+    }
+
+    #[tokio::test]
+    async fn expired_forms_give_up_after_the_retries() {
+        let (client, sent) = test_support::client(|request| match request.path() {
+            LOGIN_PATH => test_support::login_answer(FORM_EXPIRED, None),
+            _ => test_support::ok(LOGGED_OUT),
+        });
+        let mut pbinfo_user = logged_out_user(client);
+        let options = LoginOptions {
+            max_form_token_retries: 1,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            login(&mut pbinfo_user, &options).await,
+            Err(LoginError::FormTokenExpiredError { attempts: 2 })
+        ));
+        assert_eq!(sent.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn logged_in_sessions_are_kept() {
+        let (client, sent) = test_support::client(|_| test_support::ok(LOGGED_IN));
+        let mut pbinfo_user = logged_out_user(client);
+
+        let info = login(&mut pbinfo_user, &LoginOptions::default())
+            .await
+            .unwrap();
+        assert!(info.already_logged_in());
+        assert_eq!(pbinfo_user.ssid(), "old-ssid");
+        assert_eq!(sent.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn wrong_credentials_are_reported() {
+        let (client, _) = test_support::client(|request| match request.path() {
+            LOGIN_PATH => test_support::login_answer(INCORRECT_CREDENTIALS, None),
+            _ => test_support::ok(LOGGED_OUT),
+        });
+        let mut pbinfo_user = logged_out_user(client);
+
+        assert!(matches!(
+            login(&mut pbinfo_user, &LoginOptions::default()).await,
+            Err(LoginError::IncorrectCredentialsError { .. })
+        ));
     }
-    pbinfo_user.ssid = maybe_ssid?;
-    pbinfo_user.user_id = get_user_id(pbinfo_user).await?;
-    Ok(())
 }
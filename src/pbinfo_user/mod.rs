@@ -1,17 +1,72 @@
-use std::{fs, path::PathBuf};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{PoisonError, RwLock};
 
-use directories::ProjectDirs;
+use crate::user_config::{ConfigStore, UserConfig};
+use crate::{
+    telemetry::{ContextError, ErrorContext, ReportErr, ReportFuture},
+    time::SystemTime,
+    PbinfoClient,
+};
 
+#[cfg(feature = "tokio")]
+mod batch;
+#[cfg(feature = "blocking")]
+mod blocking;
+#[cfg(feature = "bundle")]
+mod bundle;
+mod cache;
+mod class;
+mod deadline;
+mod export;
 mod login;
+mod problem;
+mod profile;
+mod ranking;
+mod register;
 mod score;
+mod self_test;
+mod solutions;
 mod solve;
+mod sources;
 mod upload;
 
-pub use login::LoginError;
+#[cfg(feature = "tokio")]
+pub use batch::{
+    BatchEntry, BatchOptions, BatchOutcome, BatchReport, SolveReport, UploadManyOptions,
+};
+#[cfg(feature = "bundle")]
+pub use bundle::{BundleError, BundleManifest, BundleSolutions};
+pub use cache::ProblemCache;
+pub use class::{Assignment, AssignmentSummary, Class, ClassError};
+pub use export::{ExportError, ExportedSource};
+pub use login::{LoginError, LoginKind, LoginOptions, LoginOutcome, SessionInfo};
+pub use problem::{
+    Difficulty, Example, Problem, ProblemError, ProblemList, ProblemMeta, ProblemSummary,
+    SearchFilters,
+};
+pub use profile::{
+    Achievement, CategoryProgress, ProblemProgress, ProfileError, ProgressSummary, UserProfile,
+    UserRef,
+};
 use rand::random_iter;
-pub use score::{GetScoreError, ScoreStatus, TopSolutionResponseType};
-pub use solve::SolveError;
-pub use upload::UploadError;
+pub use ranking::{RankingEntry, RankingError, RankingPage, RankingScope};
+pub use register::{RegisterError, RegistrationRequest, MIN_PASSWORD_LEN, USERNAME_LEN};
+#[cfg(feature = "tokio")]
+pub use score::UploadAndWaitError;
+pub use score::{Evaluation, GetScoreError, PollOptions, ScoreStatus, TopSolutionResponseType};
+pub use self_test::{SelfTestCheck, SelfTestReport};
+pub use solutions::{SolutionPage, SolutionSummary, SolutionsError, SOLUTIONS_PER_PAGE};
+pub use solve::{custom_solve, SolveError, SolveOptions, SolveOutcome};
+#[cfg(feature = "tokio")]
+pub use solve::{SolveAndVerifyError, VerifyOptions};
+#[cfg(feature = "bundled-solutions")]
+pub use sources::BundledSolutions;
+pub use sources::{
+    GithubRepos, LocalDir, SolutionSource, SolutionSourceError, SolutionSources, SolutionsIndex,
+    SourceFuture, CODULLUIANDREI_URL, DEFAULT_INDEX_TTL, DEFAULT_LOCAL_LAYOUT,
+};
+pub use upload::{Language, UploadError, UploadOptions};
 
 #[derive(thiserror::Error, Debug)]
 pub enum PbinfoUserError {
@@ -24,24 +79,54 @@ pub enum PbinfoUserError {
         file: PathBuf,
         error: std::io::Error,
     },
+    #[cfg(feature = "config")]
     #[error("Got error while parsing config!\nError was: {error}")]
     TomlParseError { error: toml::de::Error },
+    #[error("Got error from the config store!\nError was: {err}")]
+    StoreError { err: String },
+    #[error("The environment variable {var} isn't set!")]
+    MissingEnvError { var: &'static str },
+    #[error("There is no profile named `{profile}` in the config!")]
+    MissingProfileError { profile: String },
+    #[error("The user only lives in memory, its config can't be saved to a file!")]
+    InMemoryError,
+    #[error(
+        "Got an invalid value for `{key}` in the config: {value}\nAccepted values are {range}"
+    )]
+    InvalidValueError {
+        key: &'static str,
+        value: String,
+        range: String,
+    },
+}
+
+/// The cookie and the tokens a user is logged in with
+#[derive(serde::Deserialize, Debug, serde::Serialize, Clone, Default)]
+pub(crate) struct Session {
+    pub(crate) ssid: String,
+    pub(crate) form_token: String,
+    pub(crate) user_id: String,
+    #[serde(default)]
+    pub(crate) session_started_at: Option<SystemTime>,
 }
 
 #[derive(serde::Deserialize, Debug, serde::Serialize)]
 pub struct PbinfoUser {
     email: String,
     password: String,
-    ssid: String,
-    form_token: String,
-    user_id: String,
-}
-
-fn get_proj_dir() -> Result<ProjectDirs, PbinfoUserError> {
-    Ok(
-        directories::ProjectDirs::from("dev", "insertokername", "pbinfo-api")
-            .ok_or_else(|| PbinfoUserError::NoHomeDirError)?,
-    )
+    /// Behind a lock so a user shared by reference keeps the session it
+    /// logged in again with after the old one expired
+    #[serde(flatten)]
+    session: RwLock<Session>,
+    #[serde(skip)]
+    client: PbinfoClient,
+    #[serde(skip)]
+    problem_cache: Option<ProblemCache>,
+    #[serde(skip)]
+    in_memory: bool,
+    /// Negated so that users read out of json also log in again by default
+    #[serde(skip)]
+    no_relogin: bool,
 }
 
 fn make_random_form_token() -> String {
@@ -76,106 +161,705 @@ fn make_random_form_ssid() -> String {
     }
 }
 
-const CONFIG_FILE_NAME: &str = "pbinfo.toml";
+/// The environment variable [PbinfoUser::from_env] reads the email from
+pub const EMAIL_VAR: &str = "PBINFO_EMAIL";
+/// The environment variable [PbinfoUser::from_env] reads the password from
+pub const PASSWORD_VAR: &str = "PBINFO_PASSWORD";
+/// The environment variable [PbinfoUser::from_env] reads the optional
+/// session cookie from
+pub const SSID_VAR: &str = "PBINFO_SSID";
+
+/// Builder for a [PbinfoUser], made with [PbinfoUser::builder]
+#[derive(Debug, Default)]
+pub struct PbinfoUserBuilder {
+    email: String,
+    password: String,
+    ssid: Option<String>,
+    client: Option<PbinfoClient>,
+    problem_cache: Option<ProblemCache>,
+    in_memory: bool,
+    no_relogin: bool,
+}
+
+impl PbinfoUserBuilder {
+    /// The email or username of the account, the spaces around it are
+    /// dropped. See [LoginKind]
+    pub fn email(mut self, email: impl Into<String>) -> Self {
+        self.email = email.into().trim().to_string();
+        self
+    }
+
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.password = password.into();
+        self
+    }
+
+    /// Starts from an existing session cookie instead of a random one
+    pub fn ssid(mut self, ssid: impl Into<String>) -> Self {
+        self.ssid = Some(ssid.into());
+        self
+    }
+
+    /// Sends the requests with `client` instead of a new one
+    pub fn client(mut self, client: PbinfoClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Keeps the problems looked up with [PbinfoUser::get_problem] and
+    /// [PbinfoUser::get_problem_meta] in `cache`
+    pub fn problem_cache(mut self, cache: ProblemCache) -> Self {
+        self.problem_cache = Some(cache);
+        self
+    }
+
+    /// Keeps the session only in memory: the config file is never written
+    /// and there is no problem cache on disk. For ephemeral CI jobs and for
+    /// users that don't want anything left behind
+    pub fn in_memory(mut self) -> Self {
+        self.in_memory = true;
+        self
+    }
+
+    /// Whether [PbinfoUser::upload], [PbinfoUser::get_score] and
+    /// [PbinfoUser::solve] log in again and retry once when the session
+    /// expired. On by default
+    pub fn auto_relogin(mut self, auto_relogin: bool) -> Self {
+        self.no_relogin = !auto_relogin;
+        self
+    }
+
+    pub fn build(self) -> PbinfoUser {
+        PbinfoUser {
+            email: self.email,
+            password: self.password,
+            session: RwLock::new(Session {
+                ssid: self.ssid.unwrap_or_else(make_random_form_ssid),
+                form_token: make_random_form_token(),
+                user_id: "".to_string(),
+                session_started_at: None,
+            }),
+            client: self.client.unwrap_or_default(),
+            problem_cache: self.problem_cache.filter(|_| !self.in_memory),
+            in_memory: self.in_memory,
+            no_relogin: self.no_relogin,
+        }
+    }
+}
 
 impl PbinfoUser {
     pub fn new(email: String, password: String) -> Self {
-        PbinfoUser {
-            email: email,
-            password: password,
-            ssid: make_random_form_ssid(),
-            form_token: make_random_form_token(),
-            user_id: "".to_string(),
+        Self::builder().email(email).password(password).build()
+    }
+
+    pub fn builder() -> PbinfoUserBuilder {
+        PbinfoUserBuilder::default()
+    }
+
+    /// A user for the SSID cookie of a session already logged in in the
+    /// browser, so a tool never sees the password. The cookie is checked
+    /// first, then the user works like a logged in one but can't log in
+    /// again once the session expires
+    pub async fn from_ssid(ssid: &str) -> Result<PbinfoUser, ContextError<LoginError>> {
+        login::from_ssid(ssid)
+            .await
+            .report(|| ErrorContext::new("from_ssid"))
+    }
+
+    /// Creates a new account on pbinfo and returns a user for it, not logged
+    /// in yet. The request is checked with [RegistrationRequest::validate]
+    /// before anything is sent
+    pub async fn register(
+        request: &RegistrationRequest,
+    ) -> Result<PbinfoUser, ContextError<RegisterError>> {
+        register::register(request)
+            .await
+            .report(|| ErrorContext::new("register"))
+    }
+
+    /// A user that never creates a file, see [PbinfoUserBuilder::in_memory]
+    pub fn in_memory(email: String, password: String) -> Self {
+        Self::builder()
+            .email(email)
+            .password(password)
+            .in_memory()
+            .build()
+    }
+
+    /// See [PbinfoUserBuilder::auto_relogin]
+    pub fn auto_relogin(&self) -> bool {
+        !self.no_relogin
+    }
+
+    pub fn set_auto_relogin(&mut self, auto_relogin: bool) {
+        self.no_relogin = !auto_relogin;
+    }
+
+    /// Whether the user was made with [PbinfoUserBuilder::in_memory]
+    pub fn is_in_memory(&self) -> bool {
+        self.in_memory
+    }
+
+    /// Fails for users that must not write files
+    fn check_can_write(&self) -> Result<(), PbinfoUserError> {
+        match self.in_memory {
+            true => Err(PbinfoUserError::InMemoryError),
+            false => Ok(()),
         }
     }
 
-    /// Saves `config` in the ~/config dir or AppData on windows
-    pub fn save_config(&self) -> Result<(), PbinfoUserError> {
-        let proj_dirs = get_proj_dir()?;
-        let config_dir = proj_dirs.config_dir();
-        let config_file_path = config_dir.join(CONFIG_FILE_NAME);
+    /// Saves `config` in the ~/config dir or AppData on windows, or where
+    /// `PBINFO_CONFIG` points. With the `keyring` feature the password and
+    /// the session go in the keyring
+    #[cfg(feature = "config")]
+    pub fn save_config(&self) -> Result<(), ContextError<PbinfoUserError>> {
+        self.write_config()
+            .report(|| ErrorContext::new("save_config"))
+    }
+
+    #[cfg(feature = "config")]
+    fn write_config(&self) -> Result<(), PbinfoUserError> {
+        self.check_can_write()?;
+        self.write_config_to(&crate::user_config::default_store(None))
+    }
+
+    /// Saves the credentials and the session, keeping the other settings
+    /// already in `store`
+    fn write_config_to(&self, store: &dyn ConfigStore) -> Result<(), PbinfoUserError> {
+        let mut config = store.load().unwrap_or_default();
+        self.update_config(&mut config);
+        store.save(&config)
+    }
 
-        let parent_dir = std::path::Path::new(&config_file_path).parent().unwrap();
-        if !parent_dir.exists() {
-            std::fs::create_dir_all(parent_dir).map_err(|err| PbinfoUserError::WriteError {
-                file: parent_dir.to_path_buf(),
-                error: err,
-            })?
+    /// Gets `config` in the ~/config dir or AppData on windows, or where
+    /// `PBINFO_CONFIG` points
+    #[cfg(feature = "config")]
+    pub fn get_config() -> Result<PbinfoUser, ContextError<PbinfoUserError>> {
+        Self::read_config().report(|| ErrorContext::new("get_config"))
+    }
+
+    #[cfg(feature = "config")]
+    fn read_config() -> Result<PbinfoUser, PbinfoUserError> {
+        Ok(Self::from_config(UserConfig::load()?))
+    }
+
+    /// Same as [PbinfoUser::save_config] but in the `[profiles.<profile>]`
+    /// section of the config file, the other profiles are kept
+    #[cfg(feature = "config")]
+    pub fn save_config_profile(&self, profile: &str) -> Result<(), ContextError<PbinfoUserError>> {
+        self.check_can_write()
+            .and_then(|_| self.write_config_to(&crate::user_config::default_store(Some(profile))))
+            .report(|| ErrorContext::new("save_config"))
+    }
+
+    /// Same as [PbinfoUser::get_config] but for a named profile, like a
+    /// personal and a school account kept in the same config file
+    #[cfg(feature = "config")]
+    pub fn get_config_profile(profile: &str) -> Result<PbinfoUser, ContextError<PbinfoUserError>> {
+        UserConfig::load_profile(profile)
+            .map(Self::from_config)
+            .report(|| ErrorContext::new("get_config"))
+    }
+
+    /// Same as [PbinfoUser::save_config] but in the toml file at `path`
+    #[cfg(feature = "config")]
+    pub fn save_config_at(
+        &self,
+        path: impl Into<PathBuf>,
+    ) -> Result<(), ContextError<PbinfoUserError>> {
+        self.check_can_write()
+            .and_then(|_| self.write_config_to(&crate::user_config::TomlFileStore::new(path)))
+            .report(|| ErrorContext::new("save_config"))
+    }
+
+    /// Same as [PbinfoUser::get_config] but out of the toml file at `path`
+    #[cfg(feature = "config")]
+    pub fn get_config_at(
+        path: impl Into<PathBuf>,
+    ) -> Result<PbinfoUser, ContextError<PbinfoUserError>> {
+        Self::get_config_from(&crate::user_config::TomlFileStore::new(path))
+    }
+
+    /// Saves the config of the user in `store` instead of the config file.
+    /// Fails with [PbinfoUserError::InMemoryError] for users
+    /// [in memory](PbinfoUserBuilder::in_memory), like the other saves
+    pub fn save_config_to(
+        &self,
+        store: &dyn ConfigStore,
+    ) -> Result<(), ContextError<PbinfoUserError>> {
+        self.check_can_write()
+            .and_then(|_| self.write_config_to(store))
+            .report(|| ErrorContext::new("save_config"))
+    }
+
+    /// Reads a user out of `store` instead of the config file
+    pub fn get_config_from(
+        store: &dyn ConfigStore,
+    ) -> Result<PbinfoUser, ContextError<PbinfoUserError>> {
+        UserConfig::load_from(store)
+            .map(Self::from_config)
+            .report(|| ErrorContext::new("get_config"))
+    }
+
+    /// Makes a user out of the [EMAIL_VAR] and [PASSWORD_VAR] environment
+    /// variables, starting from the session in [SSID_VAR] if it is set. For
+    /// CI pipelines and containers that have no config file
+    pub fn from_env() -> Result<PbinfoUser, ContextError<PbinfoUserError>> {
+        Self::read_env().report(|| ErrorContext::new("from_env"))
+    }
+
+    fn read_env() -> Result<PbinfoUser, PbinfoUserError> {
+        let var = |var: &'static str| std::env::var(var).ok().filter(|value| !value.is_empty());
+        let missing = |var: &'static str| PbinfoUserError::MissingEnvError { var };
+        let mut builder = Self::builder()
+            .email(var(EMAIL_VAR).ok_or_else(|| missing(EMAIL_VAR))?)
+            .password(var(PASSWORD_VAR).ok_or_else(|| missing(PASSWORD_VAR))?);
+        if let Some(ssid) = var(SSID_VAR) {
+            builder = builder.ssid(ssid);
         }
+        Ok(builder.build())
+    }
 
-        let _ = std::fs::File::create(&config_file_path).map_err(|err| {
-            PbinfoUserError::WriteError {
-                file: config_file_path.to_path_buf(),
-                error: err,
-            }
-        })?;
+    /// Makes a user out of the credentials and the session saved in `config`
+    pub fn from_config(config: UserConfig) -> PbinfoUser {
+        let mut builder = Self::builder()
+            .email(config.email)
+            .password(config.password);
+        if !config.ssid.is_empty() {
+            builder = builder.ssid(config.ssid);
+        }
+        let mut user = builder.build();
+        if !config.form_token.is_empty() {
+            user.session_mut().form_token = config.form_token;
+        }
+        user.session_mut().user_id = config.user_id;
+        user
+    }
 
-        std::fs::write(&config_file_path, toml::to_string(self).unwrap()).map_err(|err| {
-            PbinfoUserError::WriteError {
-                file: config_file_path.to_path_buf(),
-                error: err,
-            }
-        })?;
+    /// Returns what would be saved in the config file for this user
+    pub fn to_config(&self) -> UserConfig {
+        let mut config = UserConfig::default();
+        self.update_config(&mut config);
+        config
+    }
 
-        Ok(())
+    fn update_config(&self, config: &mut UserConfig) {
+        config.email = self.email.clone();
+        config.password = self.password.clone();
+        let session = self.session();
+        config.ssid = session.ssid;
+        config.form_token = session.form_token;
+        config.user_id = session.user_id;
     }
 
-    /// Gets `config` in the ~/config dir or AppData on windows
-    pub fn get_config() -> Result<PbinfoUser, PbinfoUserError> {
-        let proj_dirs = get_proj_dir()?;
-        let config_dir = proj_dirs.config_dir();
-        let config_file_path = config_dir.join(CONFIG_FILE_NAME);
+    /// The email or username the user logs in with
+    pub fn email(&self) -> &str {
+        self.email.as_str()
+    }
 
-        let config_file = fs::read_to_string(config_file_path)
-            .map_err(|err: std::io::Error| PbinfoUserError::ReadConfigError { error: err })?;
+    /// Whether [PbinfoUser::email] is an email or a username, `None` if it
+    /// is neither and logging in would fail with
+    /// [LoginError::InvalidLoginError]
+    pub fn login_kind(&self) -> Option<LoginKind> {
+        LoginKind::detect(&self.email)
+    }
 
-        let parsed_conf = toml::from_str(&config_file)
-            .map_err(|err: toml::de::Error| PbinfoUserError::TomlParseError { error: err })?;
+    pub fn password(&self) -> &str {
+        self.password.as_str()
+    }
 
-        Ok(parsed_conf)
+    /// The id of the user, empty until the first [PbinfoUser::login]
+    pub fn user_id(&self) -> String {
+        self.read_session().user_id.clone()
     }
 
+    /// The session cookie of the user
+    pub fn ssid(&self) -> String {
+        self.read_session().ssid.clone()
+    }
+
+    pub(crate) fn form_token(&self) -> String {
+        self.read_session().form_token.clone()
+    }
+
+    /// A copy of the whole session, read at once
+    pub(crate) fn session(&self) -> Session {
+        self.read_session().clone()
+    }
+
+    pub(crate) fn session_mut(&mut self) -> &mut Session {
+        self.session
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Replaces the session of a user shared by reference, after it logged
+    /// in again
+    pub(crate) fn adopt_session(&self, session: Session) {
+        *self.session.write().unwrap_or_else(PoisonError::into_inner) = session;
+    }
+
+    fn read_session(&self) -> std::sync::RwLockReadGuard<'_, Session> {
+        self.session.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn set_email(&mut self, email: impl Into<String>) {
+        self.email = email.into().trim().to_string();
+    }
+
+    pub fn set_password(&mut self, password: impl Into<String>) {
+        self.password = password.into();
+    }
+
+    #[deprecated(note = "use PbinfoUser::email instead")]
     pub fn get_email(&self) -> &str {
-        return self.email.as_str();
+        self.email()
     }
 
+    #[deprecated(note = "use PbinfoUser::password instead")]
     pub fn get_password(&self) -> &str {
-        return self.password.as_str();
+        self.password()
     }
 
+    #[deprecated(note = "use PbinfoUser::set_email instead")]
     pub fn get_mut_email(&mut self) -> &mut String {
-        return &mut self.email;
+        &mut self.email
     }
 
+    #[deprecated(note = "use PbinfoUser::set_password instead")]
     pub fn get_mut_password(&mut self) -> &mut String {
-        return &mut self.password;
+        &mut self.password
+    }
+
+    /// The client all the requests of this user are sent with
+    pub fn client(&self) -> &PbinfoClient {
+        &self.client
+    }
+
+    /// Mutable access to the client, for registering hooks like
+    /// [PbinfoClient::on_request_complete]
+    pub fn client_mut(&mut self) -> &mut PbinfoClient {
+        &mut self.client
+    }
+
+    /// The cache problems are looked up in, if there is one
+    pub fn problem_cache(&self) -> Option<&ProblemCache> {
+        self.problem_cache.as_ref()
+    }
+
+    /// Users [in memory](PbinfoUserBuilder::in_memory) keep no cache
+    pub fn set_problem_cache(&mut self, cache: Option<ProblemCache>) {
+        self.problem_cache = cache.filter(|_| !self.in_memory);
     }
 
     /// Makes sure a user is logged in, if not logs in the user with the
     /// provided credentials (email, password)
-    pub async fn login(&mut self) -> Result<(), LoginError> {
-        login::login(self).await
+    pub async fn login(&mut self) -> Result<SessionInfo, ContextError<LoginError>> {
+        login::login(self, &LoginOptions::default())
+            .report(|| ErrorContext::new("login"))
+            .await
+    }
+
+    /// Same as [PbinfoUser::login] but with control over the retries and
+    /// the timeout
+    pub async fn login_with(
+        &mut self,
+        options: &LoginOptions,
+    ) -> Result<SessionInfo, ContextError<LoginError>> {
+        login::login(self, options)
+            .report(|| ErrorContext::new("login"))
+            .await
     }
 
     // Resets all non credential fields and logs the user with fresh credentials
-    pub async fn fresh_login(&mut self) -> Result<(), LoginError> {
-        self.ssid = make_random_form_ssid();
-        self.form_token = make_random_form_token();
-        self.user_id = "".to_string();
-        self.login().await?;
-        Ok(())
+    pub async fn fresh_login(&mut self) -> Result<SessionInfo, ContextError<LoginError>> {
+        let options = LoginOptions {
+            force_fresh_session: true,
+            ..Default::default()
+        };
+        login::login(self, &options)
+            .report(|| ErrorContext::new("fresh_login"))
+            .await
+    }
+
+    /// Ends the session on pbinfo and forgets it, also in the config file,
+    /// so shared machines don't keep a live session around. The session is
+    /// forgotten even if pbinfo couldn't be reached
+    pub async fn logout(&mut self) -> Result<(), ContextError<LoginError>> {
+        login::logout(self)
+            .report(|| ErrorContext::new("logout"))
+            .await
+    }
+
+    /// Whether the session is still logged in, only looking at the homepage
+    /// and never sending the credentials. For deciding whether to ask the
+    /// user for them
+    pub async fn is_logged_in(&self) -> Result<bool, ContextError<LoginError>> {
+        login::is_logged_in(self)
+            .report(|| ErrorContext::new("is_logged_in"))
+            .await
+    }
+
+    /// Checks the session, resolves the user id and refreshes the form token
+    /// with a single request, logging in only if needed. Lets interactive
+    /// tools pay the startup latency once, before the user does anything
+    pub async fn prepare(&mut self) -> Result<SessionInfo, ContextError<LoginError>> {
+        login::prepare(self)
+            .report(|| ErrorContext::new("prepare"))
+            .await
+    }
+
+    /// The profile of the logged in user: the name, the school, the county,
+    /// the solved problems and the points
+    pub async fn get_profile(&self) -> Result<UserProfile, ContextError<ProfileError>> {
+        profile::get_profile(self)
+            .report(|| ErrorContext::new("get_profile"))
+            .await
+    }
+
+    /// The public profile of any user, for following a class or a rival
+    pub async fn get_user_profile(
+        &self,
+        user: &UserRef,
+    ) -> Result<UserProfile, ContextError<ProfileError>> {
+        profile::get_user_profile(user, self)
+            .report(|| ErrorContext::new("get_user_profile"))
+            .await
+    }
+
+    /// The ids of the problems a user got 100 points on
+    pub async fn get_solved_problems(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<String>, ContextError<ProfileError>> {
+        profile::get_problem_progress(user_id, self)
+            .report(|| ErrorContext::new("get_solved_problems"))
+            .await
+            .map(|progress| progress.solved)
+    }
+
+    /// Same as [PbinfoUser::get_solved_problems] but also with the problems
+    /// the user only attempted, for progress dashboards
+    pub async fn get_problem_progress(
+        &self,
+        user_id: &str,
+    ) -> Result<ProblemProgress, ContextError<ProfileError>> {
+        profile::get_problem_progress(user_id, self)
+            .report(|| ErrorContext::new("get_problem_progress"))
+            .await
+    }
+
+    /// How many problems of every grade and chapter the logged in user
+    /// solved, like the progress bars of the profile
+    pub async fn get_progress_summary(
+        &self,
+    ) -> Result<ProgressSummary, ContextError<ProfileError>> {
+        profile::get_progress_summary(self)
+            .report(|| ErrorContext::new("get_progress_summary"))
+            .await
+    }
+
+    /// A page of the ranking of the users of pbinfo, of a county or of a
+    /// school, starting from page 1
+    pub async fn get_ranking(
+        &self,
+        scope: &RankingScope,
+        page: u32,
+    ) -> Result<RankingPage, ContextError<RankingError>> {
+        ranking::get_ranking(scope, page, self)
+            .report(|| ErrorContext::new("get_ranking"))
+            .await
+    }
+
+    /// The badges of a user, with what they are given for and when the user
+    /// got them
+    pub async fn get_achievements(
+        &self,
+        username: &str,
+    ) -> Result<Vec<Achievement>, ContextError<ProfileError>> {
+        profile::get_achievements(username, self)
+            .report(|| ErrorContext::new("get_achievements"))
+            .await
+    }
+
+    /// The classes the logged in user belongs to
+    pub async fn list_classes(&self) -> Result<Vec<Class>, ContextError<ClassError>> {
+        class::list_classes(self)
+            .report(|| ErrorContext::new("list_classes"))
+            .await
+    }
+
+    /// The assignments posted in a class, with their deadlines
+    pub async fn list_assignments(
+        &self,
+        class_id: &str,
+    ) -> Result<Vec<AssignmentSummary>, ContextError<ClassError>> {
+        class::list_assignments(class_id, self)
+            .report(|| ErrorContext::new("list_assignments"))
+            .await
+    }
+
+    /// An assignment with the problems to solve for it
+    pub async fn get_assignment(
+        &self,
+        assignment_id: &str,
+    ) -> Result<Assignment, ContextError<ClassError>> {
+        class::get_assignment(assignment_id, self)
+            .report(|| ErrorContext::new("get_assignment"))
+            .await
+    }
+
+    /// Runs every page parser against the live site and reports the ones
+    /// that fail, for noticing early when pbinfo changes its pages. The
+    /// parsers that need a session are only checked after a login
+    pub async fn self_test(&self) -> SelfTestReport {
+        self_test::self_test(self).await
+    }
+
+    /// Gets a fresh form token from pbinfo and stores it, instead of
+    /// relying on the site to bounce the first post of an expired form
+    pub async fn refresh_form_token(&mut self) -> Result<String, ContextError<LoginError>> {
+        login::refresh_form_token(self)
+            .report(|| ErrorContext::new("refresh_form_token"))
+            .await
+    }
+
+    /// Clears the session saved in every profile of the config file that
+    /// belongs to this user. The session is dead anyway, so a config that
+    /// can't be written is only traced
+    #[cfg(feature = "config")]
+    fn forget_saved_session(&self) {
+        use crate::user_config::{default_store, TomlFileStore};
+
+        if self.in_memory {
+            return;
+        }
+        for profile in TomlFileStore::default().profiles().unwrap_or_default() {
+            let store = default_store(Some(&profile));
+            let Ok(mut config) = store.load() else {
+                continue;
+            };
+            if config.email != self.email || !config.has_session() {
+                continue;
+            }
+            config.clear_session();
+            if let Err(_err) = store.save(&config) {
+                trace_event!(tracing::Level::WARN, error = %_err, profile, "couldn't clear the saved session");
+            }
+        }
+    }
+
+    /// Forgets the session, keeping only the credentials
+    fn reset_session(&mut self) {
+        *self.session_mut() = Session {
+            ssid: make_random_form_ssid(),
+            form_token: make_random_form_token(),
+            user_id: "".to_string(),
+            session_started_at: None,
+        };
     }
 
     /// Uploads a source and returns a solution id
-    pub async fn upload(&self, problem_id: &str, source: &str) -> Result<String, UploadError> {
-        upload::upload(problem_id, source, self).await
+    pub async fn upload(
+        &self,
+        problem_id: &str,
+        source: &str,
+    ) -> Result<String, ContextError<UploadError>> {
+        upload::upload(problem_id, source, self)
+            .report(|| ErrorContext::new("upload").problem_id(problem_id))
+            .await
+    }
+
+    /// Same as [PbinfoUser::upload] but for sources that aren't c++
+    pub async fn upload_with_language(
+        &self,
+        problem_id: &str,
+        source: &str,
+        language: Language,
+    ) -> Result<String, ContextError<UploadError>> {
+        upload::upload_with_language(problem_id, source, language, self)
+            .report(|| ErrorContext::new("upload").problem_id(problem_id))
+            .await
+    }
+
+    /// Same as [PbinfoUser::upload] but with control over the language and
+    /// over waiting out the rate limit of pbinfo
+    pub async fn upload_with(
+        &self,
+        problem_id: &str,
+        source: &str,
+        options: &UploadOptions,
+    ) -> Result<String, ContextError<UploadError>> {
+        upload::upload_with(problem_id, source, options, self)
+            .report(|| ErrorContext::new("upload").problem_id(problem_id))
+            .await
+    }
+
+    /// Same as [PbinfoUser::upload] but returns the untouched response of
+    /// pbinfo instead of just the solution id
+    pub async fn upload_raw(
+        &self,
+        problem_id: &str,
+        source: &str,
+    ) -> Result<String, ContextError<UploadError>> {
+        upload::upload_raw(problem_id, source, Language::Cpp, self)
+            .report(|| ErrorContext::new("upload_raw").problem_id(problem_id))
+            .await
     }
 
     /// Looks up a source code solution to the given problem.
     /// If it finds it, the source code will be uploaded and a solution id
-    /// will be returned
-    pub async fn solve(&self, problem_id: &str) -> Result<String, SolveError> {
-        solve::solve(problem_id, self).await
+    /// will be returned. Problems that already have 100 points are skipped
+    pub async fn solve(&self, problem_id: &str) -> Result<SolveOutcome, ContextError<SolveError>> {
+        self.solve_with(problem_id, &SolveOptions::default()).await
+    }
+
+    /// Same as [PbinfoUser::solve] but with control over the skipping
+    pub async fn solve_with(
+        &self,
+        problem_id: &str,
+        options: &SolveOptions,
+    ) -> Result<SolveOutcome, ContextError<SolveError>> {
+        solve::solve(problem_id, options, self)
+            .report(|| ErrorContext::new("solve").problem_id(problem_id))
+            .await
+    }
+
+    /// Solves a problem and waits for its evaluation, so a script knows the
+    /// solve actually worked. Fails with
+    /// [SolveAndVerifyError::BelowThreshold] under 100 points
+    #[cfg(feature = "tokio")]
+    pub async fn solve_and_verify(
+        &self,
+        problem_id: &str,
+    ) -> Result<Evaluation, ContextError<SolveAndVerifyError>> {
+        self.solve_and_verify_with(problem_id, &VerifyOptions::default())
+            .await
+    }
+
+    /// Same as [PbinfoUser::solve_and_verify] but with control over the
+    /// solve, the polling and the score needed
+    #[cfg(feature = "tokio")]
+    pub async fn solve_and_verify_with(
+        &self,
+        problem_id: &str,
+        options: &VerifyOptions,
+    ) -> Result<Evaluation, ContextError<SolveAndVerifyError>> {
+        solve::solve_and_verify(problem_id, options, self)
+            .report(|| ErrorContext::new("solve_and_verify").problem_id(problem_id))
+            .await
+    }
+
+    /// Same as [PbinfoUser::solve] but the solutions in `custom_solutions`
+    /// (problem id to source) are tried first
+    pub async fn custom_solve(
+        &self,
+        problem_id: &str,
+        custom_solutions: &HashMap<String, String>,
+    ) -> Result<SolveOutcome, ContextError<SolveError>> {
+        solve::custom_solve(problem_id, custom_solutions, &SolveOptions::default(), self)
+            .report(|| ErrorContext::new("custom_solve").problem_id(problem_id))
+            .await
     }
 
     /// Similar to [PbinfoUser::solve] but it also accepts a json of costume solutions
@@ -186,12 +870,263 @@ impl PbinfoUser {
     ///     "problem id":"solution"
     /// }
     /// ```
+    #[deprecated(note = "use PbinfoUser::custom_solve instead")]
     pub async fn costume_solve(
         &self,
         problem_id: &str,
         costume_solutions: &serde_json::Value,
-    ) -> Result<String, SolveError> {
-        solve::costume_solve(problem_id, costume_solutions, self).await
+    ) -> Result<SolveOutcome, ContextError<SolveError>> {
+        solve::costume_solve(
+            problem_id,
+            costume_solutions,
+            &SolveOptions::default(),
+            self,
+        )
+        .report(|| ErrorContext::new("costume_solve").problem_id(problem_id))
+        .await
+    }
+
+    /// Downloads the statement of a problem: the title, the task, the
+    /// format of the input and output, the constraints and the examples
+    pub async fn get_problem(
+        &self,
+        problem_id: &str,
+    ) -> Result<Problem, ContextError<ProblemError>> {
+        problem::get_problem(problem_id, self)
+            .report(|| ErrorContext::new("get_problem").problem_id(problem_id))
+            .await
+    }
+
+    /// Whether a problem with this id exists, for checking ids before
+    /// submitting anything
+    pub async fn problem_exists(
+        &self,
+        problem_id: &str,
+    ) -> Result<bool, ContextError<ProblemError>> {
+        problem::problem_exists(problem_id, self)
+            .report(|| ErrorContext::new("problem_exists").problem_id(problem_id))
+            .await
+    }
+
+    /// The grade, category, difficulty, author and posting date of a problem
+    pub async fn get_problem_meta(
+        &self,
+        problem_id: &str,
+    ) -> Result<ProblemMeta, ContextError<ProblemError>> {
+        problem::get_problem_meta(problem_id, self)
+            .report(|| ErrorContext::new("get_problem_meta").problem_id(problem_id))
+            .await
+    }
+
+    /// The problems whose name matches `query`, as pbinfo's own search finds them
+    pub async fn search_problems(
+        &self,
+        query: &str,
+        filters: &SearchFilters,
+    ) -> Result<Vec<ProblemSummary>, ContextError<ProblemError>> {
+        problem::search_problems(query, filters, self)
+            .report(|| ErrorContext::new("search_problems"))
+            .await
+    }
+
+    /// A page of the problems of a category (by the id pbinfo has in its
+    /// urls), starting from page 1. Go through the pages until
+    /// [ProblemList::has_more] is false to get the whole category
+    pub async fn list_problems(
+        &self,
+        category: &str,
+        page: u32,
+    ) -> Result<ProblemList, ContextError<ProblemError>> {
+        problem::list_problems(category, page, self)
+            .report(|| ErrorContext::new("list_problems"))
+            .await
+    }
+
+    /// The input and output pairs of the examples of a problem, so a
+    /// source can be checked locally before wasting a submission
+    pub async fn get_examples(
+        &self,
+        problem_id: &str,
+    ) -> Result<Vec<Example>, ContextError<ProblemError>> {
+        problem::get_examples(problem_id, self)
+            .report(|| ErrorContext::new("get_examples").problem_id(problem_id))
+            .await
+    }
+
+    /// Compiles and runs the source at `source` against the examples of a
+    /// problem, see [crate::runner]. The source runs on the current thread
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn run_examples(
+        &self,
+        problem_id: &str,
+        source: impl AsRef<std::path::Path>,
+        options: &crate::runner::RunnerOptions,
+    ) -> Result<crate::runner::RunReport, ContextError<crate::runner::RunnerError>> {
+        let report = || ErrorContext::new("run_examples").problem_id(problem_id);
+        let examples = async {
+            problem::get_examples(problem_id, self)
+                .await
+                .map_err(|err| crate::runner::RunnerError::ExamplesError {
+                    err: err.to_string(),
+                })
+        }
+        .report(report)
+        .await?;
+        crate::runner::run_examples(source.as_ref(), &examples, options).report(report)
+    }
+
+    /// A page of the solutions the user sent to a problem, newest first and
+    /// [SOLUTIONS_PER_PAGE] at a time, starting from page 1
+    pub async fn list_my_solutions(
+        &self,
+        problem_id: &str,
+        page: u32,
+    ) -> Result<SolutionPage, ContextError<SolutionsError>> {
+        solutions::list_my_solutions(problem_id, page, self)
+            .report(|| ErrorContext::new("list_my_solutions").problem_id(problem_id))
+            .await
+    }
+
+    /// A page of all the solutions the user ever sent, to any problem,
+    /// newest first and [SOLUTIONS_PER_PAGE] at a time
+    pub async fn list_all_solutions(
+        &self,
+        page: u32,
+    ) -> Result<SolutionPage, ContextError<SolutionsError>> {
+        solutions::list_all_solutions(page, self)
+            .report(|| ErrorContext::new("list_all_solutions"))
+            .await
+    }
+
+    /// Every page of [PbinfoUser::list_all_solutions] as a stream, for
+    /// going through the whole history of the user. The history is read in
+    /// two requests, so the pages come once all of it is downloaded. Its
+    /// errors aren't reported to the error hook
+    pub fn all_solutions(
+        &self,
+    ) -> impl futures_util::Stream<Item = Result<SolutionPage, SolutionsError>> + '_ {
+        solutions::all_solutions(self)
+    }
+
+    /// A page of the solutions other users made public for a problem, for
+    /// studying the accepted approaches
+    pub async fn list_public_solutions(
+        &self,
+        problem_id: &str,
+        page: u32,
+    ) -> Result<SolutionPage, ContextError<SolutionsError>> {
+        solutions::list_public_solutions(problem_id, page, self)
+            .report(|| ErrorContext::new("list_public_solutions").problem_id(problem_id))
+            .await
+    }
+
+    /// The source of a solution from [PbinfoUser::list_public_solutions]
+    pub async fn get_public_solution_source(
+        &self,
+        solution_id: &str,
+    ) -> Result<String, ContextError<SolutionsError>> {
+        solutions::get_public_solution_source(solution_id, self)
+            .report(|| ErrorContext::new("get_public_solution_source").solution_id(solution_id))
+            .await
+    }
+
+    /// The source of one of the solutions of the user, for backups or for
+    /// going back to the last solution
+    pub async fn get_solution_source(
+        &self,
+        solution_id: &str,
+    ) -> Result<String, ContextError<SolutionsError>> {
+        solutions::get_solution_source(solution_id, self)
+            .report(|| ErrorContext::new("get_solution_source").solution_id(solution_id))
+            .await
+    }
+
+    /// Downloads every source the user ever sent to pbinfo into `dir`, one
+    /// folder per problem, plus a `manifest.json` describing them
+    pub async fn export_my_sources(
+        &self,
+        dir: impl AsRef<std::path::Path>,
+    ) -> Result<Vec<ExportedSource>, ContextError<ExportError>> {
+        export::export_my_sources(dir.as_ref(), self)
+            .report(|| ErrorContext::new("export_my_sources"))
+            .await
+    }
+
+    /// Solves all the problems, uploading them one after the other as fast as
+    /// pbinfo allows and waiting for their evaluations in between
+    #[cfg(feature = "tokio")]
+    pub async fn solve_many(&self, problem_ids: &[&str], options: &BatchOptions) -> BatchReport {
+        batch::solve_many(problem_ids, None, options, self).await
+    }
+
+    /// Same as [PbinfoUser::solve_many] for a range of problem ids, like
+    /// `1..=100`
+    #[cfg(feature = "tokio")]
+    pub async fn solve_range(
+        &self,
+        problem_ids: std::ops::RangeInclusive<u32>,
+        options: &BatchOptions,
+    ) -> BatchReport {
+        let problem_ids: Vec<String> = problem_ids.map(|id| id.to_string()).collect();
+        let problem_ids: Vec<&str> = problem_ids.iter().map(String::as_str).collect();
+        self.solve_many(&problem_ids, options).await
+    }
+
+    /// Solves every problem of an assignment, like [PbinfoUser::solve_many]
+    /// but with the solutions sent for the assignment
+    #[cfg(feature = "tokio")]
+    pub async fn solve_assignment(
+        &self,
+        assignment: &Assignment,
+        options: &BatchOptions,
+    ) -> BatchReport {
+        let mut options = options.clone();
+        options.solve.assignment = Some(assignment.id.clone());
+        self.solve_many(&assignment.problem_ids(), &options).await
+    }
+
+    /// Uploads many sources at once (like all the homework of a class),
+    /// returning the problem id with the solution id or the error of every
+    /// upload, in the order they were given
+    #[cfg(feature = "tokio")]
+    pub async fn upload_many(
+        &self,
+        uploads: Vec<(String, String)>,
+        options: &UploadManyOptions,
+    ) -> Vec<(String, Result<String, ContextError<UploadError>>)> {
+        batch::upload_many(uploads, options, self).await
+    }
+
+    /// Writes a zip to `path` with the statement and the examples of a
+    /// problem, the best source the user sent to it and its score report.
+    /// Handy as evidence of work or for archiving solved problems
+    #[cfg(feature = "bundle")]
+    pub async fn export_bundle(
+        &self,
+        problem_id: &str,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<BundleManifest, ContextError<BundleError>> {
+        bundle::export_bundle(problem_id, path.as_ref(), self)
+            .report(|| ErrorContext::new("export_bundle").problem_id(problem_id))
+            .await
+    }
+
+    /// Same as [PbinfoUser::custom_solve] but with the solutions read out of
+    /// bundles
+    #[cfg(feature = "bundle")]
+    pub async fn bundle_solve(
+        &self,
+        problem_id: &str,
+        bundles: &BundleSolutions,
+    ) -> Result<SolveOutcome, ContextError<SolveError>> {
+        solve::custom_solve(
+            problem_id,
+            bundles.solutions(),
+            &SolveOptions::default(),
+            self,
+        )
+        .report(|| ErrorContext::new("bundle_solve").problem_id(problem_id))
+        .await
     }
 
     /// Returns information about the top solution given to a problem
@@ -201,13 +1136,96 @@ impl PbinfoUser {
         score::get_top_score(problem_id, self).await
     }
 
+    /// Returns the untouched json list of all the solutions the user sent
+    /// to a problem, [PbinfoUser::get_top_score] is computed from it
+    pub async fn get_top_score_raw(
+        &self,
+        problem_id: &str,
+    ) -> Result<serde_json::Value, ContextError<GetScoreError>> {
+        score::get_top_score_raw(problem_id, self)
+            .report(|| ErrorContext::new("get_top_score_raw").problem_id(problem_id))
+            .await
+    }
+
     /// Returns the score of a given solution
-    pub async fn get_score(&self, sol_id: &str) -> Result<ScoreStatus, GetScoreError> {
-        score::get_score(sol_id, self).await
+    pub async fn get_score(
+        &self,
+        sol_id: &str,
+    ) -> Result<ScoreStatus, ContextError<GetScoreError>> {
+        score::get_score(sol_id, self)
+            .report(|| ErrorContext::new("get_score").solution_id(sol_id))
+            .await
     }
 
-    /// Awaits the score to finish evaluation while pooling it every 1500 milliseconds
-    pub async fn pool_score(&self, sol_id: &str) -> Result<serde_json::Value, GetScoreError> {
-        score::pool_score(sol_id, self).await
+    /// Returns the evaluation details of a solution exactly as pbinfo sent
+    /// them (a json string)
+    pub async fn get_score_raw(&self, sol_id: &str) -> Result<String, ContextError<GetScoreError>> {
+        score::get_score_raw(sol_id, self)
+            .report(|| ErrorContext::new("get_score_raw").solution_id(sol_id))
+            .await
+    }
+
+    /// Uploads a source and waits for it to be evaluated. With a `timeout`
+    /// gives up once it passes, returning the solution id if the upload made it
+    #[cfg(feature = "tokio")]
+    pub async fn upload_and_wait(
+        &self,
+        problem_id: &str,
+        source: &str,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<Evaluation, ContextError<UploadAndWaitError>> {
+        score::upload_and_wait(problem_id, source, timeout, self)
+            .report(|| ErrorContext::new("upload_and_wait").problem_id(problem_id))
+            .await
+    }
+
+    /// Awaits the score to finish evaluation while pooling it every 1500 milliseconds.
+    /// Returns the typed report together with the untouched json
+    #[cfg(feature = "tokio")]
+    pub async fn pool_score(
+        &self,
+        sol_id: &str,
+    ) -> Result<Evaluation, ContextError<GetScoreError>> {
+        self.pool_score_with(sol_id, &PollOptions::default()).await
+    }
+
+    /// Polls the score of a solution as a stream of statuses, for plugging
+    /// into select loops and UIs. The stream ends after the evaluation
+    /// finished, after an error or with a [GetScoreError::TimeoutError] after
+    /// `options.max_attempts` polls. Its errors aren't reported to the error hook
+    #[cfg(feature = "tokio")]
+    pub fn watch_score<'a>(
+        &'a self,
+        sol_id: &'a str,
+        options: &'a PollOptions,
+    ) -> impl futures_util::Stream<Item = Result<ScoreStatus, GetScoreError>> + 'a {
+        score::watch_score(sol_id, options, self)
+    }
+
+    /// Same as [PbinfoUser::pool_score_with] but calls `on_progress` after
+    /// every poll, so a "still evaluating..." can be shown while waiting
+    #[cfg(feature = "tokio")]
+    pub async fn pool_score_with_progress(
+        &self,
+        sol_id: &str,
+        options: &PollOptions,
+        on_progress: impl FnMut(&ScoreStatus),
+    ) -> Result<Evaluation, ContextError<GetScoreError>> {
+        score::pool_score_with_progress(sol_id, options, on_progress, self)
+            .report(|| ErrorContext::new("pool_score").solution_id(sol_id))
+            .await
+    }
+
+    /// Same as [PbinfoUser::pool_score] but with control over how often and
+    /// how many times the score is checked
+    #[cfg(feature = "tokio")]
+    pub async fn pool_score_with(
+        &self,
+        sol_id: &str,
+        options: &PollOptions,
+    ) -> Result<Evaluation, ContextError<GetScoreError>> {
+        score::pool_score(sol_id, options, self)
+            .report(|| ErrorContext::new("pool_score").solution_id(sol_id))
+            .await
     }
 }
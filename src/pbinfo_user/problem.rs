@@ -0,0 +1,328 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    endpoints::{self, Endpoint},
+    http, parse,
+    pbinfo_user::{cache, PbinfoUser},
+};
+
+#[derive(Error, Debug)]
+pub enum ProblemError {
+    #[error("Error: Couldn't get the page of the problem {problem_id}!\nGot error:\n{err}")]
+    RequestError { problem_id: String, err: String },
+    #[error("Error: The problem {problem_id} doesn't exist!")]
+    NotFoundError { problem_id: String },
+    #[error("Error: Couldn't parse the page of the problem {problem_id}!\nGot error:\n{err}")]
+    ParseError { problem_id: String, err: String },
+    #[error("Error: Couldn't get the list of problems!\nGot error:\n{err}")]
+    ListError { err: String },
+}
+
+/// The statement of a problem, returned by [PbinfoUser::get_problem]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Problem {
+    pub id: String,
+    pub title: String,
+    /// What has to be solved (the `Cerința` section)
+    pub statement: String,
+    /// How the input looks (the `Date de intrare` section)
+    pub input_format: String,
+    /// How the output has to look (the `Date de ieșire` section)
+    pub output_format: String,
+    /// The limits of the input (the `Restricții și precizări` section)
+    pub constraints: String,
+    pub examples: Vec<Example>,
+    /// The html of the whole statement, as pbinfo sent it
+    #[serde(default)]
+    pub html: String,
+}
+
+impl Problem {
+    /// The statement as markdown, with the title as the heading, for reading
+    /// offline or showing in an editor
+    pub fn to_markdown(&self) -> String {
+        format!("# {}\n\n{}\n", self.title, parse::markdown(&self.html))
+    }
+}
+
+/// What pbinfo shows about a problem above its statement, returned by
+/// [PbinfoUser::get_problem_meta]. Everything the page doesn't show is `None`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemMeta {
+    pub id: String,
+    /// The grade the problem is meant for, like 9, 10 or 11
+    pub grade: Option<u8>,
+    /// The chapter the problem is in, like `Tablouri bidimensionale`
+    pub category: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    /// Who wrote the problem
+    pub author: Option<String>,
+    /// Who posted the problem on pbinfo
+    pub posted_by: Option<String>,
+    /// When the problem was posted, as pbinfo writes it
+    pub posted_on: Option<String>,
+}
+
+impl ProblemMeta {
+    /// The day the problem was posted
+    #[cfg(feature = "chrono")]
+    pub fn posted_date(&self) -> Option<chrono::NaiveDate> {
+        parse::date(self.posted_on.as_deref()?)
+    }
+}
+
+/// The difficulty label of a problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    /// `ușoară`
+    Easy,
+    /// `medie`
+    Medium,
+    /// `dificilă`
+    Hard,
+    /// `concurs`
+    Contest,
+}
+
+impl Difficulty {
+    /// Reads the label pbinfo shows, with or without diacritics
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim().to_lowercase();
+        if ["usoar", "ușoar", "uşoar"]
+            .iter()
+            .any(|easy| label.starts_with(easy))
+        {
+            Some(Difficulty::Easy)
+        } else if label.starts_with("medi") {
+            Some(Difficulty::Medium)
+        } else if label.starts_with("dificil") {
+            Some(Difficulty::Hard)
+        } else if label.starts_with("concurs") {
+            Some(Difficulty::Contest)
+        } else {
+            None
+        }
+    }
+}
+
+/// A problem in a list of problems, returned by [PbinfoUser::search_problems]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemSummary {
+    pub id: String,
+    pub name: String,
+    pub grade: Option<u8>,
+    pub difficulty: Option<Difficulty>,
+}
+
+/// A page of the problems of a category, returned by
+/// [PbinfoUser::list_problems]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemList {
+    /// The page, starting from 1
+    pub page: u32,
+    pub problems: Vec<ProblemSummary>,
+    /// How many problems the category has on all the pages, `None` if the
+    /// page didn't say
+    pub total: Option<u64>,
+}
+
+impl ProblemList {
+    /// Whether there are problems on the pages after this one
+    pub fn has_more(&self) -> bool {
+        let per_page = endpoints::PROBLEMS_PER_PAGE as u64;
+        match self.total {
+            Some(total) => u64::from(self.page) * per_page < total,
+            None => self.problems.len() as u64 >= per_page,
+        }
+    }
+}
+
+/// Narrows down [PbinfoUser::search_problems]
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    /// Only the problems for a grade, like 9
+    pub grade: Option<u8>,
+    /// Only the problems of a category, by the id pbinfo has in its urls
+    pub category: Option<String>,
+}
+
+/// An example of a problem statement, what the source should print for an input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Example {
+    pub input: String,
+    pub output: String,
+}
+
+/// Downloads the html of the page of a problem
+async fn get_problem_page(
+    problem_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, ProblemError> {
+    let request_error = |err: String| ProblemError::RequestError {
+        problem_id: problem_id.to_string(),
+        err,
+    };
+    let endpoint = endpoints::ProblemPage::new(problem_id).ssid(pbinfo_user.ssid());
+    let response = http::send(&pbinfo_user.client, &endpoint)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+
+    match response.status() {
+        StatusCode::OK => (),
+        StatusCode::NOT_FOUND => {
+            return Err(ProblemError::NotFoundError {
+                problem_id: problem_id.to_string(),
+            })
+        }
+        status => return Err(request_error(format!("Got the status code {status}"))),
+    }
+
+    http::text(response)
+        .await
+        .map_err(|err| request_error(err.to_string()))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn get_problem(
+    problem_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Problem, ProblemError> {
+    let cache = pbinfo_user.problem_cache.as_ref();
+    if let Some(problem) = cache.and_then(|cache| cache.load(cache::PROBLEM, problem_id)) {
+        trace_event!(tracing::Level::DEBUG, "found the problem in the cache");
+        return Ok(problem);
+    }
+
+    let html = get_problem_page(problem_id, pbinfo_user).await?;
+    let problem = parse::problem(problem_id, &html).map_err(|err| ProblemError::ParseError {
+        problem_id: problem_id.to_string(),
+        err: err.to_string(),
+    })?;
+    if let Some(cache) = cache {
+        cache.store(cache::PROBLEM, problem_id, &problem);
+    }
+    Ok(problem)
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn get_problem_meta(
+    problem_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<ProblemMeta, ProblemError> {
+    let cache = pbinfo_user.problem_cache.as_ref();
+    if let Some(meta) = cache.and_then(|cache| cache.load(cache::PROBLEM_META, problem_id)) {
+        trace_event!(
+            tracing::Level::DEBUG,
+            "found the problem metadata in the cache"
+        );
+        return Ok(meta);
+    }
+
+    let html = get_problem_page(problem_id, pbinfo_user).await?;
+    let meta = parse::problem_meta(problem_id, &html).map_err(|err| ProblemError::ParseError {
+        problem_id: problem_id.to_string(),
+        err: err.to_string(),
+    })?;
+    if let Some(cache) = cache {
+        cache.store(cache::PROBLEM_META, problem_id, &meta);
+    }
+    Ok(meta)
+}
+
+/// Whether there is a problem with the id `problem_id`, without parsing its page
+pub async fn problem_exists(
+    problem_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<bool, ProblemError> {
+    let cached = pbinfo_user
+        .problem_cache
+        .as_ref()
+        .and_then(|cache| cache.load::<Problem>(cache::PROBLEM, problem_id));
+    if cached.is_some() {
+        return Ok(true);
+    }
+
+    let endpoint = endpoints::ProblemPage::new(problem_id).ssid(pbinfo_user.ssid());
+    let response = http::send(&pbinfo_user.client, &endpoint)
+        .await
+        .map_err(|err| ProblemError::RequestError {
+            problem_id: problem_id.to_string(),
+            err: err.to_string(),
+        })?;
+    match response.status() {
+        StatusCode::OK => Ok(true),
+        StatusCode::NOT_FOUND => Ok(false),
+        status => Err(ProblemError::RequestError {
+            problem_id: problem_id.to_string(),
+            err: format!("Got the status code {status}"),
+        }),
+    }
+}
+
+/// Downloads a page that lists problems
+async fn get_list_page<E: Endpoint>(
+    endpoint: &E,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, ProblemError> {
+    let list_error = |err: String| ProblemError::ListError { err };
+    let response = http::send(&pbinfo_user.client, endpoint)
+        .await
+        .map_err(|err| list_error(err.to_string()))?;
+    if response.status() != StatusCode::OK {
+        return Err(list_error(format!(
+            "Got the status code {}",
+            response.status()
+        )));
+    }
+    http::text(response)
+        .await
+        .map_err(|err| list_error(err.to_string()))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn search_problems(
+    query: &str,
+    filters: &SearchFilters,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Vec<ProblemSummary>, ProblemError> {
+    let mut endpoint = endpoints::ProblemSearch::new(query).ssid(pbinfo_user.ssid());
+    if let Some(grade) = filters.grade {
+        endpoint = endpoint.grade(grade);
+    }
+    if let Some(category) = &filters.category {
+        endpoint = endpoint.category(category);
+    }
+    let html = get_list_page(&endpoint, pbinfo_user).await?;
+    Ok(parse::problem_list(&html))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn list_problems(
+    category: &str,
+    page: u32,
+    pbinfo_user: &PbinfoUser,
+) -> Result<ProblemList, ProblemError> {
+    let page = page.max(1);
+    let endpoint = endpoints::CategoryProblems::new(category)
+        .page(page)
+        .ssid(pbinfo_user.ssid());
+    let html = get_list_page(&endpoint, pbinfo_user).await?;
+    Ok(ProblemList {
+        page,
+        problems: parse::problem_list(&html),
+        total: parse::problem_count(&html),
+    })
+}
+
+/// Only the examples of a problem, for checking a source locally before
+/// uploading it
+pub async fn get_examples(
+    problem_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Vec<Example>, ProblemError> {
+    get_problem(problem_id, pbinfo_user)
+        .await
+        .map(|problem| problem.examples)
+}
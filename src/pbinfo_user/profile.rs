@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::login;
+use crate::{endpoints, http, parse, pbinfo_user::PbinfoUser};
+
+#[derive(Error, Debug)]
+pub enum ProfileError {
+    #[error("Error: The user wasn't logged in!")]
+    NotLoggedInError,
+    #[error("Error: There is no user {user}!")]
+    NotFoundError { user: String },
+    #[error("Error: Couldn't get the profile page!\nGot error:\n{err}")]
+    RequestError { err: String },
+    #[error("Error: Couldn't parse the profile page!\nGot error:\n{err}")]
+    ParseError { err: String },
+}
+
+/// What the profile page of a user shows, returned by
+/// [PbinfoUser::get_profile]. Everything the page doesn't show is `None`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserProfile {
+    /// The name used in the url of the profile, or the id of the user for
+    /// profiles asked for with [UserRef::Id]
+    pub username: String,
+    /// The name shown on the site
+    pub display_name: Option<String>,
+    pub school: Option<String>,
+    /// The county of the school, like `Cluj`
+    pub county: Option<String>,
+    /// How many problems the user got 100 points on
+    pub solved_problems: Option<u32>,
+    /// The points pbinfo gives for solved problems
+    pub success_points: Option<u64>,
+}
+
+/// The problems a user sent solutions to, returned by
+/// [PbinfoUser::get_problem_progress]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemProgress {
+    /// The ids of the problems with 100 points
+    pub solved: Vec<String>,
+    /// The ids of the problems that got solutions but not 100 points
+    pub attempted: Vec<String>,
+}
+
+/// A badge of a user, returned by [PbinfoUser::get_achievements]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Achievement {
+    pub name: String,
+    /// What the badge is given for
+    pub description: String,
+    /// When the user got the badge, as pbinfo writes it
+    pub earned_on: Option<String>,
+}
+
+impl Achievement {
+    /// The day the user got the badge
+    #[cfg(feature = "chrono")]
+    pub fn earned_date(&self) -> Option<chrono::NaiveDate> {
+        parse::date(self.earned_on.as_deref()?)
+    }
+}
+
+/// Who to get the profile of with [PbinfoUser::get_user_profile]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserRef {
+    /// The numeric id of the user, like the one of [PbinfoUser::user_id]
+    Id(String),
+    /// The name used in the url of the profile
+    Username(String),
+}
+
+/// How many problems of a category of the profile progress bars are solved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryProgress {
+    /// The label of the bar, like `Clasa a IX-a` or the name of a chapter
+    pub name: String,
+    /// The grade of the category, if it is one
+    pub grade: Option<u8>,
+    pub solved: u32,
+    pub total: u32,
+}
+
+impl CategoryProgress {
+    /// How much of the category is solved, from 0 to 100
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        f64::from(self.solved) * 100.0 / f64::from(self.total)
+    }
+}
+
+/// The progress of the user per grade and chapter, returned by
+/// [PbinfoUser::get_progress_summary]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressSummary {
+    pub categories: Vec<CategoryProgress>,
+}
+
+impl ProgressSummary {
+    /// Only the categories that are grades
+    pub fn grades(&self) -> impl Iterator<Item = &CategoryProgress> {
+        self.categories
+            .iter()
+            .filter(|category| category.grade.is_some())
+    }
+}
+
+/// The username and the display name of the logged in user
+async fn own_username(pbinfo_user: &PbinfoUser) -> Result<(String, Option<String>), ProfileError> {
+    let user = login::get_page_user(pbinfo_user, None)
+        .await
+        .map_err(|err| ProfileError::RequestError {
+            err: err.to_string(),
+        })?;
+    if user.id == "0" {
+        return Err(ProfileError::NotLoggedInError);
+    }
+    let username = user.username.ok_or_else(|| ProfileError::ParseError {
+        err: "The page didn't have the username of the user".to_string(),
+    })?;
+    Ok((username, user.display_name))
+}
+
+/// Downloads the html of the profile page of a user
+async fn get_profile_page(
+    user: &UserRef,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, ProfileError> {
+    let (endpoint, username) = match user {
+        UserRef::Id(id) => (endpoints::Profile::by_id(id), id),
+        UserRef::Username(username) => (endpoints::Profile::new(username), username),
+    };
+    let endpoint = endpoint.ssid(pbinfo_user.ssid());
+
+    let request_error = |err: String| ProfileError::RequestError { err };
+    let response = http::send(&pbinfo_user.client, &endpoint)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(ProfileError::NotFoundError {
+            user: username.clone(),
+        });
+    }
+    http::text(response)
+        .await
+        .map_err(|err| request_error(err.to_string()))
+}
+
+/// Finds the username of the logged in user and scrapes their profile page
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+pub async fn get_profile(pbinfo_user: &PbinfoUser) -> Result<UserProfile, ProfileError> {
+    let (username, display_name) = own_username(pbinfo_user).await?;
+    let html = get_profile_page(&UserRef::Username(username.clone()), pbinfo_user).await?;
+    let mut profile = parse::profile(&username, &html);
+    if profile.display_name.is_none() {
+        profile.display_name = display_name;
+    }
+    Ok(profile)
+}
+
+/// Reads the progress bars of the profile page of the logged in user
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+pub async fn get_progress_summary(
+    pbinfo_user: &PbinfoUser,
+) -> Result<ProgressSummary, ProfileError> {
+    let (username, _) = own_username(pbinfo_user).await?;
+    let html = get_profile_page(&UserRef::Username(username), pbinfo_user).await?;
+    Ok(parse::progress_summary(&html))
+}
+
+/// Scrapes the solved problems page of a user
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn get_problem_progress(
+    user_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<ProblemProgress, ProfileError> {
+    let request_error = |err: String| ProfileError::RequestError { err };
+    let endpoint = endpoints::SolvedProblems::new(user_id).ssid(pbinfo_user.ssid());
+    let response = http::send(&pbinfo_user.client, &endpoint)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+    let html = http::text(response)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+    Ok(parse::solved_problems(&html))
+}
+
+/// Scrapes the achievements page of a user
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn get_achievements(
+    username: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Vec<Achievement>, ProfileError> {
+    let request_error = |err: String| ProfileError::RequestError { err };
+    let endpoint = endpoints::Achievements::new(username).ssid(pbinfo_user.ssid());
+    let response = http::send(&pbinfo_user.client, &endpoint)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(ProfileError::NotFoundError {
+            user: username.to_string(),
+        });
+    }
+    let html = http::text(response)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+    Ok(parse::achievements(&html))
+}
+
+/// Scrapes the public profile page of any user
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn get_user_profile(
+    user: &UserRef,
+    pbinfo_user: &PbinfoUser,
+) -> Result<UserProfile, ProfileError> {
+    let html = get_profile_page(user, pbinfo_user).await?;
+    let username = match user {
+        UserRef::Id(id) => id,
+        UserRef::Username(username) => username,
+    };
+    Ok(parse::profile(username, &html))
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    endpoints::{self, RANKING_PER_PAGE},
+    http, parse,
+    pbinfo_user::PbinfoUser,
+};
+
+#[derive(Error, Debug)]
+pub enum RankingError {
+    #[error("Error: Couldn't get the ranking!\nGot error:\n{err}")]
+    RequestError { err: String },
+}
+
+/// Which users to rank with [PbinfoUser::get_ranking]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RankingScope {
+    /// Everyone on pbinfo
+    #[default]
+    Global,
+    /// The users of a county, by the code pbinfo has in its urls
+    County(String),
+    /// The users of a school, by the id pbinfo has in its urls
+    School(String),
+}
+
+/// A user in a [RankingPage]. Everything the page doesn't show is `None`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankingEntry {
+    /// The place of the user, starting from 1
+    pub position: u32,
+    /// The name shown in the ranking
+    pub name: String,
+    /// The name used in the url of the profile of the user
+    pub username: Option<String>,
+    pub school: Option<String>,
+    /// How many problems the user solved
+    pub solved: Option<u32>,
+    pub points: Option<u64>,
+}
+
+/// A page of a ranking, returned by [PbinfoUser::get_ranking]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankingPage {
+    /// The page, starting from 1
+    pub page: u32,
+    pub entries: Vec<RankingEntry>,
+}
+
+impl RankingPage {
+    /// Whether there are users on the pages after this one
+    pub fn has_more(&self) -> bool {
+        self.entries.len() >= RANKING_PER_PAGE as usize
+    }
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn get_ranking(
+    scope: &RankingScope,
+    page: u32,
+    pbinfo_user: &PbinfoUser,
+) -> Result<RankingPage, RankingError> {
+    let page = page.max(1);
+    let mut endpoint = endpoints::Ranking::new()
+        .page(page)
+        .ssid(pbinfo_user.ssid());
+    match scope {
+        RankingScope::Global => (),
+        RankingScope::County(county) => endpoint = endpoint.county(county),
+        RankingScope::School(school) => endpoint = endpoint.school(school),
+    }
+
+    let request_error = |err: String| RankingError::RequestError { err };
+    let response = http::send(&pbinfo_user.client, &endpoint)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+    let html = http::text(response)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+    Ok(RankingPage {
+        page,
+        entries: parse::ranking(&html),
+    })
+}
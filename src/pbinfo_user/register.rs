@@ -0,0 +1,208 @@
+use thiserror::Error;
+
+use crate::{
+    endpoints, http, models,
+    pbinfo_user::{login, LoginKind, PbinfoUser},
+};
+
+/// The shortest password [RegistrationRequest::validate] accepts
+pub const MIN_PASSWORD_LEN: usize = 6;
+/// The shortest and longest username [RegistrationRequest::validate] accepts
+pub const USERNAME_LEN: std::ops::RangeInclusive<usize> = 3..=30;
+
+const FORM_EXPIRED: &str = "Formularul a expirat. Încearcă din nou!";
+
+#[derive(Error, Debug)]
+pub enum RegisterError {
+    #[error("Error: `{username}` can't be a pbinfo username!\nUsernames have 3 to 30 letters, digits, `_`, `.` or `-`!")]
+    InvalidUsernameError { username: String },
+    #[error("Error: `{email}` isn't a valid email!")]
+    InvalidEmailError { email: String },
+    #[error("Error: The password has to be at least {min_len} characters long!")]
+    WeakPasswordError { min_len: usize },
+    #[error("Error: The county and the school of the account have to be filled in!")]
+    MissingSchoolError,
+    #[error("Error: The username `{username}` is already taken!")]
+    UsernameTakenError { username: String },
+    #[error("Error: There already is an account with the email `{email}`!")]
+    EmailTakenError { email: String },
+    #[error("Error: pbinfo didn't create the account!\nIt said:\n{message}")]
+    RejectedError { message: String },
+    #[error("Error: Couldn't send the registration!\nGot error:\n{err}")]
+    RequestError { err: String },
+}
+
+/// A new account, for [PbinfoUser::register]
+#[derive(Debug, Clone, Default)]
+pub struct RegistrationRequest {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    /// The county of the school, like `Cluj`
+    pub county: String,
+    /// The school as it is named on pbinfo
+    pub school: String,
+}
+
+impl RegistrationRequest {
+    /// Checks the fields the way the sign up form does, so obviously wrong
+    /// accounts are caught without sending anything
+    pub fn validate(&self) -> Result<(), RegisterError> {
+        let username = self.username.trim();
+        if LoginKind::detect(username) != Some(LoginKind::Username)
+            || !USERNAME_LEN.contains(&username.chars().count())
+        {
+            return Err(RegisterError::InvalidUsernameError {
+                username: self.username.clone(),
+            });
+        }
+        if LoginKind::detect(&self.email) != Some(LoginKind::Email) {
+            return Err(RegisterError::InvalidEmailError {
+                email: self.email.clone(),
+            });
+        }
+        if self.password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(RegisterError::WeakPasswordError {
+                min_len: MIN_PASSWORD_LEN,
+            });
+        }
+        if self.county.trim().is_empty() || self.school.trim().is_empty() {
+            return Err(RegisterError::MissingSchoolError);
+        }
+        Ok(())
+    }
+}
+
+/// What pbinfo answers with once the account was created, compared without
+/// the case and the final punctuation
+const REGISTERED: [&str; 3] = [
+    "Contul a fost creat",
+    "Contul a fost creat cu succes",
+    "Contul a fost creat cu succes. Verifică adresa de email pentru a-l activa",
+];
+
+/// Tells apart what pbinfo answered with, `Ok` once the account was created.
+/// The taken account errors are looked for first, anything that isn't a
+/// whole success message is a [RegisterError::RejectedError]
+fn read_answer(request: &RegistrationRequest, message: &str) -> Result<(), RegisterError> {
+    let normalize = |message: &str| {
+        message
+            .trim()
+            .trim_end_matches(['!', '.'])
+            .trim_end()
+            .to_lowercase()
+    };
+    let lowercase = normalize(message);
+    let taken = ["exist", "folosit", "ocupat"]
+        .iter()
+        .any(|marker| lowercase.contains(marker));
+    if taken && lowercase.contains("email") {
+        Err(RegisterError::EmailTakenError {
+            email: request.email.clone(),
+        })
+    } else if taken && (lowercase.contains("utilizator") || lowercase.contains("user")) {
+        Err(RegisterError::UsernameTakenError {
+            username: request.username.clone(),
+        })
+    } else if REGISTERED
+        .iter()
+        .any(|registered| normalize(registered) == lowercase)
+    {
+        Ok(())
+    } else {
+        Err(RegisterError::RejectedError {
+            message: message.to_string(),
+        })
+    }
+}
+
+/// Creates the account and returns a user that logs in with it. pbinfo may
+/// ask for the email to be confirmed before the first login works
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(username = request.username), err))]
+pub async fn register(request: &RegistrationRequest) -> Result<PbinfoUser, RegisterError> {
+    request.validate()?;
+    let request_error = |err: String| RegisterError::RequestError { err };
+
+    let mut pbinfo_user = PbinfoUser::builder()
+        .email(&request.username)
+        .password(&request.password)
+        .build();
+    let mut form_token = login::fetch_form_token(&pbinfo_user, None)
+        .await
+        .map_err(|err| request_error(err.to_string()))?;
+
+    // the form can expire once between getting the token and posting it
+    for _ in 0..2 {
+        let endpoint = endpoints::Register::new(
+            request.username.trim(),
+            request.email.trim(),
+            &request.password,
+        )
+        .county(&request.county)
+        .school(&request.school)
+        .form_token(&form_token)
+        .ssid(pbinfo_user.ssid());
+        let response = http::send(&pbinfo_user.client, &endpoint)
+            .await
+            .map_err(|err| request_error(err.to_string()))?;
+        let text = http::text(response)
+            .await
+            .map_err(|err| request_error(err.to_string()))?;
+        let answer: models::LoginResponse =
+            models::from_json(&text).map_err(|err| request_error(err.to_string()))?;
+
+        match answer.form_token {
+            Some(new_token) if answer.raspuns == FORM_EXPIRED => form_token = new_token,
+            _ => {
+                read_answer(request, &answer.raspuns)?;
+                trace_event!(tracing::Level::INFO, "registered a new account");
+                pbinfo_user.session_mut().form_token = form_token;
+                return Ok(pbinfo_user);
+            }
+        }
+    }
+    Err(RegisterError::RejectedError {
+        message: FORM_EXPIRED.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> RegistrationRequest {
+        RegistrationRequest {
+            username: "ionpopescu".to_string(),
+            email: "ion@example.com".to_string(),
+            password: "parola123".to_string(),
+            county: "Cluj".to_string(),
+            school: "Colegiul Național".to_string(),
+        }
+    }
+
+    #[test]
+    fn read_answer_accepts_whole_success_messages() {
+        assert!(read_answer(&request(), "Contul a fost creat cu succes!").is_ok());
+        assert!(read_answer(&request(), " contul a fost creat. ").is_ok());
+    }
+
+    #[test]
+    fn read_answer_rejects_failures_that_look_like_success() {
+        assert!(matches!(
+            read_answer(&request(), "Contul nu a fost creat!"),
+            Err(RegisterError::RejectedError { .. })
+        ));
+        assert!(matches!(
+            read_answer(&request(), "Contul este inactiv!"),
+            Err(RegisterError::RejectedError { .. })
+        ));
+        assert!(matches!(
+            read_answer(&request(), "Există deja un cont activ cu acest email!"),
+            Err(RegisterError::EmailTakenError { .. })
+        ));
+        assert!(matches!(
+            read_answer(&request(), "Numele de utilizator este deja folosit!"),
+            Err(RegisterError::UsernameTakenError { .. })
+        ));
+    }
+}
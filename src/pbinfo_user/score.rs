@@ -1,11 +1,18 @@
-use std::{future::Future, time::Duration};
+use std::future::Future;
+use std::time::Duration;
 
-use reqwest::header::{HeaderValue, InvalidHeaderValue};
+use reqwest::header::InvalidHeaderValue;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
 
-use crate::pbinfo_user::PbinfoUser;
+use crate::{
+    endpoints,
+    http::{self, BodyError},
+    models::{ScoreReport, SolutionEntry},
+    parse::{self, ParseError},
+    pbinfo_user::{login, problem, PbinfoUser},
+};
 
 #[derive(Error, Debug)]
 pub enum GetScoreError {
@@ -15,8 +22,32 @@ pub enum GetScoreError {
         "Error: Couldn't parse a response json while getting a score:\n{json}\nError was: {err}"
     )]
     ParseJsonError { json: String, err: String },
-    #[error("Error: The execution of a problem timed out!\nA problem took longer than 30 seconds to evaluate!")]
-    TimeoutError,
+    #[error("Error: The execution of a problem timed out!\nA problem took longer than {waited:?} to evaluate!")]
+    TimeoutError {
+        /// How long the score was polled for, [PollOptions::interval] times
+        /// [PollOptions::max_attempts]
+        waited: Duration,
+    },
+    #[error("Error: The session expired, pbinfo answered with the login page!\nLog in again!")]
+    SessionExpired,
+}
+
+impl From<BodyError> for GetScoreError {
+    fn from(err: BodyError) -> Self {
+        match err {
+            BodyError::SessionExpired => GetScoreError::SessionExpired,
+            BodyError::Request(err) => err.into(),
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for GetScoreError {
+    fn from(err: Box<dyn std::error::Error>) -> Self {
+        match err.downcast::<BodyError>() {
+            Ok(err) => (*err).into(),
+            Err(err) => GetScoreError::GenericError { err },
+        }
+    }
 }
 
 impl From<reqwest::Error> for GetScoreError {
@@ -31,101 +62,290 @@ impl From<InvalidHeaderValue> for GetScoreError {
     }
 }
 
+/// A finished evaluation, both typed and exactly as pbinfo sent it
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub report: ScoreReport,
+    /// The untouched json of the evaluation details
+    pub raw: Value,
+}
+
+impl Evaluation {
+    /// Types the untouched json of the evaluation details
+    pub fn from_value(raw: Value) -> Result<Self, GetScoreError> {
+        let report = parse::score_report(&raw).map_err(|err| match err {
+            ParseError::JsonError { json, err } => GetScoreError::ParseJsonError { json, err },
+            err => GetScoreError::GenericError { err: Box::new(err) },
+        })?;
+        Ok(Evaluation { report, raw })
+    }
+}
+
+/// How [PbinfoUser::pool_score_with] checks on an evaluation
+#[derive(Debug, Clone)]
+pub struct PollOptions {
+    /// How long to wait between two checks. Defaults to 1500 milliseconds
+    pub interval: Duration,
+    /// How many times the score is checked before giving up with
+    /// [GetScoreError::TimeoutError]. Defaults to 60
+    pub max_attempts: u32,
+}
+
+#[cfg(feature = "tokio")]
+impl PollOptions {
+    fn timeout_error(&self) -> GetScoreError {
+        GetScoreError::TimeoutError {
+            waited: self.interval.saturating_mul(self.max_attempts),
+        }
+    }
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        PollOptions {
+            interval: Duration::from_millis(1500),
+            max_attempts: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum ScoreStatus {
-    DoneExecuting { value: Value },
+    /// The evaluation is done, [Evaluation::raw] keeps the json for the
+    /// fields [ScoreReport] doesn't know about
+    DoneExecuting {
+        evaluation: Box<Evaluation>,
+    },
+    /// The source didn't compile, `message` is what the compiler printed
+    CompileError {
+        message: String,
+        evaluation: Box<Evaluation>,
+    },
     StillExecuting,
 }
 
+/// Returns the evaluation details of a given solution exactly as pbinfo
+/// sent them (a json string)
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn get_score_raw(
+    sol_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, GetScoreError> {
+    get_score_raw_keeping(sol_id, pbinfo_user, &mut false).await
+}
+
+async fn get_score_raw_once(
+    sol_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, GetScoreError> {
+    let endpoint = endpoints::EvaluationDetails::new(sol_id).ssid(pbinfo_user.ssid());
+
+    let response = http::send(&pbinfo_user.client, &endpoint).await?;
+    Ok(http::json_body(response).await?)
+}
+
+/// [get_score_raw] that logs in again once the session expired, keeping the
+/// new session on the user and setting `relogged` so a long poll logs in at
+/// most once
+async fn get_score_raw_keeping(
+    sol_id: &str,
+    pbinfo_user: &PbinfoUser,
+    relogged: &mut bool,
+) -> Result<String, GetScoreError> {
+    match get_score_raw_once(sol_id, pbinfo_user).await {
+        Err(GetScoreError::SessionExpired) if !*relogged && pbinfo_user.auto_relogin() => {
+            *relogged = true;
+            login::relogin(pbinfo_user)
+                .await
+                .map_err(|_| GetScoreError::SessionExpired)?;
+            get_score_raw_once(sol_id, pbinfo_user).await
+        }
+        result => result,
+    }
+}
+
 /// Returns the score of a given solution
 pub async fn get_score(
     sol_id: &str,
     pbinfo_user: &PbinfoUser,
 ) -> Result<ScoreStatus, GetScoreError> {
-    let client = reqwest::Client::builder().build()?;
-
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        "Cookie",
-        HeaderValue::from_str(&format!("SSID={}", pbinfo_user.ssid))?,
-    );
-    let request = client
-        .request(
-            reqwest::Method::POST,
-            format!(
-                "https://www.pbinfo.ro/ajx-module/ajx-solutie-detalii-evaluare.php?force_reload&id={sol_id}"
-            ),
-        )
-        .headers(headers);
-
-    let response = request.send().await?;
-    let text = response.text().await?;
-
-    let table: Value =
-        serde_json::from_str(&text).map_err(|err| GetScoreError::ParseJsonError {
-            json: text,
-            err: err.to_string(),
-        })?;
+    get_score_keeping(sol_id, pbinfo_user, &mut false).await
+}
 
-    if table["status_sursa"] == "executing" || table["status_sursa"] == "pending" {
-        return Ok(ScoreStatus::StillExecuting);
-    }
+async fn get_score_keeping(
+    sol_id: &str,
+    pbinfo_user: &PbinfoUser,
+    relogged: &mut bool,
+) -> Result<ScoreStatus, GetScoreError> {
+    let text = get_score_raw_keeping(sol_id, pbinfo_user, relogged).await?;
 
-    Ok(ScoreStatus::DoneExecuting { value: table })
+    parse::score_status(&text).map_err(|err| match err {
+        ParseError::JsonError { json, err } => GetScoreError::ParseJsonError { json, err },
+        err => GetScoreError::GenericError { err: Box::new(err) },
+    })
+}
+
+#[cfg(feature = "tokio")]
+#[derive(Error, Debug)]
+pub enum UploadAndWaitError {
+    #[error("{err}")]
+    UploadError { err: super::UploadError },
+    #[error("Error: Couldn't get the score of the solution {solution_id}!\nGot error:\n{err}")]
+    ScoreError {
+        solution_id: String,
+        err: GetScoreError,
+    },
+    #[error("Error: The upload and the evaluation took longer than {timeout:?}!")]
+    DeadlineExceeded {
+        /// Set if the solution was uploaded before the deadline
+        solution_id: Option<String>,
+        timeout: Duration,
+    },
+}
+
+/// Uploads a source and waits for its evaluation, all within `timeout`
+#[cfg(feature = "tokio")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip(source, pbinfo_user), err)
+)]
+pub async fn upload_and_wait(
+    problem_id: &str,
+    source: &str,
+    timeout: Option<Duration>,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Evaluation, UploadAndWaitError> {
+    use super::deadline::{self, Deadline};
+
+    let deadline = Deadline::new(timeout);
+
+    let solution_id = deadline::within(
+        deadline,
+        super::upload::upload(problem_id, source, pbinfo_user),
+    )
+    .await
+    .map_err(|timeout| UploadAndWaitError::DeadlineExceeded {
+        solution_id: None,
+        timeout,
+    })?
+    .map_err(|err| UploadAndWaitError::UploadError { err })?;
+
+    let options = PollOptions::default();
+    let poll = pool_score(&solution_id, &options, pbinfo_user);
+    match deadline::within(deadline, poll).await {
+        Ok(Ok(evaluation)) => Ok(evaluation),
+        Ok(Err(err)) => Err(UploadAndWaitError::ScoreError { solution_id, err }),
+        Err(timeout) => Err(UploadAndWaitError::DeadlineExceeded {
+            solution_id: Some(solution_id),
+            timeout,
+        }),
+    }
 }
 
-/// Awaits the score to finish evaluation while pooling it every 1500 milliseconds
+/// Awaits the score to finish evaluation while pooling it every
+/// `options.interval`
+#[cfg(feature = "tokio")]
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
 pub async fn pool_score(
     solution_id: &str,
+    options: &PollOptions,
     pbinfo_user: &PbinfoUser,
-) -> Result<Value, GetScoreError> {
-    let mut tries = 60;
-    tokio::time::sleep(Duration::from_millis(1500)).await;
+) -> Result<Evaluation, GetScoreError> {
+    pool_score_with_progress(solution_id, options, |_| (), pbinfo_user).await
+}
+
+/// Same as [pool_score] but calls `on_progress` with the status got on
+/// every poll, the last one included
+#[cfg(feature = "tokio")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip(on_progress, pbinfo_user), err)
+)]
+pub async fn pool_score_with_progress(
+    solution_id: &str,
+    options: &PollOptions,
+    mut on_progress: impl FnMut(&ScoreStatus),
+    pbinfo_user: &PbinfoUser,
+) -> Result<Evaluation, GetScoreError> {
+    #[cfg(feature = "metrics")]
+    let started = std::time::Instant::now();
+
+    let mut tries = options.max_attempts;
+    let mut relogged = false;
+    tokio::time::sleep(options.interval).await;
     while tries > 0 {
-        match get_score(solution_id, pbinfo_user).await? {
+        let status = get_score_keeping(solution_id, pbinfo_user, &mut relogged).await?;
+        on_progress(&status);
+        match status {
             ScoreStatus::StillExecuting => {
-                tokio::time::sleep(Duration::from_millis(1500)).await;
+                trace_event!(tracing::Level::DEBUG, tries_left = tries, "still executing");
+                tokio::time::sleep(options.interval).await;
             }
-            ScoreStatus::DoneExecuting { value } => {
+            ScoreStatus::DoneExecuting { evaluation }
+            | ScoreStatus::CompileError { evaluation, .. } => {
+                trace_event!(tracing::Level::INFO, "evaluation finished");
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_evaluation_wait(started.elapsed());
                 // one last force_reload of the score so that pbinfo
                 // actually displays the score on the site
-                let _ = get_score(solution_id, pbinfo_user).await;
-                return Ok(value);
+                let _ = get_score_keeping(solution_id, pbinfo_user, &mut relogged).await;
+                return Ok(*evaluation);
             }
         }
         tries -= 1;
     }
 
-    Err(GetScoreError::TimeoutError)
+    trace_event!(tracing::Level::WARN, "evaluation timed out");
+    Err(options.timeout_error())
 }
 
-async fn check_problem_exists(
-    problem_id: &str,
-    pbinfo_user: &PbinfoUser,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::builder().build()?;
-
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert("Cookie", format!("SSID={}", pbinfo_user.ssid).parse()?);
-    let request = client
-        .request(
-            reqwest::Method::POST,
-            format!("https://www.pbinfo.ro/probleme/{problem_id}"),
-        )
-        .headers(headers);
+/// Polls the score of a solution every `options.interval`, yielding every
+/// status it gets. Ends after the evaluation finished, after an error or
+/// with a [GetScoreError::TimeoutError] after `options.max_attempts` polls
+#[cfg(feature = "tokio")]
+pub fn watch_score<'a>(
+    solution_id: &'a str,
+    options: &'a PollOptions,
+    pbinfo_user: &'a PbinfoUser,
+) -> impl futures_util::Stream<Item = Result<ScoreStatus, GetScoreError>> + 'a {
+    futures_util::stream::unfold(Some((0, false)), move |state| async move {
+        let (attempt, mut relogged) = state?;
+        if attempt >= options.max_attempts {
+            return Some((Err(options.timeout_error()), None));
+        }
 
-    let response = request.send().await?;
-    return Ok(response.status() == reqwest::StatusCode::OK);
+        tokio::time::sleep(options.interval).await;
+        let status = get_score_keeping(solution_id, pbinfo_user, &mut relogged).await;
+        let next = match &status {
+            Ok(ScoreStatus::StillExecuting) => Some((attempt + 1, relogged)),
+            _ => None,
+        };
+        Some((status, next))
+    })
 }
 
 async fn try_repeated<T, E, F, Fut>(attempts: u32, f: F) -> Result<T, E>
 where
     F: Fn() -> Fut,
     Fut: Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
 {
-    for _ in 0..attempts {
-        match f().await {
+    for _attempt in 0..attempts {
+        let fut = f();
+        #[cfg(feature = "tracing")]
+        let fut = tracing::Instrument::instrument(
+            fut,
+            tracing::info_span!("attempt", attempt = _attempt),
+        );
+
+        match fut.await {
             Ok(ok) => return Ok(ok),
-            Err(_) => continue,
+            Err(_err) => {
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_retry();
+                trace_event!(tracing::Level::WARN, attempt = _attempt, error = %_err, "attempt failed, retrying");
+                continue;
+            }
         }
     }
     f().await
@@ -147,50 +367,99 @@ pub enum TopSolutionResponseType {
     PageError(String),
 }
 
-async fn get_last_n_solutions(
+async fn get_last_n_solutions_text(
+    problem_id: &str,
+    sol_number: u32,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let endpoint = endpoints::SolutionList::new(problem_id, pbinfo_user.user_id())
+        .count(sol_number)
+        .ssid(pbinfo_user.ssid());
+
+    let response = http::send(&pbinfo_user.client, &endpoint).await?;
+    Ok(http::json_body(response).await?)
+}
+
+async fn get_last_n_solutions_raw(
     problem_id: &str,
     sol_number: u32,
     pbinfo_user: &PbinfoUser,
 ) -> Result<Value, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::builder().build()?;
-
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert("Cookie", format!("SSID={}", pbinfo_user.ssid).parse()?);
-    let request = client
-        .request(
-            reqwest::Method::POST,
-            format!(
-                "https://www.pbinfo.ro/ajx-module/ajx-solutii-lista-json.php?id_problema={problem_id}&id_user={}&numar_solutii={sol_number}"
-                , pbinfo_user.user_id
-            ),
-        )
-        .headers(headers);
-
-    let response = request.send().await?;
-    let text = response.text().await?;
+    let text = get_last_n_solutions_text(problem_id, sol_number, pbinfo_user).await?;
     Ok(serde_json::from_str(&text)?)
 }
 
+/// What [get_top_score] needs to know about the solutions of a problem
+struct SolutionScores {
+    /// `numar_total_solutii`
+    total: i64,
+    /// The first of the solutions that got 100 points
+    perfect: Option<SolutionEntry>,
+}
+
+/// Goes through the last `sol_number` solutions as they are downloaded,
+/// without keeping the list around
+async fn get_last_n_solutions_scores(
+    problem_id: &str,
+    sol_number: u32,
+    pbinfo_user: &PbinfoUser,
+) -> Result<SolutionScores, Box<dyn std::error::Error>> {
+    let endpoint = endpoints::SolutionList::new(problem_id, pbinfo_user.user_id())
+        .count(sol_number)
+        .ssid(pbinfo_user.ssid());
+
+    let response = http::send(&pbinfo_user.client, &endpoint).await?;
+
+    let mut perfect = None;
+    let total = http::for_each_solution(response, |solution| {
+        let score = solution
+            .scor
+            .as_deref()
+            .unwrap_or("-2")
+            .parse::<i64>()
+            .map_err(|err| {
+                format!(
+                    "Couldn't parse a score for a solution!\nSolution was {solution:?}\nParse Error was {err}"
+                )
+            })?;
+        if score == 100 && perfect.is_none() {
+            perfect = Some(solution);
+        }
+        Ok(())
+    })
+    .await?;
+
+    Ok(SolutionScores { total, perfect })
+}
+
 /// Returns information about the top solution given to a problem
 /// (if it has been solved, is the solution perfect, does problem even
 /// exist, etc...)
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), ret))]
 pub async fn get_top_score(problem_id: &str, pbinfo_user: &PbinfoUser) -> TopSolutionResponseType {
-    match try_repeated(3, || check_problem_exists(problem_id, pbinfo_user)).await {
+    match try_repeated(3, || problem::problem_exists(problem_id, pbinfo_user)).await {
         Ok(false) => return TopSolutionResponseType::ProblemNotFound,
         Ok(true) => (),
         Err(err) => return TopSolutionResponseType::PageError(err.to_string()),
     };
 
-    let last_solution =
-        match try_repeated(3, || get_last_n_solutions(problem_id, 1, pbinfo_user)).await {
-            Ok(ok) => ok,
-            Err(err) => return TopSolutionResponseType::PageError(err.to_string()),
-        };
+    let last_solution = match try_repeated(3, || {
+        get_last_n_solutions_scores(problem_id, 1, pbinfo_user)
+    })
+    .await
+    {
+        Ok(ok) => ok,
+        Err(err) => return TopSolutionResponseType::PageError(err.to_string()),
+    };
 
-    let sol_number = match last_solution["numar_total_solutii"].as_i64(){
-        Some(some) if u32::try_from(some).is_ok() => some as u32,
-        Some(some) => return TopSolutionResponseType::PageError(format!("numar_total_solutii couldn't be parsed to an u32\nnumar_total_solutii was {some}")),
-        None=> return TopSolutionResponseType::PageError(format!("numar_total_solutii couldn't be found in response json as an int!\nResponse json was: {}",last_solution.to_string())),
+    let sol_number = match u32::try_from(last_solution.total) {
+        Ok(ok) => ok,
+        Err(_) => {
+            return TopSolutionResponseType::PageError(format!(
+                "numar_total_solutii couldn't be parsed to an u32\nnumar_total_solutii was {}",
+                last_solution.total
+            ))
+        }
     };
 
     if sol_number == 0 {
@@ -198,40 +467,161 @@ pub async fn get_top_score(problem_id: &str, pbinfo_user: &PbinfoUser) -> TopSol
     }
 
     let all_solutions = match try_repeated(3, || {
-        get_last_n_solutions(problem_id, sol_number as u32, pbinfo_user)
+        get_last_n_solutions_scores(problem_id, sol_number, pbinfo_user)
     })
     .await
     {
-        Ok(ok) if ok["surse"].is_array() => {
-            let array = ok["surse"].as_array().unwrap().clone();
-            array
-        }
-        Ok(_) => {
-            return TopSolutionResponseType::PageError(format!(
-                "surse was not an array\nResponse was: {}",
-                last_solution.to_string()
-            ))
-        }
+        Ok(ok) => ok,
         Err(err) => return TopSolutionResponseType::PageError(err.to_string()),
     };
 
-    let scores = match all_solutions
-        .iter()
-        .map(|sol| sol["scor"].as_str().unwrap_or("-2").parse::<i64>())
-        .collect::<Result<Vec<i64>, _>>()
-    {
-        Ok(ok) => ok,
-        Err(err) => {
-            return TopSolutionResponseType::PageError(format!(
-            "Couldn't parse a score for a solution!\nSolution list was{:?}\nParse Error was {err}",
-            all_solutions
-        ))
+    if all_solutions.perfect.is_some() {
+        TopSolutionResponseType::PerfectSolution
+    } else {
+        TopSolutionResponseType::ImperfectSolution
+    }
+}
+
+/// Returns the first solution of the user that got 100 points on a problem,
+/// `None` if there is none
+pub async fn get_perfect_solution(
+    problem_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Option<SolutionEntry>, GetScoreError> {
+    let last_solution = try_repeated(3, || {
+        get_last_n_solutions_scores(problem_id, 1, pbinfo_user)
+    })
+    .await?;
+
+    let sol_number =
+        u32::try_from(last_solution.total).map_err(|_| GetScoreError::ParseJsonError {
+            json: last_solution.total.to_string(),
+            err: "numar_total_solutii couldn't be parsed to an u32".to_string(),
+        })?;
+    if sol_number <= 1 {
+        return Ok(last_solution.perfect);
+    }
+
+    let all_solutions = try_repeated(3, || {
+        get_last_n_solutions_scores(problem_id, sol_number, pbinfo_user)
+    })
+    .await?;
+    Ok(all_solutions.perfect)
+}
+
+/// Returns the list of all the solutions the user sent to a problem exactly
+/// as pbinfo sent it, this is what [get_top_score] is computed from
+pub async fn get_top_score_raw(
+    problem_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Value, GetScoreError> {
+    let last_solution =
+        try_repeated(3, || get_last_n_solutions_raw(problem_id, 1, pbinfo_user)).await?;
+
+    let sol_number = last_solution["numar_total_solutii"]
+        .as_i64()
+        .and_then(|some| u32::try_from(some).ok())
+        .ok_or_else(|| GetScoreError::ParseJsonError {
+            json: last_solution.to_string(),
+            err: "numar_total_solutii couldn't be parsed to an u32".to_string(),
+        })?;
+
+    if sol_number <= 1 {
+        return Ok(last_solution);
+    }
+
+    try_repeated(3, || {
+        get_last_n_solutions_raw(problem_id, sol_number, pbinfo_user)
+    })
+    .await
+    .map_err(GetScoreError::from)
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, SentRequest, LOGGED_IN, LOGGED_OUT, SCORE_DONE};
+
+    const EVALUATION_PATH: &str = "/ajx-module/ajx-solutie-detalii-evaluare.php";
+    const LOGIN_PATH: &str = "/ajx-module/php-login.php";
+
+    /// pbinfo for a session that expired: only `new-ssid` is logged in
+    fn expired_session(request: &SentRequest) -> ::http::Response<String> {
+        let logged_in = test_support::has_session(request, "new-ssid");
+        if request.path() == LOGIN_PATH {
+            test_support::login_answer("", Some("new-ssid"))
+        } else if request.path().starts_with(EVALUATION_PATH) && logged_in {
+            test_support::ok(SCORE_DONE)
+        } else if logged_in {
+            test_support::ok(LOGGED_IN)
+        } else {
+            test_support::ok(LOGGED_OUT)
         }
-    };
+    }
 
-    if scores.iter().any(|score| *score == 100) {
-        return TopSolutionResponseType::PerfectSolution;
-    } else {
-        return TopSolutionResponseType::ImperfectSolution;
+    fn user(client: crate::PbinfoClient, auto_relogin: bool) -> PbinfoUser {
+        PbinfoUser::builder()
+            .email("ion@example.com")
+            .password("parola")
+            .ssid("old-ssid")
+            .client(client)
+            .auto_relogin(auto_relogin)
+            .build()
+    }
+
+    fn logins(requests: &[SentRequest]) -> usize {
+        requests
+            .iter()
+            .filter(|request| request.path() == LOGIN_PATH)
+            .count()
+    }
+
+    #[tokio::test]
+    async fn the_login_page_is_a_session_expired_error() {
+        let (client, sent) = test_support::client(expired_session);
+        let pbinfo_user = user(client, false);
+
+        assert!(matches!(
+            get_score("1000", &pbinfo_user).await,
+            Err(GetScoreError::SessionExpired)
+        ));
+        assert_eq!(sent.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_log_in_again() {
+        let (client, sent) = test_support::client(expired_session);
+        let pbinfo_user = user(client, true);
+
+        assert!(matches!(
+            get_score("1000", &pbinfo_user).await,
+            Ok(ScoreStatus::DoneExecuting { .. })
+        ));
+        assert_eq!(pbinfo_user.ssid(), "new-ssid");
+        assert_eq!(logins(&sent.requests()), 1);
+    }
+
+    #[tokio::test]
+    async fn sessions_that_stay_expired_log_in_once() {
+        let (client, sent) =
+            test_support::client(
+                |request| match request.path().starts_with(EVALUATION_PATH) {
+                    true => test_support::ok(LOGGED_OUT),
+                    false => expired_session(request),
+                },
+            );
+        let pbinfo_user = user(client, true);
+
+        assert!(matches!(
+            get_score("1000", &pbinfo_user).await,
+            Err(GetScoreError::SessionExpired)
+        ));
+        let requests = sent.requests();
+        assert_eq!(logins(&requests), 1);
+        let evaluations = requests
+            .iter()
+            .filter(|request| request.path().starts_with(EVALUATION_PATH))
+            .count();
+        assert_eq!(evaluations, 2);
     }
 }
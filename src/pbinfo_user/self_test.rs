@@ -0,0 +1,138 @@
+use crate::{
+    endpoints::{self, Endpoint},
+    http, parse,
+    pbinfo_user::PbinfoUser,
+};
+
+/// The problem whose page is used for the checks, the a+b problem
+const PROBE_PROBLEM_ID: &str = "1";
+
+/// One parser checked by [PbinfoUser::self_test]
+#[derive(Debug, Clone)]
+pub struct SelfTestCheck {
+    /// What was checked, like `user_id` or `source_field`
+    pub name: &'static str,
+    /// Why the parser failed, `None` if it worked
+    pub error: Option<String>,
+}
+
+/// Returned by [PbinfoUser::self_test]
+#[derive(Debug, Clone, Default)]
+pub struct SelfTestReport {
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    /// Whether every parser worked
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.error.is_none())
+    }
+
+    /// The checks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &SelfTestCheck> {
+        self.checks.iter().filter(|check| check.error.is_some())
+    }
+
+    fn push(&mut self, name: &'static str, result: Result<(), String>) {
+        trace_event!(
+            tracing::Level::INFO,
+            name,
+            ok = result.is_ok(),
+            "self test check"
+        );
+        self.checks.push(SelfTestCheck {
+            name,
+            error: result.err(),
+        });
+    }
+}
+
+async fn get_page<E: Endpoint>(endpoint: &E, pbinfo_user: &PbinfoUser) -> Result<String, String> {
+    let response = http::send(&pbinfo_user.client, endpoint)
+        .await
+        .map_err(|err| err.to_string())?;
+    http::text(response).await.map_err(|err| err.to_string())
+}
+
+async fn check_solution_list(pbinfo_user: &PbinfoUser) -> Result<(), String> {
+    let endpoint = endpoints::SolutionList::new(PROBE_PROBLEM_ID, pbinfo_user.user_id())
+        .ssid(pbinfo_user.ssid());
+    let response = http::send(&pbinfo_user.client, &endpoint)
+        .await
+        .map_err(|err| err.to_string())?;
+    http::for_each_solution(response, |_| Ok(()))
+        .await
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// Fetches the pages the crate scrapes and runs every parser on them, so a
+/// change in the layout of pbinfo is noticed before it breaks anything
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub async fn self_test(pbinfo_user: &PbinfoUser) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+
+    let homepage = get_page(
+        &endpoints::Homepage::new().ssid(pbinfo_user.ssid()),
+        pbinfo_user,
+    )
+    .await;
+    report.push(
+        "user_id",
+        homepage
+            .as_deref()
+            .map_err(|err| err.clone())
+            .and_then(|html| {
+                parse::page_user(html)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            }),
+    );
+    report.push(
+        "form_token",
+        homepage
+            .as_deref()
+            .map_err(|err| err.clone())
+            .and_then(|html| {
+                parse::form_token(html)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            }),
+    );
+
+    let problem_page = get_page(
+        &endpoints::ProblemPage::new(PROBE_PROBLEM_ID).ssid(pbinfo_user.ssid()),
+        pbinfo_user,
+    )
+    .await;
+    report.push(
+        "problem",
+        problem_page
+            .as_deref()
+            .map_err(|err| err.clone())
+            .and_then(|html| {
+                parse::problem(PROBE_PROBLEM_ID, html)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            }),
+    );
+
+    let user_id = pbinfo_user.user_id();
+    let logged_in = !user_id.is_empty() && user_id != "0";
+    if !logged_in {
+        // the upload form and the solutions are only there for logged in users
+        return report;
+    }
+
+    report.push(
+        "source_field",
+        problem_page.and_then(|html| {
+            parse::source_field(&html)
+                .map(|_| ())
+                .map_err(|err| err.to_string())
+        }),
+    );
+    report.push("solution_list", check_solution_list(pbinfo_user).await);
+
+    report
+}
@@ -0,0 +1,285 @@
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::export;
+use crate::{
+    endpoints, http,
+    models::{self, SolutionEntry},
+    pbinfo_user::PbinfoUser,
+};
+
+/// How many solutions are on a page of [PbinfoUser::list_my_solutions]
+pub const SOLUTIONS_PER_PAGE: u32 = 20;
+
+#[derive(Error, Debug)]
+pub enum SolutionsError {
+    #[error("Error: Couldn't get the list of solutions!\nGot error:\n{err}")]
+    ListError { err: String },
+    #[error(
+        "Error: Couldn't download the source of the solution {solution_id}!\nGot error:\n{err}"
+    )]
+    SourceError { solution_id: String, err: String },
+}
+
+/// A solution in a [SolutionPage], the pages are newest first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolutionSummary {
+    pub id: String,
+    pub problem_id: Option<String>,
+    /// The score out of 100, `None` while the solution is evaluated
+    pub score: Option<u8>,
+    /// The language of the source, like `cpp` or `c`
+    pub language: Option<String>,
+    /// When the solution was uploaded, like `2024-03-15 18:04:12`
+    pub date: Option<String>,
+    /// Who sent the solution, only known for public solutions
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+impl SolutionSummary {
+    /// `None` for entries without an id
+    fn from_entry(entry: SolutionEntry) -> Option<Self> {
+        Some(SolutionSummary {
+            id: entry.solution_id()?,
+            problem_id: entry.problem_id(),
+            score: entry
+                .scor
+                .as_deref()
+                .and_then(|score| score.trim().parse().ok()),
+            date: entry.submitted_at(),
+            language: entry.limbaj,
+            author: entry.user,
+        })
+    }
+}
+
+/// A page of the solutions of the user, returned by
+/// [PbinfoUser::list_my_solutions]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolutionPage {
+    /// The page, starting from 1
+    pub page: u32,
+    pub solutions: Vec<SolutionSummary>,
+    /// How many solutions there are on all the pages
+    pub total: u64,
+}
+
+impl SolutionPage {
+    /// Whether there are solutions on the pages after this one
+    pub fn has_more(&self) -> bool {
+        u64::from(self.page) * u64::from(SOLUTIONS_PER_PAGE) < self.total
+    }
+}
+
+/// pbinfo only sends the last solutions, so a page is read by asking for all
+/// the solutions up to its end and skipping the ones of the pages before it
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn list_my_solutions(
+    problem_id: &str,
+    page: u32,
+    pbinfo_user: &PbinfoUser,
+) -> Result<SolutionPage, SolutionsError> {
+    let page = page.max(1);
+    let endpoint = endpoints::SolutionList::new(problem_id, pbinfo_user.user_id())
+        .count(page.saturating_mul(SOLUTIONS_PER_PAGE))
+        .ssid(pbinfo_user.ssid());
+    let skip = (page - 1) as usize * SOLUTIONS_PER_PAGE as usize;
+    read_page(&endpoint, page, skip, pbinfo_user).await
+}
+
+/// Same as [list_my_solutions] but for the solutions to every problem
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn list_all_solutions(
+    page: u32,
+    pbinfo_user: &PbinfoUser,
+) -> Result<SolutionPage, SolutionsError> {
+    let page = page.max(1);
+    let endpoint = endpoints::UserSolutionList::new(pbinfo_user.user_id())
+        .count(page.saturating_mul(SOLUTIONS_PER_PAGE))
+        .ssid(pbinfo_user.ssid());
+    let skip = (page - 1) as usize * SOLUTIONS_PER_PAGE as usize;
+    read_page(&endpoint, page, skip, pbinfo_user).await
+}
+
+/// Goes through the pages of [list_all_solutions] one after the other.
+/// Ends after the last page or after an error
+pub fn all_solutions(
+    pbinfo_user: &PbinfoUser,
+) -> impl futures_util::Stream<Item = Result<SolutionPage, SolutionsError>> + '_ {
+    futures_util::stream::once(all_solution_pages(pbinfo_user)).flat_map(|result| {
+        let pages = match result {
+            Ok(pages) => pages.into_iter().map(Ok).collect(),
+            Err(err) => vec![Err(err)],
+        };
+        futures_util::stream::iter(pages)
+    })
+}
+
+/// The first page tells how many solutions there are, then all of them are
+/// read in one request (with room for the ones sent in the meantime) and
+/// split into pages. Reading page after page would read the first pages
+/// over and over and shift everything when a solution is sent meanwhile
+async fn all_solution_pages(pbinfo_user: &PbinfoUser) -> Result<Vec<SolutionPage>, SolutionsError> {
+    let first = list_all_solutions(1, pbinfo_user).await?;
+    if !first.has_more() {
+        return Ok(vec![first]);
+    }
+
+    let mut total = first.total;
+    let all = loop {
+        let count = u32::try_from(total)
+            .unwrap_or(u32::MAX)
+            .saturating_add(SOLUTIONS_PER_PAGE);
+        let endpoint = endpoints::UserSolutionList::new(pbinfo_user.user_id())
+            .count(count)
+            .ssid(pbinfo_user.ssid());
+        let page = read_page(&endpoint, 1, 0, pbinfo_user).await?;
+        if page.total <= u64::from(count) || count == u32::MAX {
+            break page.solutions;
+        }
+        total = page.total;
+    };
+    let total = all.len() as u64;
+    Ok(all
+        .chunks(SOLUTIONS_PER_PAGE as usize)
+        .zip(1..)
+        .map(|(solutions, page)| SolutionPage {
+            page,
+            solutions: solutions.to_vec(),
+            total,
+        })
+        .collect())
+}
+
+/// Same as [list_my_solutions] but for the solutions other users made
+/// public for the problem
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn list_public_solutions(
+    problem_id: &str,
+    page: u32,
+    pbinfo_user: &PbinfoUser,
+) -> Result<SolutionPage, SolutionsError> {
+    let page = page.max(1);
+    let endpoint = endpoints::PublicSolutionList::new(problem_id)
+        .count(page.saturating_mul(SOLUTIONS_PER_PAGE))
+        .ssid(pbinfo_user.ssid());
+    let skip = (page - 1) as usize * SOLUTIONS_PER_PAGE as usize;
+    read_page(&endpoint, page, skip, pbinfo_user).await
+}
+
+/// The source of a solution another user made public
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn get_public_solution_source(
+    solution_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, SolutionsError> {
+    let source_error = |err: String| SolutionsError::SourceError {
+        solution_id: solution_id.to_string(),
+        err,
+    };
+    let endpoint = endpoints::SolutionSource::new(solution_id)
+        .public(true)
+        .ssid(pbinfo_user.ssid());
+    let response = http::send(&pbinfo_user.client, &endpoint)
+        .await
+        .map_err(|err| source_error(err.to_string()))?;
+    let json = http::json_body(response)
+        .await
+        .map_err(|err| source_error(err.to_string()))?;
+    models::from_json::<models::SolutionSource>(&json)
+        .map(|source| source.sursa)
+        .map_err(|err| source_error(err.to_string()))
+}
+
+/// The source of a solution exactly as it was uploaded, only works for
+/// solutions of the logged in user
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(pbinfo_user), err))]
+pub async fn get_solution_source(
+    solution_id: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, SolutionsError> {
+    export::get_source(solution_id, pbinfo_user)
+        .await
+        .map_err(|err| SolutionsError::SourceError {
+            solution_id: solution_id.to_string(),
+            err: err.to_string(),
+        })
+}
+
+async fn read_page<E: endpoints::Endpoint>(
+    endpoint: &E,
+    page: u32,
+    skip: usize,
+    pbinfo_user: &PbinfoUser,
+) -> Result<SolutionPage, SolutionsError> {
+    let list_error = |err: String| SolutionsError::ListError { err };
+    let response = http::send(&pbinfo_user.client, endpoint)
+        .await
+        .map_err(|err| list_error(err.to_string()))?;
+
+    let mut seen = 0;
+    let mut solutions = Vec::new();
+    let total = http::for_each_solution(response, |entry| {
+        seen += 1;
+        if seen > skip {
+            solutions.extend(SolutionSummary::from_entry(entry));
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|err| list_error(err.to_string()))?;
+
+    Ok(SolutionPage {
+        page,
+        solutions,
+        total: u64::try_from(total).unwrap_or_default(),
+    })
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use futures_util::TryStreamExt;
+
+    use super::*;
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn all_solutions_reads_every_solution_once() {
+        // a solution is sent after every request
+        let sent_solutions = AtomicU32::new(45);
+        let (client, sent) = test_support::client(move |request| {
+            let total = sent_solutions.fetch_add(1, Ordering::SeqCst);
+            let count: u32 = request
+                .url
+                .split_once("numar_solutii=")
+                .and_then(|(_, count)| count.parse().ok())
+                .unwrap();
+            let solutions: Vec<String> = (1..=total)
+                .rev()
+                .take(count as usize)
+                .map(|id| format!(r#"{{"id":"{id}","id_problema":"1","scor":"100"}}"#))
+                .collect();
+            test_support::ok(format!(
+                r#"{{"surse":[{}],"numar_total_solutii":{total}}}"#,
+                solutions.join(",")
+            ))
+        });
+        let pbinfo_user = PbinfoUser::builder().client(client).build();
+
+        let pages: Vec<SolutionPage> = all_solutions(&pbinfo_user).try_collect().await.unwrap();
+        let ids: Vec<&str> = pages
+            .iter()
+            .flat_map(|page| &page.solutions)
+            .map(|solution| solution.id.as_str())
+            .collect();
+        let expected: Vec<String> = (1..=46).rev().map(|id| id.to_string()).collect();
+        assert_eq!(ids, expected);
+        assert_eq!(pages.len(), 3);
+        assert!(!pages[2].has_more());
+        assert_eq!(sent.requests().len(), 2);
+    }
+}
@@ -1,120 +1,396 @@
-use std::sync::LazyLock;
+use std::collections::HashMap;
 
-use reqwest::StatusCode;
 use serde_json::Value;
 
-use super::upload::upload;
-use crate::pbinfo_user::PbinfoUser;
+use std::time::Duration;
 
+use super::{
+    deadline::{self, Deadline},
+    score::get_perfect_solution,
+    sources::{SolutionSource, SolutionSourceError, SolutionSources},
+    upload::{upload_with, UploadOptions},
+};
+use crate::{pbinfo_user::PbinfoUser, PbinfoClient};
+
+#[cfg(feature = "tokio")]
+use super::score::{pool_score, Evaluation, GetScoreError, PollOptions};
 use super::UploadError;
 
 #[derive(thiserror::Error, Debug)]
 enum GetSolutionError {
-    #[error("Couldn't find a solution for the problem {problem_id} on github codulluiandrei")]
-    NoGithubSolution { problem_id: String },
-    #[error("Couldn't create a reqwest client!\nGot error {err}")]
-    CreateReqwestClientError { err: String },
-    #[error("Couldn't send a request to the url: '{url}'\nGot error {err}")]
-    SendRequestError { err: String, url: String },
-    #[error("Couldn't parse the text in a response from url: '{url}'\nGot error {err}")]
-    RequestParseTextError { err: String, url: String },
+    #[error(
+        "Couldn't find a solution for the problem {problem_id} in any of the sources {tried:?}"
+    )]
+    NoSolution {
+        problem_id: String,
+        tried: Vec<String>,
+    },
+    #[error("The {name} source failed\n{err}")]
+    SourceFailed {
+        name: String,
+        err: SolutionSourceError,
+    },
 }
 
-static SOLUTIONS: LazyLock<Value> =
-    LazyLock::new(|| serde_json::from_str(include_str!("solutions.json")).unwrap());
-
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip(client, custom_solutions, sources), err)
+)]
 async fn get_raw_solution(
+    client: &PbinfoClient,
     problem_id: &str,
-    costume_solutions: Option<&Value>,
+    custom_solutions: Option<&dyn SolutionSource>,
+    sources: &SolutionSources,
 ) -> Result<String, GetSolutionError> {
-    if let Some(val) = costume_solutions {
-        if let Some(solution) = val[problem_id].as_str() {
-            println!("Found a solution user provided custom_solutions!");
-            return Ok(solution.to_string());
+    // a source that fails doesn't stop the ones after it, the error is only
+    // returned if none of them had a solution
+    let mut failed = None;
+    let consulted = || custom_solutions.into_iter().chain(sources.iter());
+    for source in consulted() {
+        match source.find(problem_id, client).await {
+            Ok(Some(solution)) => {
+                trace_event!(
+                    tracing::Level::INFO,
+                    source = source.name(),
+                    "found a solution"
+                );
+                return Ok(solution);
+            }
+            Ok(None) => (),
+            Err(err) => {
+                trace_event!(
+                    tracing::Level::WARN,
+                    source = source.name(),
+                    error = %err,
+                    "couldn't look in a solution source"
+                );
+                failed = Some(GetSolutionError::SourceFailed {
+                    name: source.name().to_string(),
+                    err,
+                });
+            }
         }
     }
+    Err(failed.unwrap_or_else(|| GetSolutionError::NoSolution {
+        problem_id: problem_id.to_string(),
+        tried: consulted()
+            .map(|source| source.name().to_string())
+            .collect(),
+    }))
+}
 
-    if let Some(solution) = SOLUTIONS[problem_id].as_str() {
-        println!("Found a solution from builtin solutions!");
-        return Ok(solution.to_string());
-    }
+#[derive(thiserror::Error, Debug)]
+pub enum SolveError {
+    #[error("Couldn't get a solution for the problem {problem_id}\nGot error{err}")]
+    GetSolutionError { problem_id: String, err: String },
+    #[error("Couldn't upload a solution for the problem {problem_id}\nGot error{}",err.to_string())]
+    UploadError {
+        problem_id: String,
+        err: UploadError,
+    },
+    #[error("Error: Solving the problem {problem_id} took longer than {timeout:?}!")]
+    DeadlineExceeded {
+        problem_id: String,
+        timeout: Duration,
+    },
+}
 
-    if SOLUTIONS[problem_id].is_string() {
-        println!("Found a solution in builtin solutions!");
-        return Ok(SOLUTIONS[problem_id].as_str().unwrap().to_string());
+/// How [crate::pbinfo_user::PbinfoUser::solve_with] behaves
+#[derive(Debug, Clone)]
+pub struct SolveOptions {
+    /// Don't upload anything if the user already got 100 points on the
+    /// problem. Defaults to `true`
+    pub skip_if_perfect: bool,
+    /// How long looking up and uploading the solution can take in total.
+    /// Only enforced with the `tokio` feature
+    pub timeout: Option<Duration>,
+    /// The id of the assignment ("tema") the solution is uploaded for, see
+    /// [UploadOptions::assignment]
+    pub assignment: Option<String>,
+    /// Where the solutions are looked up, in order. Defaults to the bundled
+    /// solutions and then github
+    pub sources: SolutionSources,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        SolveOptions {
+            skip_if_perfect: true,
+            timeout: None,
+            assignment: None,
+            sources: SolutionSources::default(),
+        }
     }
+}
 
-    let client = reqwest::Client::builder().build().map_err(|err| {
-        GetSolutionError::CreateReqwestClientError {
-            err: err.to_string(),
+impl SolveOptions {
+    /// How the solution is uploaded
+    pub(crate) fn upload_options(&self) -> UploadOptions {
+        UploadOptions {
+            assignment: self.assignment.clone(),
+            ..Default::default()
         }
-    })?;
+    }
+}
 
-    let url = format!("https://raw.githubusercontent.com/codulluiandrei/pbinfo/refs/heads/main/pbinfo-{problem_id}/main.cpp");
-    let response = client
-        .request(reqwest::Method::GET, &url)
-        .send()
-        .await
-        .map_err(|err| GetSolutionError::SendRequestError {
-            err: err.to_string(),
-            url: url.clone(),
-        })?;
+/// How [crate::pbinfo_user::PbinfoUser::solve_and_verify_with] solves and
+/// checks a problem
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub struct VerifyOptions {
+    pub solve: SolveOptions,
+    /// How the evaluation is waited for
+    pub poll: PollOptions,
+    /// The score below which the solve counts as failed. Defaults to 100
+    pub min_score: u8,
+}
 
-    if response.status() != StatusCode::OK {
-        return Err(GetSolutionError::NoGithubSolution {
-            problem_id: problem_id.to_string(),
-        });
+#[cfg(feature = "tokio")]
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        VerifyOptions {
+            solve: SolveOptions::default(),
+            poll: PollOptions::default(),
+            min_score: 100,
+        }
     }
-
-    let text = response
-        .text()
-        .await
-        .map_err(|err| GetSolutionError::RequestParseTextError {
-            err: err.to_string(),
-            url,
-        })?;
-    Ok(text)
 }
 
+#[cfg(feature = "tokio")]
 #[derive(thiserror::Error, Debug)]
-pub enum SolveError {
-    #[error("Couldn't get a solution for the problem {problem_id}\nGot error{err}")]
-    GetSolutionError { problem_id: String, err: String },
-    #[error("Couldn't upload a solution for the problem {problem_id}\nGot error{}",err.to_string())]
-    UploadError {
+pub enum SolveAndVerifyError {
+    #[error("{err}")]
+    SolveError { err: SolveError },
+    #[error(
+        "Error: The problem {problem_id} was skipped but there is no solution of it to check!"
+    )]
+    NothingToVerify { problem_id: String },
+    #[error("Error: Couldn't get the score of the solution {solution_id}!\nGot error:\n{err}")]
+    ScoreError {
+        solution_id: String,
+        err: GetScoreError,
+    },
+    #[error("Error: The solution {solution_id} of the problem {problem_id} got {score} points, at least {min_score} were needed!")]
+    BelowThreshold {
         problem_id: String,
-        err: UploadError,
+        solution_id: String,
+        score: u8,
+        min_score: u8,
+        evaluation: Box<Evaluation>,
+    },
+}
+
+/// What solving a problem ended up doing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveOutcome {
+    /// A solution was uploaded
+    Submitted { solution_id: String },
+    /// The user already had 100 points so nothing was uploaded
+    Skipped {
+        /// The solution that got the 100 points
+        existing_sol_id: Option<String>,
+        /// When that solution was uploaded, see [crate::models::SolutionEntry::submitted_at]
+        submitted_at: Option<String>,
     },
 }
 
+impl SolveOutcome {
+    /// Whether the problem already had 100 points, so no submission was
+    /// spent on it. See [SolveOptions::skip_if_perfect]
+    pub fn already_solved(&self) -> bool {
+        matches!(self, SolveOutcome::Skipped { .. })
+    }
+
+    /// The uploaded solution, or for a skipped problem the one that already
+    /// had 100 points
+    pub fn solution_id(&self) -> Option<&str> {
+        match self {
+            SolveOutcome::Submitted { solution_id } => Some(solution_id),
+            SolveOutcome::Skipped {
+                existing_sol_id, ..
+            } => existing_sol_id.as_deref(),
+        }
+    }
+}
+
+/// What has to be done to solve a problem
+pub(crate) enum PreparedSolution {
+    /// Nothing has to be uploaded
+    Skip(SolveOutcome),
+    /// This source has to be uploaded
+    Source(String),
+}
+
+/// Does everything [solve] does before uploading
+pub(crate) async fn prepare_solution(
+    problem_id: &str,
+    pbinfo_user: &PbinfoUser,
+    custom_solutions: Option<&dyn SolutionSource>,
+    options: &SolveOptions,
+) -> Result<PreparedSolution, SolveError> {
+    if options.skip_if_perfect {
+        // not being able to check is not a reason not to solve
+        match get_perfect_solution(problem_id, pbinfo_user).await {
+            Ok(Some(existing)) => {
+                trace_event!(tracing::Level::INFO, "already solved, skipping");
+                return Ok(PreparedSolution::Skip(SolveOutcome::Skipped {
+                    existing_sol_id: existing.solution_id(),
+                    submitted_at: existing.submitted_at(),
+                }));
+            }
+            Ok(None) => (),
+            Err(_err) => {
+                trace_event!(
+                    tracing::Level::WARN,
+                    error = %_err,
+                    "couldn't check for a perfect solution, solving anyway"
+                );
+            }
+        }
+    }
+
+    get_raw_solution(
+        &pbinfo_user.client,
+        problem_id,
+        custom_solutions,
+        &options.sources,
+    )
+    .await
+    .map(PreparedSolution::Source)
+    .map_err(|err| SolveError::GetSolutionError {
+        problem_id: problem_id.to_string(),
+        err: err.to_string(),
+    })
+}
+
 async fn solve_helper(
     problem_id: &str,
     pbinfo_user: &PbinfoUser,
-    costume_solutions: Option<&Value>,
-) -> Result<String, SolveError> {
-    let correct_solution = get_raw_solution(problem_id, costume_solutions)
-        .await
-        .map_err(|err| SolveError::GetSolutionError {
-            problem_id: problem_id.to_string(),
-            err: err.to_string(),
-        })?;
+    custom_solutions: Option<&dyn SolutionSource>,
+    options: &SolveOptions,
+) -> Result<SolveOutcome, SolveError> {
+    let deadline = Deadline::new(options.timeout);
+    let exceeded = |timeout| SolveError::DeadlineExceeded {
+        problem_id: problem_id.to_string(),
+        timeout,
+    };
 
-    upload(&problem_id, &correct_solution, pbinfo_user)
-        .await
-        .map_err(|err| SolveError::UploadError {
-            problem_id: problem_id.to_string(),
-            err: err,
-        })
+    let prepared = deadline::within(
+        deadline,
+        prepare_solution(problem_id, pbinfo_user, custom_solutions, options),
+    )
+    .await
+    .map_err(exceeded)??;
+    let correct_solution = match prepared {
+        PreparedSolution::Skip(outcome) => return Ok(outcome),
+        PreparedSolution::Source(source) => source,
+    };
+
+    let upload_options = options.upload_options();
+    let solution_id = deadline::within(
+        deadline,
+        upload_with(problem_id, &correct_solution, &upload_options, pbinfo_user),
+    )
+    .await
+    .map_err(exceeded)?
+    .map_err(|err| SolveError::UploadError {
+        problem_id: problem_id.to_string(),
+        err,
+    })?;
+    Ok(SolveOutcome::Submitted { solution_id })
 }
 
-pub async fn solve(problem_id: &str, pbinfo_user: &PbinfoUser) -> Result<String, SolveError> {
-    solve_helper(problem_id, pbinfo_user, None).await
+pub async fn solve(
+    problem_id: &str,
+    options: &SolveOptions,
+    pbinfo_user: &PbinfoUser,
+) -> Result<SolveOutcome, SolveError> {
+    solve_helper(problem_id, pbinfo_user, None, options).await
 }
 
 pub async fn costume_solve(
     problem_id: &str,
     costume_solutions: &Value,
+    options: &SolveOptions,
+    pbinfo_user: &PbinfoUser,
+) -> Result<SolveOutcome, SolveError> {
+    solve_helper(problem_id, pbinfo_user, Some(costume_solutions), options).await
+}
+
+/// Same as [PbinfoUser::solve] but the solutions in `custom_solutions`
+/// (problem id to source) are looked at before any of the sources
+pub async fn custom_solve(
+    problem_id: &str,
+    custom_solutions: &HashMap<String, String>,
+    options: &SolveOptions,
     pbinfo_user: &PbinfoUser,
-) -> Result<String, SolveError> {
-    solve_helper(problem_id, pbinfo_user, Some(costume_solutions)).await
+) -> Result<SolveOutcome, SolveError> {
+    solve_helper(problem_id, pbinfo_user, Some(custom_solutions), options).await
+}
+
+/// Solves a problem and waits for the evaluation, failing if it got less
+/// than `options.min_score`. A skipped problem is checked with the solution
+/// that already got the points
+#[cfg(feature = "tokio")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip(options, pbinfo_user), err)
+)]
+pub async fn solve_and_verify(
+    problem_id: &str,
+    options: &VerifyOptions,
+    pbinfo_user: &PbinfoUser,
+) -> Result<Evaluation, SolveAndVerifyError> {
+    let outcome = solve(problem_id, &options.solve, pbinfo_user)
+        .await
+        .map_err(|err| SolveAndVerifyError::SolveError { err })?;
+    let solution_id = match outcome {
+        SolveOutcome::Submitted { solution_id } => solution_id,
+        SolveOutcome::Skipped {
+            existing_sol_id: Some(solution_id),
+            ..
+        } => solution_id,
+        SolveOutcome::Skipped { .. } => {
+            return Err(SolveAndVerifyError::NothingToVerify {
+                problem_id: problem_id.to_string(),
+            })
+        }
+    };
+
+    let evaluation = match pool_score(&solution_id, &options.poll, pbinfo_user).await {
+        Ok(evaluation) => evaluation,
+        Err(err) => return Err(SolveAndVerifyError::ScoreError { solution_id, err }),
+    };
+    // a source that didn't compile has no score
+    let score = evaluation.report.score().unwrap_or(0);
+    if score < options.min_score {
+        return Err(SolveAndVerifyError::BelowThreshold {
+            problem_id: problem_id.to_string(),
+            solution_id,
+            score,
+            min_score: options.min_score,
+            evaluation: Box::new(evaluation),
+        });
+    }
+    Ok(evaluation)
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::pbinfo_user::LocalDir;
+
+    #[tokio::test]
+    async fn no_solution_lists_every_source_tried() {
+        let custom_solutions = HashMap::from([("2".to_string(), "int main() {}".to_string())]);
+        let sources = SolutionSources::empty().with(LocalDir::new("/nonexistent/solutions"));
+
+        let err = get_raw_solution(&PbinfoClient::new(), "1", Some(&custom_solutions), &sources)
+            .await
+            .unwrap_err();
+        let GetSolutionError::NoSolution { tried, .. } = err else {
+            panic!("expected NoSolution, got {err:?}");
+        };
+        assert_eq!(tried, ["custom", "local"]);
+    }
 }
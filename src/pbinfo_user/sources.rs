@@ -0,0 +1,545 @@
+#[cfg(feature = "bundled-solutions")]
+use std::sync::LazyLock;
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use futures_util::lock::Mutex;
+
+use reqwest::{header::HeaderMap, Method, StatusCode};
+use thiserror::Error;
+
+use crate::{endpoints::Endpoint, http, time::Instant, PbinfoClient};
+
+#[derive(Error, Debug)]
+pub enum SolutionSourceError {
+    #[error("Error: Couldn't send a request to the url: '{url}'\nGot error:\n{err}")]
+    RequestError { url: String, err: String },
+    #[error("Error: Couldn't read the solution in {path}!\nGot error:\n{err}")]
+    ReadError { path: PathBuf, err: String },
+    #[error("Error: {err}")]
+    Other { err: String },
+}
+
+/// The future returned by [SolutionSource::find]
+#[cfg(not(target_arch = "wasm32"))]
+pub type SourceFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Option<String>, SolutionSourceError>> + Send + 'a>>;
+/// The future returned by [SolutionSource::find]
+#[cfg(target_arch = "wasm32")]
+pub type SourceFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Option<String>, SolutionSourceError>> + 'a>>;
+
+/// Somewhere solutions can be looked up, by problem id
+pub trait SolutionSource: Send + Sync {
+    /// A short name for the logs and the errors, like `github`
+    fn name(&self) -> &str;
+
+    /// The source that solves the problem, `Ok(None)` if this source has
+    /// none. `client` is there for the sources that download them
+    fn find<'a>(&'a self, problem_id: &'a str, client: &'a PbinfoClient) -> SourceFuture<'a>;
+}
+
+/// Problem id to source, checked by build.rs
+#[cfg(feature = "bundled-solutions")]
+static SOLUTIONS: LazyLock<HashMap<String, String>> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("solutions.json"))
+        .expect("solutions.json is validated by build.rs")
+});
+
+/// The solutions.json shipped inside the crate
+#[cfg(feature = "bundled-solutions")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BundledSolutions;
+
+#[cfg(feature = "bundled-solutions")]
+impl SolutionSource for BundledSolutions {
+    fn name(&self) -> &str {
+        "bundled"
+    }
+
+    fn find<'a>(&'a self, problem_id: &'a str, _client: &'a PbinfoClient) -> SourceFuture<'a> {
+        Box::pin(async move { Ok(SOLUTIONS.get(problem_id).cloned()) })
+    }
+}
+
+/// Solutions given by the user, problem id to source
+impl SolutionSource for HashMap<String, String> {
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn find<'a>(&'a self, problem_id: &'a str, _client: &'a PbinfoClient) -> SourceFuture<'a> {
+        Box::pin(async move { Ok(self.get(problem_id).cloned()) })
+    }
+}
+
+/// A json object of problem ids to sources, like the one
+/// [crate::pbinfo_user::PbinfoUser::costume_solve] takes
+impl SolutionSource for serde_json::Value {
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn find<'a>(&'a self, problem_id: &'a str, _client: &'a PbinfoClient) -> SourceFuture<'a> {
+        Box::pin(async move { Ok(self[problem_id].as_str().map(|source| source.to_string())) })
+    }
+}
+
+/// The codulluiandrei/pbinfo repo, one folder per problem
+pub const CODULLUIANDREI_URL: &str =
+    "https://raw.githubusercontent.com/codulluiandrei/pbinfo/refs/heads/main/pbinfo-{problem_id}/main.cpp";
+
+/// `GET` of a file outside of pbinfo, like a solution out of a github repo
+struct RawFile {
+    name: &'static str,
+    url: String,
+}
+
+impl Endpoint for RawFile {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn headers(&self) -> HeaderMap {
+        HeaderMap::new()
+    }
+}
+
+/// Github repos with a file per problem, tried one after the other. Every
+/// repo is the url of the raw file with `{problem_id}` in it, like
+/// [CODULLUIANDREI_URL]
+#[derive(Debug, Clone)]
+pub struct GithubRepos {
+    url_templates: Vec<String>,
+}
+
+impl GithubRepos {
+    pub fn new(url_templates: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        GithubRepos {
+            url_templates: url_templates.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Only the codulluiandrei/pbinfo repo
+impl Default for GithubRepos {
+    fn default() -> Self {
+        Self::new([CODULLUIANDREI_URL])
+    }
+}
+
+impl SolutionSource for GithubRepos {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn find<'a>(&'a self, problem_id: &'a str, client: &'a PbinfoClient) -> SourceFuture<'a> {
+        Box::pin(async move {
+            for url_template in &self.url_templates {
+                let endpoint = RawFile {
+                    name: "github-solution",
+                    url: url_template.replace("{problem_id}", problem_id),
+                };
+                let request_error = |err: String| SolutionSourceError::RequestError {
+                    url: endpoint.url(),
+                    err,
+                };
+                let response = http::send(client, &endpoint)
+                    .await
+                    .map_err(|err| request_error(err.to_string()))?;
+                if response.status() != StatusCode::OK {
+                    continue;
+                }
+                return http::text(response)
+                    .await
+                    .map(Some)
+                    .map_err(|err| request_error(err.to_string()));
+            }
+            Ok(None)
+        })
+    }
+}
+
+/// Where [LocalDir] looks for a solution by default, inside its folder
+pub const DEFAULT_LOCAL_LAYOUT: &str = "{problem_id}.cpp";
+
+/// A folder of solutions kept by the user, an offline archive that works
+/// without github. Every solution is a file, `{problem_id}.cpp` by default
+#[derive(Debug, Clone)]
+pub struct LocalDir {
+    dir: PathBuf,
+    layout: String,
+}
+
+impl LocalDir {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        LocalDir {
+            dir: dir.into(),
+            layout: DEFAULT_LOCAL_LAYOUT.to_string(),
+        }
+    }
+
+    /// Where a solution is inside the folder, with `{problem_id}` in it.
+    /// Like `pbinfo-{problem_id}/main.cpp` for a clone of a github repo
+    pub fn layout(mut self, layout: impl Into<String>) -> Self {
+        self.layout = layout.into();
+        self
+    }
+
+    /// The folder the solutions are read from
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The file the solution of a problem would be in, `None` for ids that
+    /// could point outside of the folder
+    pub fn path(&self, problem_id: &str) -> Option<PathBuf> {
+        let valid = !problem_id.is_empty()
+            && problem_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        valid.then(|| {
+            self.dir
+                .join(self.layout.replace("{problem_id}", problem_id))
+        })
+    }
+}
+
+impl SolutionSource for LocalDir {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn find<'a>(&'a self, problem_id: &'a str, _client: &'a PbinfoClient) -> SourceFuture<'a> {
+        Box::pin(async move {
+            let Some(path) = self.path(problem_id) else {
+                return Ok(None);
+            };
+            match std::fs::read_to_string(&path) {
+                Ok(source) => Ok(Some(source)),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(SolutionSourceError::ReadError {
+                    path,
+                    err: err.to_string(),
+                }),
+            }
+        })
+    }
+}
+
+/// How long [SolutionsIndex] uses its cached file before downloading the
+/// index again
+pub const DEFAULT_INDEX_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long [SolutionsIndex] uses its fallback after a failed download
+/// before trying to download the index again
+const FALLBACK_RETRY: Duration = Duration::from_secs(60);
+
+struct LoadedIndex {
+    index: Arc<HashMap<String, String>>,
+    /// When to try downloading again, `None` for an index that was
+    /// downloaded or read out of a fresh cache file
+    retry_at: Option<Instant>,
+}
+
+/// A solutions.json (problem id to source) downloaded from `url`, so new
+/// solutions don't wait for a release of the crate. The index is downloaded
+/// once, kept in memory and, with [SolutionsIndex::cache_file], on disk.
+///
+/// When the index can't be downloaded the cached file is used even if it is
+/// old, and without one the bundled solutions are. The download is tried
+/// again a minute later
+pub struct SolutionsIndex {
+    url: String,
+    cache_file: Option<PathBuf>,
+    ttl: Duration,
+    /// Held while the index loads, so concurrent lookups download it once
+    loaded: Mutex<Option<LoadedIndex>>,
+}
+
+impl SolutionsIndex {
+    pub fn new(url: impl Into<String>) -> Self {
+        SolutionsIndex {
+            url: url.into(),
+            cache_file: None,
+            ttl: DEFAULT_INDEX_TTL,
+            loaded: Mutex::new(None),
+        }
+    }
+
+    /// Saves the downloaded index in `path` and reads it from there while it
+    /// is younger than the ttl
+    pub fn cache_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cache_file = Some(path.into());
+        self
+    }
+
+    /// Same as [SolutionsIndex::new] with the index cached in the cache dir
+    /// of the crate, like `~/.cache/pbinfo-api/solutions/index.json`, out of
+    /// the way of [super::ProblemCache::in_cache_dir]
+    #[cfg(feature = "config")]
+    pub fn in_cache_dir(url: impl Into<String>) -> Result<Self, super::PbinfoUserError> {
+        let path = crate::user_config::cache_dir()?
+            .join("solutions")
+            .join("index.json");
+        Ok(Self::new(url).cache_file(path))
+    }
+
+    /// How old the cached file can be, [DEFAULT_INDEX_TTL] by default
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Downloads the index now, even if there is a fresh one, and returns how
+    /// many solutions it has
+    pub async fn refresh(&self, client: &PbinfoClient) -> Result<usize, SolutionSourceError> {
+        let index = Arc::new(self.download(client).await?);
+        let len = index.len();
+        *self.loaded.lock().await = Some(LoadedIndex {
+            index,
+            retry_at: None,
+        });
+        Ok(len)
+    }
+
+    async fn download(
+        &self,
+        client: &PbinfoClient,
+    ) -> Result<HashMap<String, String>, SolutionSourceError> {
+        let endpoint = RawFile {
+            name: "solutions-index",
+            url: self.url.clone(),
+        };
+        let request_error = |err: String| SolutionSourceError::RequestError {
+            url: self.url.clone(),
+            err,
+        };
+        let response = http::send(client, &endpoint)
+            .await
+            .map_err(|err| request_error(err.to_string()))?;
+        if response.status() != StatusCode::OK {
+            return Err(request_error(format!(
+                "Got the status code {}",
+                response.status()
+            )));
+        }
+        let json = http::text(response)
+            .await
+            .map_err(|err| request_error(err.to_string()))?;
+        let index = serde_json::from_str(&json).map_err(|err| request_error(err.to_string()))?;
+
+        if let Some(path) = &self.cache_file {
+            let result = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::write(path, &json));
+            if let Err(_err) = result {
+                trace_event!(tracing::Level::WARN, error = %_err, "couldn't cache the solutions index");
+            }
+        }
+        Ok(index)
+    }
+
+    /// The cached file, `None` if there is none or it is older than `max_age`
+    fn read_cache(&self, max_age: Option<Duration>) -> Option<HashMap<String, String>> {
+        let path = self.cache_file.as_ref()?;
+        if let Some(max_age) = max_age {
+            let age = std::fs::metadata(path).ok()?.modified().ok()?.elapsed();
+            if age.unwrap_or_default() > max_age {
+                return None;
+            }
+        }
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+    }
+
+    async fn index(&self, client: &PbinfoClient) -> Arc<HashMap<String, String>> {
+        let mut loaded = self.loaded.lock().await;
+        if let Some(loaded) = &*loaded {
+            if loaded
+                .retry_at
+                .is_none_or(|retry_at| Instant::now() < retry_at)
+            {
+                return loaded.index.clone();
+            }
+        }
+
+        let (index, retry_at) = match self.read_cache(Some(self.ttl)) {
+            Some(index) => (index, None),
+            None => match self.download(client).await {
+                Ok(index) => (index, None),
+                Err(_err) => {
+                    trace_event!(
+                        tracing::Level::WARN,
+                        error = %_err,
+                        "couldn't download the solutions index, using the fallback"
+                    );
+                    let fallback = self.read_cache(None).unwrap_or_else(bundled_index);
+                    (fallback, Some(Instant::now() + FALLBACK_RETRY))
+                }
+            },
+        };
+        let index = Arc::new(index);
+        *loaded = Some(LoadedIndex {
+            index: index.clone(),
+            retry_at,
+        });
+        index
+    }
+}
+
+/// What [SolutionsIndex] falls back to when there is no index at all
+fn bundled_index() -> HashMap<String, String> {
+    #[cfg(feature = "bundled-solutions")]
+    return SOLUTIONS.clone();
+    #[cfg(not(feature = "bundled-solutions"))]
+    return HashMap::new();
+}
+
+impl SolutionSource for SolutionsIndex {
+    fn name(&self) -> &str {
+        "index"
+    }
+
+    fn find<'a>(&'a self, problem_id: &'a str, client: &'a PbinfoClient) -> SourceFuture<'a> {
+        Box::pin(async move { Ok(self.index(client).await.get(problem_id).cloned()) })
+    }
+}
+
+impl std::fmt::Debug for SolutionsIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SolutionsIndex")
+            .field("url", &self.url)
+            .field("cache_file", &self.cache_file)
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+/// The sources [crate::pbinfo_user::PbinfoUser::solve] asks, in order. The
+/// default one has the bundled solutions and then [GithubRepos::default]
+#[derive(Clone)]
+pub struct SolutionSources {
+    sources: Vec<Arc<dyn SolutionSource>>,
+}
+
+impl SolutionSources {
+    /// No sources at all, add them with [SolutionSources::with]
+    pub fn empty() -> Self {
+        SolutionSources {
+            sources: Vec::new(),
+        }
+    }
+
+    /// Asks `source` after the ones already added
+    pub fn with(mut self, source: impl SolutionSource + 'static) -> Self {
+        self.sources.push(Arc::new(source));
+        self
+    }
+
+    /// Asks `source` before the ones already added
+    pub fn with_first(mut self, source: impl SolutionSource + 'static) -> Self {
+        self.sources.insert(0, Arc::new(source));
+        self
+    }
+
+    /// Asks the solutions in `dir` before the ones already added, see
+    /// [LocalDir]
+    pub fn with_local_dir(self, dir: impl Into<PathBuf>) -> Self {
+        self.with_first(LocalDir::new(dir))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn SolutionSource> {
+        self.sources.iter().map(|source| source.as_ref())
+    }
+}
+
+impl Default for SolutionSources {
+    fn default() -> Self {
+        let sources = Self::empty();
+        #[cfg(feature = "bundled-solutions")]
+        let sources = sources.with(BundledSolutions);
+        sources.with(GithubRepos::default())
+    }
+}
+
+impl std::fmt::Debug for SolutionSources {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(|source| source.name()))
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+    use crate::test_support;
+
+    const INDEX_URL: &str = "https://example.com/solutions.json";
+
+    #[tokio::test]
+    async fn concurrent_lookups_download_the_index_once() {
+        let (client, sent) =
+            test_support::client(|_| test_support::ok(r#"{"1": "int main() {}"}"#));
+        let index = SolutionsIndex::new(INDEX_URL);
+
+        let lookups = (0..8).map(|_| index.find("1", &client));
+        for found in futures_util::future::join_all(lookups).await {
+            assert_eq!(found.unwrap().as_deref(), Some("int main() {}"));
+        }
+        assert_eq!(sent.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_failed_download_is_tried_again() {
+        let online = Arc::new(AtomicBool::new(false));
+        let (client, sent) = test_support::client({
+            let online = online.clone();
+            move |_| match online.load(Ordering::SeqCst) {
+                true => test_support::ok(r#"{"new": "int main() {}"}"#),
+                false => test_support::status(503),
+            }
+        });
+        let index = SolutionsIndex::new(INDEX_URL);
+
+        assert_eq!(index.find("new", &client).await.unwrap(), None);
+        // the fallback is used until the retry is due
+        online.store(true, Ordering::SeqCst);
+        assert_eq!(index.find("new", &client).await.unwrap(), None);
+        assert_eq!(sent.requests().len(), 1);
+
+        if let Some(loaded) = &mut *index.loaded.lock().await {
+            loaded.retry_at = Some(Instant::now());
+        }
+        assert_eq!(
+            index.find("new", &client).await.unwrap().as_deref(),
+            Some("int main() {}")
+        );
+        assert_eq!(sent.requests().len(), 2);
+
+        // a downloaded index is kept
+        index.find("new", &client).await.unwrap();
+        assert_eq!(sent.requests().len(), 2);
+    }
+}
@@ -1,8 +1,17 @@
+use std::time::Duration;
+
 use reqwest::StatusCode;
 use thiserror::Error;
 
-use crate::pbinfo_user::PbinfoUser;
+use crate::{
+    endpoints,
+    http::{self, BodyError},
+    parse,
+    pbinfo_user::{login, PbinfoUser},
+    PbinfoClient,
+};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error)]
 pub enum ResponseIdError {
     #[error("Error: An unknown upload error happened!\nResponse from pbinfo was: {response}")]
@@ -14,9 +23,10 @@ pub enum ResponseIdError {
     #[error("Error: Another solution is being evaulated!\nPlease wait for it to finish!")]
     AlreadyEvaluationError,
     #[error("Error: Too many solutions were uploaded in too short of a time!")]
-    CooldownError,
+    RateLimited { retry_after: Option<Duration> },
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(thiserror::Error, Debug)]
 pub enum UploadError {
     #[error("Error: An error occurred when uploading the solution!\nError was: {err}")]
@@ -27,14 +37,79 @@ pub enum UploadError {
     NotLoggedInError,
     #[error("Error: Got a status code that wasn't 200 when uploading solution!\nInstead got {status_code}")]
     BadStatusCodeError { status_code: String },
+    #[deprecated(note = "pbinfo refusing uploads for a while is now UploadError::RateLimited")]
     #[error("Error: Too many solutions were uploaded in too short of a time!")]
     CooldownError,
+    #[error("Error: Too many solutions were uploaded in too short of a time!")]
+    RateLimited {
+        /// How long pbinfo asked to wait, if it said
+        retry_after: Option<Duration>,
+    },
+    #[error("Error: Another solution is being evaulated!\nPlease wait for it to finish!")]
+    EvaluationPendingError,
+    #[error("Error: The session expired, pbinfo answered with the login page!\nLog in again!")]
+    SessionExpired,
 }
 
-#[derive(Debug, Error)]
-pub enum GetEncodedSursaError {
-    #[error("Error: couldn't find '.val(Editor.getValue())' in the response of the problem!\nMaybe the user wasn't logged in or the api of pbinfo changed!")]
-    NotFoundEditorGetValue,
+/// The languages pbinfo evaluates sources in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Language {
+    #[default]
+    Cpp,
+    C,
+    Pascal,
+    Python,
+    Java,
+}
+
+impl Language {
+    /// The value pbinfo expects in the `limbaj_de_programare` field
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Language::Cpp => "cpp",
+            Language::C => "c",
+            Language::Pascal => "pascal",
+            Language::Python => "py",
+            Language::Java => "java",
+        }
+    }
+
+    /// Guesses the language of a source file out of its extension
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.trim_start_matches('.').to_lowercase().as_str() {
+            "cpp" | "cc" | "cxx" | "c++" => Some(Language::Cpp),
+            "c" => Some(Language::C),
+            "pas" | "pp" => Some(Language::Pascal),
+            "py" => Some(Language::Python),
+            "java" => Some(Language::Java),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How long to wait after pbinfo refused an upload without saying for how long
+#[cfg(feature = "tokio")]
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// How [PbinfoUser::upload_with] uploads a source
+#[derive(Debug, Clone, Default)]
+pub struct UploadOptions {
+    /// The language of the source, c++ by default
+    pub language: Language,
+    /// The id of the assignment ("tema") the solution is for, so pbinfo
+    /// counts it for the homework and not only for the problem. See
+    /// [PbinfoUser::get_assignment]
+    pub assignment: Option<String>,
+    /// How many times to sleep for as long as pbinfo asks and try again
+    /// after an [UploadError::RateLimited]. Defaults to 0
+    #[cfg(feature = "tokio")]
+    pub rate_limit_retries: u32,
 }
 
 /// Returns the encoded "sursa" field
@@ -44,75 +119,42 @@ pub enum GetEncodedSursaError {
 /// the problem page, this function finds it and returns it.
 async fn get_encoded_sursa(
     problem_id: &str,
-    logged_in_client: &reqwest::Client,
-    logged_in_headers: reqwest::header::HeaderMap,
+    logged_in_client: &PbinfoClient,
+    ssid: &str,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let request = logged_in_client
-        .request(
-            reqwest::Method::GET,
-            format!("https://www.pbinfo.ro/probleme/{problem_id}"),
-        )
-        .headers(logged_in_headers);
-
-    let response = request.send().await?;
-
-    let body = response.text().await?;
-
-    // we are looking for the token in a string that looks something
-    // like this:
-    // {page html}
-    // $("#eedd451d5e1eb7dfd9c6e3a0e918f02cc2a87d03").val(Editor.getValue());
-    // {continuation page html}
-    let marker = ".val(Editor.getValue())";
-    let before = body
-        .split(marker)
-        .next()
-        .ok_or_else(|| GetEncodedSursaError::NotFoundEditorGetValue)?;
-
-    let encoded_sursa_rev: String = before
-        .chars()
-        .rev()
-        .skip_while(|&c| c != '\'')
-        .skip(1)
-        .take_while(|&c| c != '#')
-        .collect();
-    let encoded_sursa = encoded_sursa_rev.chars().rev().collect::<String>();
-
-    Ok(encoded_sursa)
+    let endpoint = endpoints::ProblemPage::new(problem_id).ssid(ssid);
+
+    let response = http::send(logged_in_client, &endpoint).await?;
+
+    let body = http::text(response).await?;
+
+    parse::source_field(&body).map_err(|err| {
+        if parse::user_id(&body).is_ok_and(|user_id| user_id == "0") {
+            BodyError::SessionExpired.into()
+        } else {
+            err.into()
+        }
+    })
 }
 
 async fn upload_helper(
     problem_id: &str,
     source: &str,
+    language: Language,
+    assignment: Option<&str>,
     pbinfo_user: &PbinfoUser,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::builder().build()?;
-
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert("Origin", "https://www.pbinfo.ro".parse()?);
-    headers.insert(
-        "Referer",
-        format!("https://www.pbinfo.ro/probleme/{problem_id}").parse()?,
-    );
-    headers.insert("Cookie", format!("SSID={}", pbinfo_user.ssid).parse()?);
-
-    let encoded_sursa = get_encoded_sursa(problem_id, &client, headers.clone()).await?;
-
-    let form = reqwest::multipart::Form::new()
-        .text("limbaj_de_programare", "cpp")
-        .text("sursa", "")
-        .text(encoded_sursa, source.to_string())
-        .text("id", problem_id.to_string());
-
-    let request = client
-        .request(
-            reqwest::Method::POST,
-            "https://www.pbinfo.ro/ajx-module/php-solutie-incarcare.php",
-        )
-        .headers(headers)
-        .multipart(form);
-
-    let response = request.send().await?;
+    let encoded_sursa =
+        get_encoded_sursa(problem_id, &pbinfo_user.client, &pbinfo_user.ssid()).await?;
+
+    let mut endpoint = endpoints::UploadSolution::new(problem_id, source, encoded_sursa)
+        .language(language.as_str())
+        .ssid(pbinfo_user.ssid());
+    if let Some(assignment) = assignment {
+        endpoint = endpoint.assignment(assignment);
+    }
+
+    let response = http::send(&pbinfo_user.client, &endpoint).await?;
 
     if response.status() != StatusCode::OK {
         return Err(UploadError::BadStatusCodeError {
@@ -121,9 +163,7 @@ async fn upload_helper(
         .into());
     }
 
-    let body = response.text().await?;
-
-    Ok(body)
+    Ok(http::json_body(response).await?)
 }
 
 /// Returns the id of a response
@@ -132,45 +172,227 @@ async fn upload_helper(
 ///
 /// * `response` - the response to parse
 fn get_response_id(response: String) -> Result<String, ResponseIdError> {
-    let table: serde_json::Value =
-        serde_json::from_str(&response).map_err(|err| ResponseIdError::ParseError {
-            json: response.clone(),
-            err: err.to_string(),
-        })?;
-    if table["stare"] != "success" {
-        if table["raspuns"] == "Lipsa autentificare" {
-            return Err(ResponseIdError::NotLoggedInError);
+    let table = parse::upload_response(&response).map_err(|err| ResponseIdError::ParseError {
+        json: response.clone(),
+        err: err.to_string(),
+    })?;
+    if table.stare != "success" {
+        match table.raspuns.as_deref() {
+            Some("Lipsa autentificare") => return Err(ResponseIdError::NotLoggedInError),
+            Some("Așteaptă evaluarea surselor deja trimise") => {
+                return Err(ResponseIdError::AlreadyEvaluationError)
+            }
+            Some(message) if message.starts_with("Mai așteaptă") => {
+                return Err(ResponseIdError::RateLimited {
+                    retry_after: parse::retry_after(message),
+                })
+            }
+            _ => return Err(ResponseIdError::UnknownUploadError { response }),
         }
-        if table["raspuns"] == "Așteaptă evaluarea surselor deja trimise" {
-            return Err(ResponseIdError::AlreadyEvaluationError);
+    }
+    table
+        .solution_id()
+        .ok_or_else(|| ResponseIdError::ParseError {
+            json: response,
+            err: "id_solutie was missing from the response".to_string(),
+        })
+}
+
+/// Uploads a source and returns the response of pbinfo exactly as it was
+/// sent (a json string)
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip(source, pbinfo_user), err)
+)]
+pub async fn upload_raw(
+    problem_id: &str,
+    source: &str,
+    language: Language,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, UploadError> {
+    upload_raw_to(problem_id, source, language, None, pbinfo_user).await
+}
+
+/// [upload_raw] with the solution sent for an assignment
+async fn upload_raw_to(
+    problem_id: &str,
+    source: &str,
+    language: Language,
+    assignment: Option<&str>,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, UploadError> {
+    upload_helper(problem_id, source, language, assignment, pbinfo_user)
+        .await
+        .map_err(|err| match err.downcast_ref::<BodyError>() {
+            Some(BodyError::SessionExpired) => UploadError::SessionExpired,
+            _ => UploadError::UploadError {
+                err: err.to_string(),
+            },
+        })
+}
+
+/// Uploads a c++ source and returns the id of the new solution
+pub async fn upload(
+    problem_id: &str,
+    source: &str,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, UploadError> {
+    upload_with_language(problem_id, source, Language::Cpp, pbinfo_user).await
+}
+
+/// Uploads a source written in `language` and returns the id of the new
+/// solution
+pub async fn upload_with_language(
+    problem_id: &str,
+    source: &str,
+    language: Language,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, UploadError> {
+    // the other fields only exist with some features
+    #[allow(clippy::needless_update)]
+    let options = UploadOptions {
+        language,
+        ..Default::default()
+    };
+    upload_with(problem_id, source, &options, pbinfo_user).await
+}
+
+/// Uploads a source and returns the id of the new solution, waiting out
+/// the rate limit of pbinfo if `options` allow it
+pub async fn upload_with(
+    problem_id: &str,
+    source: &str,
+    options: &UploadOptions,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, UploadError> {
+    #[cfg(not(feature = "tokio"))]
+    return upload_once(problem_id, source, options, pbinfo_user).await;
+
+    #[cfg(feature = "tokio")]
+    let mut retries = 0;
+    #[cfg(feature = "tokio")]
+    loop {
+        match upload_once(problem_id, source, options, pbinfo_user).await {
+            Err(UploadError::RateLimited { retry_after })
+                if retries < options.rate_limit_retries =>
+            {
+                let wait = retry_after.unwrap_or(DEFAULT_RETRY_AFTER);
+                trace_event!(
+                    tracing::Level::INFO,
+                    problem_id,
+                    ?wait,
+                    "rate limited, waiting before uploading again"
+                );
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_retry();
+                retries += 1;
+                tokio::time::sleep(wait).await;
+            }
+            result => return result,
         }
-        if table["raspuns"]
-            == "Mai așteaptă! Ai trimis prea multe surse într-un interval scurt de timp."
+    }
+}
+
+/// Uploads once more with a new session if pbinfo says the old one expired
+async fn upload_once(
+    problem_id: &str,
+    source: &str,
+    options: &UploadOptions,
+    pbinfo_user: &PbinfoUser,
+) -> Result<String, UploadError> {
+    match upload_attempt(problem_id, source, options, pbinfo_user).await {
+        Err(UploadError::SessionExpired | UploadError::NotLoggedInError)
+            if pbinfo_user.auto_relogin() =>
         {
-            return Err(ResponseIdError::CooldownError);
+            login::relogin(pbinfo_user)
+                .await
+                .map_err(|_| UploadError::SessionExpired)?;
+            upload_attempt(problem_id, source, options, pbinfo_user).await
         }
-        return Err(ResponseIdError::UnknownUploadError { response: response });
+        result => result,
     }
-    Ok(table["id_solutie"].to_string())
 }
 
-pub async fn upload(
+async fn upload_attempt(
     problem_id: &str,
     source: &str,
+    options: &UploadOptions,
     pbinfo_user: &PbinfoUser,
 ) -> Result<String, UploadError> {
-    let response = upload_helper(problem_id, source, pbinfo_user)
-        .await
-        .map_err(|err| UploadError::UploadError {
-            err: err.to_string(),
-        })?;
+    let response = upload_raw_to(
+        problem_id,
+        source,
+        options.language,
+        options.assignment.as_deref(),
+        pbinfo_user,
+    )
+    .await?;
 
     let response_id = get_response_id(response).map_err(|err| match err {
         ResponseIdError::NotLoggedInError => UploadError::NotLoggedInError,
-        ResponseIdError::CooldownError => UploadError::CooldownError,
+        ResponseIdError::RateLimited { retry_after } => UploadError::RateLimited { retry_after },
+        ResponseIdError::AlreadyEvaluationError => UploadError::EvaluationPendingError,
         err => UploadError::ResponseParseError {
             err: err.to_string(),
         },
     })?;
+    #[cfg(feature = "metrics")]
+    crate::metrics::record_submission();
+    trace_event!(tracing::Level::INFO, problem_id, solution_id = %response_id, "uploaded solution");
     Ok(response_id)
 }
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, LOGGED_IN, LOGGED_OUT};
+
+    const UPLOAD_PATH: &str = "/ajx-module/php-solutie-incarcare.php";
+
+    #[tokio::test]
+    async fn uploads_log_in_again_when_the_session_expired() {
+        let (client, sent) = test_support::client(|request| {
+            let logged_in = test_support::has_session(request, "new-ssid");
+            match request.path() {
+                "/ajx-module/php-login.php" => test_support::login_answer("", Some("new-ssid")),
+                UPLOAD_PATH if !logged_in => test_support::ok(LOGGED_OUT),
+                "/" if !logged_in => test_support::ok(LOGGED_OUT),
+                "/" => test_support::ok(LOGGED_IN),
+                _ => test_support::accepting_uploads(request, "1000"),
+            }
+        });
+        let pbinfo_user = PbinfoUser::builder()
+            .email("ion@example.com")
+            .password("parola")
+            .ssid("old-ssid")
+            .client(client)
+            .build();
+
+        let solution_id = upload("1", "int main() {}", &pbinfo_user).await.unwrap();
+        assert_eq!(solution_id, "1000");
+        assert_eq!(pbinfo_user.ssid(), "new-ssid");
+        let uploads = sent
+            .requests()
+            .iter()
+            .filter(|request| request.path() == UPLOAD_PATH)
+            .count();
+        assert_eq!(uploads, 2);
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_arent_parse_errors() {
+        let (client, _) = test_support::client(|request| match request.path() {
+            UPLOAD_PATH => test_support::ok(LOGGED_OUT),
+            _ => test_support::accepting_uploads(request, "1000"),
+        });
+        let pbinfo_user = PbinfoUser::builder()
+            .client(client)
+            .auto_relogin(false)
+            .build();
+
+        assert!(matches!(
+            upload("1", "int main() {}", &pbinfo_user).await,
+            Err(UploadError::SessionExpired)
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+//! A token bucket keeping the requests of a client under a rate, so bulk
+//! operations don't get the account blocked.
+
+use std::{sync::Mutex, time::Duration};
+
+use crate::time::Instant;
+
+#[derive(Debug)]
+struct Bucket {
+    /// Negative when requests reserved tokens that weren't refilled yet
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    /// Lets `burst` requests through right away, then `requests_per_second`
+    pub(crate) fn new(requests_per_second: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        RateLimiter {
+            requests_per_second,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token and returns how long to wait before using it
+    fn reserve(&self) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|err| err.into_inner());
+        let now = Instant::now();
+        let refilled = now.duration_since(bucket.updated).as_secs_f64() * self.requests_per_second;
+        bucket.tokens = (bucket.tokens + refilled).min(self.burst) - 1.0;
+        bucket.updated = now;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            // a tiny rate can ask for a wait longer than a Duration holds
+            Duration::try_from_secs_f64(-bucket.tokens / self.requests_per_second)
+                .unwrap_or(Duration::MAX)
+        }
+    }
+
+    /// Waits until the next request is allowed
+    pub(crate) async fn acquire(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            trace_event!(tracing::Level::DEBUG, ?wait, "rate limited");
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burst_goes_through_right_away() {
+        let limiter = RateLimiter::new(1.0, 3);
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(), Duration::ZERO);
+        }
+        let wait = limiter.reserve();
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn tiny_rates_wait_as_long_as_possible() {
+        let limiter = RateLimiter::new(1e-320, 1);
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::MAX);
+    }
+}
@@ -0,0 +1,3 @@
+//! Turning the results of the crate into something to show to users.
+
+pub mod render;
@@ -0,0 +1,229 @@
+//! Aligned terminal tables for [ScoreReport] and, with the `tokio` feature,
+//! [crate::pbinfo_user::BatchReport], so every CLI built on the crate prints
+//! results the same way.
+
+use std::fmt::Write;
+
+use crate::models::{EvaluationOutcome, ScoreReport};
+#[cfg(feature = "tokio")]
+use crate::pbinfo_user::{BatchOutcome, BatchReport};
+
+const RESET: &str = "\x1b[0m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+
+/// How the tables are rendered
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Color the verdicts with ANSI escape codes
+    pub color: bool,
+}
+
+/// A table with the width of every column computed from its cells
+struct Table {
+    header: Vec<String>,
+    rows: Vec<(Vec<String>, Option<&'static str>)>,
+}
+
+impl Table {
+    fn new(header: &[&str]) -> Self {
+        Table {
+            header: header.iter().map(|cell| cell.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds a row, colored with `color` if coloring is enabled
+    fn row(&mut self, cells: Vec<String>, color: Option<&'static str>) {
+        self.rows.push((cells, color));
+    }
+
+    fn render(&self, out: &mut String, options: &RenderOptions) {
+        let widths: Vec<usize> = (0..self.header.len())
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .map(|(cells, _)| &cells[column])
+                    .chain([&self.header[column]])
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let line = |cells: &[String]| {
+            cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let _ = writeln!(out, "{}", line(&self.header));
+        let _ = writeln!(
+            out,
+            "{}",
+            widths
+                .iter()
+                .map(|width| "-".repeat(*width))
+                .collect::<Vec<_>>()
+                .join("  ")
+        );
+        for (cells, color) in &self.rows {
+            match color {
+                Some(color) if options.color => {
+                    let _ = writeln!(out, "{color}{}{RESET}", line(cells));
+                }
+                _ => {
+                    let _ = writeln!(out, "{}", line(cells));
+                }
+            }
+        }
+    }
+}
+
+fn value_cell(value: Option<&serde_json::Value>) -> String {
+    match value {
+        Some(serde_json::Value::String(value)) => value.clone(),
+        Some(serde_json::Value::Null) | None => "-".to_string(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn outcome_label(outcome: EvaluationOutcome) -> String {
+    match outcome {
+        EvaluationOutcome::Accepted => "accepted".to_string(),
+        EvaluationOutcome::PartialScore(score) => format!("partial score ({score})"),
+        EvaluationOutcome::CompileError => "compile error".to_string(),
+        EvaluationOutcome::RuntimeError => "runtime error".to_string(),
+        EvaluationOutcome::TimeLimit => "time limit exceeded".to_string(),
+        EvaluationOutcome::InternalError => "internal error".to_string(),
+        EvaluationOutcome::Pending => "still evaluating".to_string(),
+    }
+}
+
+fn outcome_color(outcome: EvaluationOutcome) -> &'static str {
+    match outcome {
+        EvaluationOutcome::Accepted => GREEN,
+        EvaluationOutcome::PartialScore(_) | EvaluationOutcome::Pending => YELLOW,
+        _ => RED,
+    }
+}
+
+/// Renders the tests of an evaluation (points, time, memory and verdict)
+/// followed by the total score
+pub fn score_report(report: &ScoreReport, options: &RenderOptions) -> String {
+    let mut out = String::new();
+
+    let mut table = Table::new(&["test", "points", "time", "memory", "verdict"]);
+    for (index, test) in report.teste.iter().enumerate() {
+        let color = match test.score() {
+            Some(0) | None => RED,
+            Some(_) => GREEN,
+        };
+        table.row(
+            vec![
+                (index + 1).to_string(),
+                value_cell(test.scor.as_ref()),
+                value_cell(test.timp.as_ref()),
+                value_cell(test.memorie.as_ref()),
+                test.mesaj.clone().unwrap_or_default(),
+            ],
+            Some(color),
+        );
+    }
+    if !report.teste.is_empty() {
+        table.render(&mut out, options);
+        out.push('\n');
+    }
+
+    for warning in &report.compiler_warnings {
+        let _ = writeln!(out, "warning: {warning}");
+    }
+
+    let outcome = report.outcome();
+    let total = format!(
+        "total: {}/100, {}",
+        report
+            .score()
+            .map(|score| score.to_string())
+            .unwrap_or_else(|| "-".to_string()),
+        outcome_label(outcome)
+    );
+    if options.color {
+        let _ = writeln!(out, "{}{total}{RESET}", outcome_color(outcome));
+    } else {
+        let _ = writeln!(out, "{total}");
+    }
+
+    out
+}
+
+/// Renders one row per problem of a batch followed by how many problems
+/// got 100 points
+#[cfg(feature = "tokio")]
+pub fn batch_report(report: &BatchReport, options: &RenderOptions) -> String {
+    let mut out = String::new();
+
+    let mut table = Table::new(&["problem", "solution", "score", "result"]);
+    for entry in &report.entries {
+        let (solution_id, score, result, color) = match &entry.outcome {
+            BatchOutcome::Skipped {
+                existing_sol_id, ..
+            } => (
+                existing_sol_id.clone(),
+                "100".to_string(),
+                "already solved".to_string(),
+                GREEN,
+            ),
+            BatchOutcome::Evaluated {
+                solution_id,
+                evaluation,
+            } => {
+                let outcome = evaluation.report.outcome();
+                (
+                    Some(solution_id.clone()),
+                    value_cell(evaluation.report.scor.as_ref()),
+                    outcome_label(outcome),
+                    outcome_color(outcome),
+                )
+            }
+            BatchOutcome::Failed { solution_id, error } => (
+                solution_id.clone(),
+                "-".to_string(),
+                // the errors span multiple lines, the first one says enough
+                error.lines().next().unwrap_or_default().to_string(),
+                RED,
+            ),
+            BatchOutcome::DeadlineExceeded { solution_id } => (
+                solution_id.clone(),
+                "-".to_string(),
+                "deadline exceeded".to_string(),
+                RED,
+            ),
+        };
+        table.row(
+            vec![
+                entry.problem_id.clone(),
+                solution_id.unwrap_or_else(|| "-".to_string()),
+                score,
+                result,
+            ],
+            Some(color),
+        );
+    }
+    table.render(&mut out, options);
+
+    let _ = writeln!(
+        out,
+        "\n{}/{} problems with 100 points",
+        report.perfect(),
+        report.entries.len()
+    );
+
+    out
+}
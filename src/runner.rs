@@ -0,0 +1,375 @@
+//! Compiling and running a source locally against the examples of a problem,
+//! to catch the obvious mistakes before wasting a submission.
+//!
+//! Commands are split on whitespace and can use `{source}` (the path of the
+//! source) and `{binary}` (where the compiled program should be written).
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+use crate::pbinfo_user::Example;
+
+#[derive(Error, Debug)]
+pub enum RunnerError {
+    #[error("Error: Couldn't get the examples of the problem!\nGot error:\n{err}")]
+    ExamplesError { err: String },
+    #[error("Error: Couldn't run `{command}`!\nGot error:\n{err}")]
+    SpawnError { command: String, err: String },
+    #[error("Error: The source didn't compile!\nThe compiler said:\n{output}")]
+    CompileError { output: String },
+    #[error("Error: Couldn't work with the file {file}!\nGot error:\n{error}")]
+    IoError {
+        file: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+/// How [run_examples] compiles and runs a source
+#[derive(Debug, Clone)]
+pub struct RunnerOptions {
+    /// The command that compiles the source, `None` for interpreted
+    /// languages. Defaults to `g++ -std=c++17 -O2 {source} -o {binary}`
+    pub compile: Option<String>,
+    /// The command that runs the program. Defaults to `{binary}`, use
+    /// something like `python3 {source}` for interpreted languages
+    pub run: String,
+    /// The files the program reads and writes, like `("sum.in", "sum.out")`,
+    /// for problems that don't use the keyboard and the screen
+    pub files: Option<(String, String)>,
+    /// How long one example may run before it is killed. Defaults to 2s
+    pub timeout: Duration,
+}
+
+impl Default for RunnerOptions {
+    fn default() -> Self {
+        RunnerOptions {
+            compile: Some("g++ -std=c++17 -O2 {source} -o {binary}".to_string()),
+            run: "{binary}".to_string(),
+            files: None,
+            timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+/// What happened when an example was run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Passed,
+    /// The output didn't match, ignoring the whitespace
+    WrongAnswer,
+    /// The program exited with an error code or was killed by a signal
+    RuntimeError {
+        code: Option<i32>,
+    },
+    TimeLimit,
+}
+
+/// The result of one example, see [RunReport]
+#[derive(Debug, Clone)]
+pub struct ExampleResult {
+    /// The number of the example, starting from 1
+    pub index: usize,
+    pub verdict: Verdict,
+    pub expected: String,
+    /// What the program printed (or wrote in the output file)
+    pub actual: String,
+    pub time: Duration,
+}
+
+/// Returned by [run_examples]
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    /// What the compiler printed, warnings included
+    pub compile_output: String,
+    pub results: Vec<ExampleResult>,
+}
+
+impl RunReport {
+    /// Whether every example passed
+    pub fn passed(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| result.verdict == Verdict::Passed)
+    }
+}
+
+/// Splits a command and fills in the `{source}` and `{binary}` placeholders
+fn command(template: &str, source: &Path, binary: &Path) -> Option<Command> {
+    let mut parts = template.split_whitespace().map(|part| {
+        part.replace("{source}", &source.to_string_lossy())
+            .replace("{binary}", &binary.to_string_lossy())
+    });
+    let mut command = Command::new(parts.next()?);
+    command.args(parts);
+    Some(command)
+}
+
+fn io_error(file: &Path) -> impl FnOnce(std::io::Error) -> RunnerError + '_ {
+    move |error| RunnerError::IoError {
+        file: file.to_path_buf(),
+        error,
+    }
+}
+
+/// Compares two outputs the way pbinfo does for most problems, token by token
+fn same_output(expected: &str, actual: &str) -> bool {
+    expected.split_whitespace().eq(actual.split_whitespace())
+}
+
+/// Compiles `source` and runs it against every example, each one in a
+/// fresh directory. Blocks the current thread until all of them finished
+pub fn run_examples(
+    source: &Path,
+    examples: &[Example],
+    options: &RunnerOptions,
+) -> Result<RunReport, RunnerError> {
+    let work_dir = std::env::temp_dir().join(format!(
+        "pbinfo-runner-{}-{}",
+        std::process::id(),
+        rand::random::<u32>()
+    ));
+    fs::create_dir_all(&work_dir).map_err(io_error(&work_dir))?;
+    let result = run_in(&work_dir, source, examples, options);
+    let _ = fs::remove_dir_all(&work_dir);
+    result
+}
+
+fn run_in(
+    work_dir: &Path,
+    source: &Path,
+    examples: &[Example],
+    options: &RunnerOptions,
+) -> Result<RunReport, RunnerError> {
+    let source = fs::canonicalize(source).map_err(io_error(source))?;
+    let binary = work_dir.join(if cfg!(windows) { "main.exe" } else { "main" });
+    let mut report = RunReport::default();
+
+    if let Some(template) = &options.compile {
+        let spawn_error = |err: String| RunnerError::SpawnError {
+            command: template.clone(),
+            err,
+        };
+        let output = command(template, &source, &binary)
+            .ok_or_else(|| spawn_error("The command is empty".to_string()))?
+            .output()
+            .map_err(|err| spawn_error(err.to_string()))?;
+        report.compile_output = String::from_utf8_lossy(&output.stderr).into_owned();
+        if !output.status.success() {
+            return Err(RunnerError::CompileError {
+                output: report.compile_output,
+            });
+        }
+    }
+
+    for (i, example) in examples.iter().enumerate() {
+        let example_dir = work_dir.join(format!("example-{}", i + 1));
+        fs::create_dir_all(&example_dir).map_err(io_error(&example_dir))?;
+        let (verdict, actual, time) =
+            run_example(&example_dir, &source, &binary, example, options)?;
+        report.results.push(ExampleResult {
+            index: i + 1,
+            verdict,
+            expected: example.output.clone(),
+            actual,
+            time,
+        });
+    }
+    Ok(report)
+}
+
+fn run_example(
+    dir: &Path,
+    source: &Path,
+    binary: &Path,
+    example: &Example,
+    options: &RunnerOptions,
+) -> Result<(Verdict, String, Duration), RunnerError> {
+    let spawn_error = |err: String| RunnerError::SpawnError {
+        command: options.run.clone(),
+        err,
+    };
+    let mut command = command(&options.run, source, binary)
+        .ok_or_else(|| spawn_error("The command is empty".to_string()))?;
+    command
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    if let Some((input, _)) = &options.files {
+        let input = dir.join(input);
+        fs::write(&input, &example.input).map_err(io_error(&input))?;
+    }
+
+    let started = Instant::now();
+    let mut child = command
+        .spawn()
+        .map_err(|err| spawn_error(err.to_string()))?;
+    // read on another thread so a program printing a lot doesn't block on
+    // a full pipe while being waited for
+    let stdout = child.stdout.take().map(|mut stdout| {
+        std::thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = std::io::Read::read_to_end(&mut stdout, &mut output);
+            String::from_utf8_lossy(&output).into_owned()
+        })
+    });
+    // written on its own thread too, a program that doesn't read its input
+    // would otherwise block the write past the timeout. Killing the program
+    // closes the pipe and ends the thread
+    if let Some(mut stdin) = child.stdin.take() {
+        if options.files.is_none() {
+            let input = example.input.clone();
+            std::thread::spawn(move || {
+                // a program that exits without reading everything closes
+                // the pipe
+                let _ = stdin.write_all(input.as_bytes());
+            });
+        }
+    }
+
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|err| spawn_error(err.to_string()))?
+        {
+            break Some(status);
+        }
+        if started.elapsed() > options.timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    };
+    let time = started.elapsed();
+
+    // a process started by a killed program can keep the pipe open, so the
+    // output of a timed out example isn't waited for
+    let stdout = stdout
+        .filter(|_| status.is_some())
+        .and_then(|stdout| stdout.join().ok())
+        .unwrap_or_default();
+    let actual = match &options.files {
+        Some((_, output)) => {
+            let output = dir.join(output);
+            fs::read_to_string(&output).unwrap_or_default()
+        }
+        None => stdout,
+    };
+
+    let verdict = match status {
+        None => Verdict::TimeLimit,
+        Some(status) if !status.success() => Verdict::RuntimeError {
+            code: status.code(),
+        },
+        Some(_) if same_output(&example.output, &actual) => Verdict::Passed,
+        Some(_) => Verdict::WrongAnswer,
+    };
+    Ok((verdict, actual, time))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// Runs a shell script as the source of a problem
+    fn run(script: &str, examples: &[(&str, &str)], options: RunnerOptions) -> RunReport {
+        let source = std::env::temp_dir().join(format!(
+            "pbinfo-runner-test-{}-{}.sh",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        fs::write(&source, script).unwrap();
+        let examples: Vec<Example> = examples
+            .iter()
+            .map(|(input, output)| Example {
+                input: input.to_string(),
+                output: output.to_string(),
+            })
+            .collect();
+        let options = RunnerOptions {
+            compile: None,
+            run: "sh {source}".to_string(),
+            ..options
+        };
+        let report = run_examples(&source, &examples, &options).unwrap();
+        let _ = fs::remove_file(&source);
+        report
+    }
+
+    fn verdicts(report: &RunReport) -> Vec<Verdict> {
+        report
+            .results
+            .iter()
+            .map(|result| result.verdict.clone())
+            .collect()
+    }
+
+    #[test]
+    fn examples_are_given_on_stdin() {
+        let report = run(
+            "read a b\necho $((a + b))\n",
+            &[("1 2\n", "3"), ("2 2\n", "5")],
+            RunnerOptions::default(),
+        );
+        assert_eq!(verdicts(&report), [Verdict::Passed, Verdict::WrongAnswer]);
+        assert_eq!(report.results[1].actual, "4\n");
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn examples_can_use_files() {
+        let report = run(
+            "read a b < sum.in\necho $((a + b)) > sum.out\n",
+            &[("1 2", "3")],
+            RunnerOptions {
+                files: Some(("sum.in".to_string(), "sum.out".to_string())),
+                ..RunnerOptions::default()
+            },
+        );
+        assert!(report.passed());
+    }
+
+    #[test]
+    fn slow_programs_are_killed() {
+        let started = Instant::now();
+        // never reads its input nor exits on its own
+        let report = run(
+            "sleep 30\n",
+            &[(&"1 ".repeat(1 << 20), "")],
+            RunnerOptions {
+                timeout: Duration::from_millis(200),
+                ..RunnerOptions::default()
+            },
+        );
+        assert_eq!(verdicts(&report), [Verdict::TimeLimit]);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn failing_programs_are_runtime_errors() {
+        let report = run("exit 3\n", &[("", "")], RunnerOptions::default());
+        assert_eq!(verdicts(&report), [Verdict::RuntimeError { code: Some(3) }]);
+    }
+
+    #[test]
+    fn compile_errors_are_reported() {
+        let source = std::env::temp_dir().join("pbinfo-runner-test-missing.cpp");
+        let options = RunnerOptions {
+            compile: Some("sh -c false".to_string()),
+            ..RunnerOptions::default()
+        };
+        let _ = fs::write(&source, "");
+        assert!(matches!(
+            run_examples(&source, &[], &options),
+            Err(RunnerError::CompileError { .. })
+        ));
+    }
+}
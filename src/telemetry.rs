@@ -0,0 +1,352 @@
+//! Global hooks for feeding what the crate does into external monitoring.
+
+use std::{
+    cell::RefCell,
+    future::Future,
+    ops::Deref,
+    sync::{Arc, RwLock},
+};
+
+use reqwest::Method;
+
+use crate::{endpoints::Endpoint, time::SystemTime};
+
+/// Where an error happened
+#[derive(Debug, Clone)]
+pub struct ErrorContext {
+    /// The public operation that failed (`login`, `upload`, `get_score`, etc...)
+    pub operation: &'static str,
+    /// The problem the operation was about, if any
+    pub problem_id: Option<String>,
+    /// The solution the operation was about, if any
+    pub solution_id: Option<String>,
+    /// The endpoint of the last request sent before the error (see
+    /// [crate::endpoints::Endpoint::name]), `None` if nothing was sent
+    pub endpoint: Option<&'static str>,
+    /// The http method of the last request
+    pub method: Option<Method>,
+    /// The url of the last request
+    pub url: Option<String>,
+    /// How many times in a row the last request was sent, 0 if nothing was sent
+    pub attempt: u32,
+    /// When the error happened
+    pub timestamp: SystemTime,
+}
+
+impl Default for ErrorContext {
+    fn default() -> Self {
+        ErrorContext {
+            operation: "",
+            problem_id: None,
+            solution_id: None,
+            endpoint: None,
+            method: None,
+            url: None,
+            attempt: 0,
+            timestamp: SystemTime::now(),
+        }
+    }
+}
+
+impl ErrorContext {
+    pub fn new(operation: &'static str) -> Self {
+        ErrorContext {
+            operation,
+            ..Default::default()
+        }
+    }
+
+    /// Fills in the last request the operation sent
+    fn last_request(mut self, last_request: Option<RequestRecord>) -> Self {
+        if let Some(request) = last_request {
+            self.endpoint = Some(request.endpoint);
+            self.method = Some(request.method);
+            self.url = Some(request.url);
+            self.attempt = request.attempt;
+        }
+        self
+    }
+
+    pub fn problem_id(mut self, problem_id: &str) -> Self {
+        self.problem_id = Some(problem_id.to_string());
+        self
+    }
+
+    pub fn solution_id(mut self, solution_id: &str) -> Self {
+        self.solution_id = Some(solution_id.to_string());
+        self
+    }
+}
+
+/// An error together with the [ErrorContext] it happened in, returned by
+/// the methods of [crate::pbinfo_user::PbinfoUser]. Derefs to the error
+/// itself, so its variants can still be matched on
+#[derive(Debug)]
+pub struct ContextError<E> {
+    err: E,
+    context: Box<ErrorContext>,
+}
+
+impl<E> ContextError<E> {
+    pub fn new(err: E, context: ErrorContext) -> Self {
+        ContextError {
+            err,
+            context: Box::new(context),
+        }
+    }
+
+    /// Where the error happened
+    pub fn context(&self) -> &ErrorContext {
+        &self.context
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.err
+    }
+
+    pub fn into_inner(self) -> E {
+        self.err
+    }
+}
+
+impl<E> Deref for ContextError<E> {
+    type Target = E;
+
+    fn deref(&self) -> &E {
+        &self.err
+    }
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for ContextError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.err.fmt(f)
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for ContextError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.err.source()
+    }
+}
+
+/// Called with every error returned by the crate and where it happened
+pub type ErrorHook = dyn Fn(&dyn std::error::Error, &ErrorContext) + Send + Sync;
+
+static ON_ERROR: RwLock<Option<Arc<ErrorHook>>> = RwLock::new(None);
+
+/// Registers a global hook that is called with every error the crate
+/// returns, replacing the previous one
+pub fn set_on_error(hook: impl Fn(&dyn std::error::Error, &ErrorContext) + Send + Sync + 'static) {
+    *ON_ERROR.write().unwrap_or_else(|err| err.into_inner()) = Some(Arc::new(hook));
+}
+
+/// Removes the hook set with [set_on_error]
+pub fn clear_on_error() {
+    *ON_ERROR.write().unwrap_or_else(|err| err.into_inner()) = None;
+}
+
+pub(crate) fn report_error(err: &dyn std::error::Error, context: &ErrorContext) {
+    trace_event!(
+        tracing::Level::ERROR,
+        operation = context.operation,
+        problem_id = context.problem_id,
+        solution_id = context.solution_id,
+        endpoint = context.endpoint,
+        url = context.url,
+        attempt = context.attempt,
+        error = %err,
+        "operation failed"
+    );
+
+    let hook = ON_ERROR
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone();
+    if let Some(hook) = hook {
+        hook(err, context);
+    }
+}
+
+/// Reports the error of a result (if any) with [report_error] and attaches
+/// the context to it
+pub(crate) trait ReportErr<T, E> {
+    fn report(self, context: impl FnOnce() -> ErrorContext) -> Result<T, ContextError<E>>;
+}
+
+impl<T, E: std::error::Error> ReportErr<T, E> for Result<T, E> {
+    fn report(self, context: impl FnOnce() -> ErrorContext) -> Result<T, ContextError<E>> {
+        self.map_err(|err| {
+            let context = context();
+            report_error(&err, &context);
+            ContextError::new(err, context)
+        })
+    }
+}
+
+/// Same as [ReportErr] for an operation that sends requests, the context
+/// gets the last request the operation itself sent
+pub(crate) trait ReportFuture<T, E> {
+    fn report(
+        self,
+        context: impl FnOnce() -> ErrorContext,
+    ) -> impl Future<Output = Result<T, ContextError<E>>>;
+}
+
+impl<T, E: std::error::Error, F: Future<Output = Result<T, E>>> ReportFuture<T, E> for F {
+    async fn report(self, context: impl FnOnce() -> ErrorContext) -> Result<T, ContextError<E>> {
+        let (result, last_request) = recorded(self).await;
+        result.report(|| context().last_request(last_request))
+    }
+}
+
+/// A request sent by an operation, for the context of its errors
+#[derive(Debug, Clone)]
+pub(crate) struct RequestRecord {
+    pub(crate) endpoint: &'static str,
+    pub(crate) method: Method,
+    pub(crate) url: String,
+    /// How many times in a row the same request was sent
+    pub(crate) attempt: u32,
+}
+
+thread_local! {
+    /// The last request of the operation being polled on this thread, the
+    /// outer `None` when no operation is recording
+    static LAST_REQUEST: RefCell<Option<Option<RequestRecord>>> = const { RefCell::new(None) };
+}
+
+/// Remembers that `endpoint` is about to be sent to `url` by the operation
+/// being polled
+pub(crate) fn record_request<E: Endpoint>(endpoint: &E, url: &str) {
+    LAST_REQUEST.with_borrow_mut(|slot| {
+        let Some(last_request) = slot else {
+            return;
+        };
+        let url = url.to_string();
+        let attempt = match last_request {
+            Some(last) if last.endpoint == endpoint.name() && last.url == url => last.attempt + 1,
+            _ => 1,
+        };
+        *last_request = Some(RequestRecord {
+            endpoint: endpoint.name(),
+            method: endpoint.method(),
+            url,
+            attempt,
+        });
+    });
+}
+
+/// Runs `future` with its own record of the last request, so operations
+/// running at the same time (even on clones of one client) don't see each
+/// other's requests. Polling is what scopes the record, nothing has to be
+/// passed down to [crate::http]
+async fn recorded<F: Future>(future: F) -> (F::Output, Option<RequestRecord>) {
+    /// Puts the record of the operation in place for one poll
+    struct Scope<'a> {
+        last_request: &'a mut Option<RequestRecord>,
+        outer: Option<Option<RequestRecord>>,
+    }
+
+    impl Drop for Scope<'_> {
+        fn drop(&mut self) {
+            let outer = self.outer.take();
+            *self.last_request = LAST_REQUEST.replace(outer).flatten();
+            // an operation run by another one still counts for the outer one
+            if let Some(request) = &self.last_request {
+                LAST_REQUEST.with_borrow_mut(|slot| {
+                    if let Some(outer) = slot {
+                        *outer = Some(request.clone());
+                    }
+                });
+            }
+        }
+    }
+
+    let mut future = std::pin::pin!(future);
+    let mut last_request = None;
+    let output = std::future::poll_fn(|cx| {
+        let outer = LAST_REQUEST.replace(Some(last_request.take()));
+        let _scope = Scope {
+            last_request: &mut last_request,
+            outer,
+        };
+        future.as_mut().poll(cx)
+    })
+    .await;
+    (output, last_request)
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::endpoints::ProblemPage;
+
+    fn record_problem_page(problem_id: &str) {
+        let endpoint = ProblemPage::new(problem_id);
+        record_request(&endpoint, &endpoint.url());
+    }
+
+    /// Sends the problem page of `problem_id`, lets the other operations
+    /// run and fails
+    async fn fail_after_request(problem_id: &str) -> Result<(), std::io::Error> {
+        record_problem_page(problem_id);
+        tokio::task::yield_now().await;
+        Err(std::io::Error::other("failed"))
+    }
+
+    #[tokio::test]
+    async fn operations_at_the_same_time_keep_their_own_last_request() {
+        let (first, second) = tokio::join!(
+            fail_after_request("1").report(|| ErrorContext::new("first")),
+            fail_after_request("2").report(|| ErrorContext::new("second")),
+        );
+
+        let first = first.unwrap_err();
+        assert_eq!(
+            first.context().url.as_deref(),
+            Some("https://www.pbinfo.ro/probleme/1")
+        );
+        assert_eq!(first.context().attempt, 1);
+        let second = second.unwrap_err();
+        assert_eq!(
+            second.context().url.as_deref(),
+            Some("https://www.pbinfo.ro/probleme/2")
+        );
+    }
+
+    #[tokio::test]
+    async fn an_inner_operation_counts_for_the_outer_one() {
+        let outer = async {
+            record_problem_page("1");
+            let _ = fail_after_request("1")
+                .report(|| ErrorContext::new("inner"))
+                .await;
+            Err::<(), _>(std::io::Error::other("failed"))
+        }
+        .report(|| ErrorContext::new("outer"))
+        .await
+        .unwrap_err();
+
+        assert_eq!(outer.context().endpoint, Some("problem-page"));
+        assert_eq!(outer.context().attempt, 1);
+    }
+
+    #[tokio::test]
+    async fn nothing_is_recorded_outside_an_operation() {
+        record_problem_page("1");
+        let err = async { Err::<(), _>(std::io::Error::other("failed")) }
+            .report(|| ErrorContext::new("empty"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.context().url, None);
+        assert_eq!(err.context().attempt, 0);
+    }
+
+    #[test]
+    fn reported_operations_can_be_spawned() {
+        fn assert_send<T: Send>(_: &T) {}
+        let pbinfo_user = crate::pbinfo_user::PbinfoUser::builder().build();
+        assert_send(&pbinfo_user.get_problem("1"));
+        assert_send(&pbinfo_user.upload("1", "int main() {}"));
+    }
+}
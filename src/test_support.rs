@@ -0,0 +1,159 @@
+//! A [HttpTransport] answering with canned responses, for testing the flows
+//! that talk to pbinfo without the network.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use reqwest::{header::HeaderMap, Method};
+
+use crate::{
+    transport::{HttpTransport, TransportFuture},
+    PbinfoClient,
+};
+
+pub(crate) const LOGGED_IN: &str = include_str!("../tests/fixtures/homepage_logged_in.html");
+pub(crate) const LOGGED_OUT: &str = include_str!("../tests/fixtures/homepage_logged_out.html");
+pub(crate) const PROBLEM_PAGE: &str = include_str!("../tests/fixtures/problem_page.html");
+pub(crate) const SCORE_DONE: &str = include_str!("../tests/fixtures/score_done.json");
+pub(crate) const SCORE_PENDING: &str = include_str!("../tests/fixtures/score_pending.json");
+
+/// A request the [MockTransport] answered
+#[derive(Debug, Clone)]
+pub(crate) struct SentRequest {
+    pub(crate) method: Method,
+    pub(crate) url: String,
+    pub(crate) headers: HeaderMap,
+}
+
+impl SentRequest {
+    /// The path and the query of the url, like `/json/solutie/1`
+    pub(crate) fn path(&self) -> &str {
+        let after_scheme = self.url.split_once("://").map_or("", |(_, rest)| rest);
+        after_scheme
+            .find('/')
+            .map_or("/", |start| &after_scheme[start..])
+    }
+}
+
+/// Every request a [MockTransport] answered, shared with the test
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Sent(Arc<Mutex<Vec<SentRequest>>>);
+
+impl Sent {
+    pub(crate) fn requests(&self) -> Vec<SentRequest> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Answers every request with what `respond` makes out of it
+pub(crate) struct MockTransport<F> {
+    respond: F,
+    sent: Sent,
+}
+
+impl<F> HttpTransport for MockTransport<F>
+where
+    F: Fn(&SentRequest) -> http::Response<String> + Send + Sync,
+{
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+        let sent = SentRequest {
+            method: request.method().clone(),
+            url: request.url().to_string(),
+            headers: request.headers().clone(),
+        };
+        let response = (self.respond)(&sent);
+        self.sent
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sent);
+        Box::pin(async move { Ok(reqwest::Response::from(response)) })
+    }
+}
+
+/// A transport answering with what `respond` makes out of every request,
+/// and the requests it answered
+pub(crate) fn transport<F>(respond: F) -> (MockTransport<F>, Sent)
+where
+    F: Fn(&SentRequest) -> http::Response<String> + Send + Sync + 'static,
+{
+    let sent = Sent::default();
+    let transport = MockTransport {
+        respond,
+        sent: sent.clone(),
+    };
+    (transport, sent)
+}
+
+/// A client whose requests are answered by `respond`, and the requests it
+/// sent
+pub(crate) fn client<F>(respond: F) -> (PbinfoClient, Sent)
+where
+    F: Fn(&SentRequest) -> http::Response<String> + Send + Sync + 'static,
+{
+    let (transport, sent) = transport(respond);
+    let client = PbinfoClient::builder()
+        .transport(transport)
+        .build()
+        .unwrap();
+    (client, sent)
+}
+
+/// Answers like pbinfo for a logged in user who uploads solutions: the
+/// problem pages have a source field, every upload is accepted as
+/// `solution_id` and every evaluation is done
+pub(crate) fn accepting_uploads(
+    request: &SentRequest,
+    solution_id: &str,
+) -> http::Response<String> {
+    let path = request.path();
+    if path.starts_with("/probleme/") {
+        ok(PROBLEM_PAGE)
+    } else if path.starts_with("/ajx-module/php-solutie-incarcare.php") {
+        ok(format!(
+            "{{\"stare\":\"success\",\"id_solutie\":\"{solution_id}\"}}"
+        ))
+    } else if path.starts_with("/ajx-module/ajx-solutie-detalii-evaluare.php") {
+        ok(SCORE_DONE)
+    } else {
+        ok(LOGGED_IN)
+    }
+}
+
+/// A `200 OK` with `body`
+pub(crate) fn ok(body: impl Into<String>) -> http::Response<String> {
+    http::Response::new(body.into())
+}
+
+/// The answer of pbinfo to a login post, with the session cookie if the
+/// login worked
+pub(crate) fn login_answer(message: &str, ssid: Option<&str>) -> http::Response<String> {
+    let mut response = http::Response::builder();
+    if let Some(ssid) = ssid {
+        response = response.header("set-cookie", format!("SSID={ssid}; path=/"));
+    }
+    response
+        .body(format!(
+            "{{\"raspuns\":\"{message}\",\"form_token\":\"new-token\"}}"
+        ))
+        .unwrap()
+}
+
+/// Whether the request was sent with the session cookie `ssid`
+pub(crate) fn has_session(request: &SentRequest, ssid: &str) -> bool {
+    request
+        .headers
+        .get(reqwest::header::COOKIE)
+        .and_then(|cookie| cookie.to_str().ok())
+        .is_some_and(|cookie| cookie.contains(ssid))
+}
+
+/// A response with `status` and an empty body
+pub(crate) fn status(status: u16) -> http::Response<String> {
+    http::Response::builder()
+        .status(status)
+        .body(String::new())
+        .unwrap()
+}
@@ -0,0 +1,7 @@
+//! `Instant` and `SystemTime` that also work in the browser, where the ones
+//! of std panic.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::{Instant, SystemTime};
+#[cfg(target_arch = "wasm32")]
+pub use web_time::{Instant, SystemTime};
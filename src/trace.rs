@@ -0,0 +1,8 @@
+/// Emits a [tracing] event when the `tracing` feature is enabled, otherwise
+/// expands to nothing. Takes the same arguments as `tracing::event!`
+macro_rules! trace_event {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!($($arg)+);
+    };
+}
@@ -0,0 +1,27 @@
+//! What actually sends the requests, swappable so the code using the crate
+//! can be tested without hitting pbinfo.
+
+use std::{future::Future, pin::Pin};
+
+/// The future returned by [HttpTransport::execute]
+#[cfg(not(target_arch = "wasm32"))]
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<reqwest::Response, reqwest::Error>> + Send + 'a>>;
+/// The future returned by [HttpTransport::execute], the requests of the
+/// browser can't be sent between threads
+#[cfg(target_arch = "wasm32")]
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<reqwest::Response, reqwest::Error>> + 'a>>;
+
+/// Sends the requests of a [crate::PbinfoClient]. `reqwest::Client` is the
+/// default one, a test double can answer with canned responses made out of
+/// `http::Response` (`reqwest::Response` implements `From` for it)
+pub trait HttpTransport: Send + Sync {
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_>;
+}
+
+impl HttpTransport for reqwest::Client {
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+        Box::pin(reqwest::Client::execute(self, request))
+    }
+}
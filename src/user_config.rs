@@ -0,0 +1,976 @@
+//! The settings stored in the config file, in a form a settings screen can
+//! be built on.
+//!
+//! Where the settings are kept is up to a [ConfigStore], by default (with
+//! the `config` feature) a toml file in the config dir of the system.
+
+#[cfg(feature = "config")]
+use std::{collections::BTreeMap, fs, path::PathBuf};
+use std::{sync::Arc, time::Duration};
+
+#[cfg(feature = "config")]
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "tokio")]
+use crate::pbinfo_user::BatchOptions;
+use crate::pbinfo_user::{LoginOptions, PbinfoUserError, PollOptions};
+
+#[cfg(feature = "config")]
+const CONFIG_FILE_NAME: &str = "pbinfo.toml";
+
+#[cfg(feature = "config")]
+fn get_proj_dir() -> Result<ProjectDirs, PbinfoUserError> {
+    directories::ProjectDirs::from("dev", "insertokername", "pbinfo-api")
+        .ok_or(PbinfoUserError::NoHomeDirError)
+}
+
+/// Returns the directory cached data is kept in, like `~/.cache/pbinfo-api`
+/// or AppData on windows
+#[cfg(feature = "config")]
+pub fn cache_dir() -> Result<PathBuf, PbinfoUserError> {
+    Ok(get_proj_dir()?.cache_dir().to_path_buf())
+}
+
+/// The environment variable that moves the config file somewhere else, for
+/// containers and portable installs
+pub const CONFIG_PATH_VAR: &str = "PBINFO_CONFIG";
+
+/// Returns the path of the config file, [CONFIG_PATH_VAR] if it is set and
+/// `pbinfo.toml` in the ~/config dir or AppData on windows otherwise
+#[cfg(feature = "config")]
+pub fn config_file_path() -> Result<PathBuf, PbinfoUserError> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_VAR).filter(|path| !path.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    Ok(get_proj_dir()?.config_dir().join(CONFIG_FILE_NAME))
+}
+
+/// Somewhere a [UserConfig] can be kept, like a file, the keyring of the
+/// system or the settings database of the application embedding the crate
+pub trait ConfigStore {
+    fn load(&self) -> Result<UserConfig, PbinfoUserError>;
+    fn save(&self, config: &UserConfig) -> Result<(), PbinfoUserError>;
+}
+
+/// The profile used when none is asked for and the config file doesn't
+/// name a `default_profile`
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// A whole config file, one [UserConfig] per `[profiles.<name>]` section,
+/// so a personal and a school account can live side by side
+#[cfg(feature = "config")]
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ConfigFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, UserConfig>,
+}
+
+#[cfg(feature = "config")]
+impl ConfigFile {
+    /// Files written before there were profiles are read as the default
+    /// profile, and get rewritten with sections on the next save
+    fn parse(config_file: &str) -> Result<Self, PbinfoUserError> {
+        let toml_error = |err: toml::de::Error| PbinfoUserError::TomlParseError { error: err };
+        let table: toml::Table = toml::from_str(config_file).map_err(toml_error)?;
+        if table.contains_key("profiles") {
+            return toml::from_str(config_file).map_err(toml_error);
+        }
+        let config = toml::from_str(config_file).map_err(toml_error)?;
+        Ok(ConfigFile {
+            default_profile: None,
+            profiles: BTreeMap::from([(DEFAULT_PROFILE.to_string(), config)]),
+        })
+    }
+
+    /// `profile`, or the default one if it is `None`
+    fn profile_name(&self, profile: Option<&str>) -> String {
+        profile
+            .or(self.default_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE)
+            .to_string()
+    }
+
+    fn take(mut self, profile: Option<&str>) -> Result<UserConfig, PbinfoUserError> {
+        let profile = self.profile_name(profile);
+        self.profiles
+            .remove(&profile)
+            .ok_or(PbinfoUserError::MissingProfileError { profile })
+    }
+
+    fn set(&mut self, profile: Option<&str>, config: &UserConfig) {
+        self.profiles
+            .insert(self.profile_name(profile), config.clone());
+    }
+}
+
+/// Keeps the config in a toml file, by default [config_file_path]
+#[cfg(feature = "config")]
+#[derive(Debug, Clone, Default)]
+pub struct TomlFileStore {
+    path: Option<PathBuf>,
+    profile: Option<String>,
+}
+
+#[cfg(feature = "config")]
+impl TomlFileStore {
+    /// A store for the toml file at `path` instead of the default one
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        TomlFileStore {
+            path: Some(path.into()),
+            profile: None,
+        }
+    }
+
+    /// Reads and writes the `[profiles.<profile>]` section instead of the
+    /// default profile, the other profiles are kept as they are
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Same as [check_permissions] for the file of the store
+    pub fn check_permissions(&self) -> Result<Option<PermissionsWarning>, PbinfoUserError> {
+        Ok(check_permissions(&self.path()?))
+    }
+
+    /// The names of the profiles in the file
+    pub fn profiles(&self) -> Result<Vec<String>, PbinfoUserError> {
+        Ok(self
+            .read()?
+            .map(|file| file.profiles.into_keys().collect())
+            .unwrap_or_default())
+    }
+
+    fn path(&self) -> Result<PathBuf, PbinfoUserError> {
+        match &self.path {
+            Some(path) => Ok(path.clone()),
+            None => config_file_path(),
+        }
+    }
+
+    /// The whole file, `None` if there is no file yet
+    fn read(&self) -> Result<Option<ConfigFile>, PbinfoUserError> {
+        match fs::read_to_string(self.path()?) {
+            Ok(config_file) => ConfigFile::parse(&config_file).map(Some),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(PbinfoUserError::ReadConfigError { error: err }),
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+impl ConfigStore for TomlFileStore {
+    fn load(&self) -> Result<UserConfig, PbinfoUserError> {
+        let path = self.path()?;
+        if let Some(_warning) = check_permissions(&path) {
+            trace_event!(tracing::Level::WARN, warning = %_warning, "the config file isn't private");
+        }
+        let config_file = fs::read_to_string(path)
+            .map_err(|err: std::io::Error| PbinfoUserError::ReadConfigError { error: err })?;
+
+        ConfigFile::parse(&config_file)?.take(self.profile.as_deref())
+    }
+
+    fn save(&self, config: &UserConfig) -> Result<(), PbinfoUserError> {
+        let mut file = self.read()?.unwrap_or_default();
+        file.set(self.profile.as_deref(), config);
+        write_file(&self.path()?, toml::to_string(&file).unwrap().as_bytes())
+    }
+}
+
+/// Writes a config file, creating its directory if needed
+#[cfg(feature = "config")]
+fn write_file(config_file_path: &std::path::Path, contents: &[u8]) -> Result<(), PbinfoUserError> {
+    if let Some(parent_dir) = config_file_path.parent() {
+        if !parent_dir.exists() {
+            fs::create_dir_all(parent_dir).map_err(|err| PbinfoUserError::WriteError {
+                file: parent_dir.to_path_buf(),
+                error: err,
+            })?
+        }
+    }
+
+    let write_error = |err| PbinfoUserError::WriteError {
+        file: config_file_path.to_path_buf(),
+        error: err,
+    };
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // only the owner can read the credentials, on windows the config dir is
+    // already private to the user
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(config_file_path).map_err(write_error)?;
+    // files made before are narrowed down too, the mode only applies to new ones
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))
+        .map_err(write_error)?;
+    std::io::Write::write_all(&mut file, contents).map_err(write_error)
+}
+
+/// Returned by [check_permissions] for a config file other users can read
+#[cfg(feature = "config")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionsWarning {
+    pub path: PathBuf,
+    /// The unix mode of the file, like `0o644`
+    pub mode: u32,
+}
+
+#[cfg(feature = "config")]
+impl std::fmt::Display for PermissionsWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Warning: The config file {} can be read by other users (mode {:o})!\nRun `chmod 600` on it or save the config again",
+            self.path.display(),
+            self.mode
+        )
+    }
+}
+
+/// Whether users other than the owner can read or write the config file at
+/// `path`. Always `None` on windows and for files that don't exist
+#[cfg(feature = "config")]
+pub fn check_permissions(path: &std::path::Path) -> Option<PermissionsWarning> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(path).ok()?.permissions().mode() & 0o777;
+        (mode & 0o077 != 0).then(|| PermissionsWarning {
+            path: path.to_path_buf(),
+            mode,
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+#[cfg(feature = "encrypted-config")]
+const ENCRYPTED_CONFIG_FILE_NAME: &str = "pbinfo.toml.enc";
+/// The start of every encrypted config file, followed by the salt of the
+/// key, the nonce and the encrypted toml
+#[cfg(feature = "encrypted-config")]
+const ENCRYPTED_CONFIG_MAGIC: &[u8] = b"pbinfo-enc1";
+#[cfg(feature = "encrypted-config")]
+const SALT_LEN: usize = 16;
+#[cfg(feature = "encrypted-config")]
+const NONCE_LEN: usize = 12;
+
+/// Where the key of an [EncryptedFileStore] comes from
+#[cfg(feature = "encrypted-config")]
+#[derive(Clone)]
+pub enum EncryptionKey {
+    Passphrase(String),
+    /// A file with the secret in it, like one made with
+    /// `head -c 32 /dev/urandom > pbinfo.key`
+    KeyFile(PathBuf),
+}
+
+#[cfg(feature = "encrypted-config")]
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncryptionKey::Passphrase(_) => f.write_str("Passphrase(\"***\")"),
+            EncryptionKey::KeyFile(path) => f.debug_tuple("KeyFile").field(path).finish(),
+        }
+    }
+}
+
+/// Keeps the config in a toml file encrypted with ChaCha20-Poly1305, by
+/// default `pbinfo.toml.enc` next to [config_file_path]. The key is derived
+/// out of the passphrase or the key file with Argon2, so a weak passphrase
+/// is still slow to guess.
+///
+/// Use it with [PbinfoUser::save_config_to](crate::pbinfo_user::PbinfoUser::save_config_to)
+/// and [PbinfoUser::get_config_from](crate::pbinfo_user::PbinfoUser::get_config_from)
+#[cfg(feature = "encrypted-config")]
+#[derive(Debug, Clone)]
+pub struct EncryptedFileStore {
+    key: EncryptionKey,
+    path: Option<PathBuf>,
+    profile: Option<String>,
+}
+
+#[cfg(feature = "encrypted-config")]
+impl EncryptedFileStore {
+    pub fn new(key: EncryptionKey) -> Self {
+        EncryptedFileStore {
+            key,
+            path: None,
+            profile: None,
+        }
+    }
+
+    /// Keeps the config at `path` instead of the default file
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Same as [TomlFileStore::profile]
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    fn path(&self) -> Result<PathBuf, PbinfoUserError> {
+        match &self.path {
+            Some(path) => Ok(path.clone()),
+            None => Ok(get_proj_dir()?
+                .config_dir()
+                .join(ENCRYPTED_CONFIG_FILE_NAME)),
+        }
+    }
+
+    fn cipher(&self, salt: &[u8]) -> Result<chacha20poly1305::ChaCha20Poly1305, PbinfoUserError> {
+        use chacha20poly1305::KeyInit;
+
+        let secret = match &self.key {
+            EncryptionKey::Passphrase(passphrase) => passphrase.as_bytes().to_vec(),
+            EncryptionKey::KeyFile(path) => {
+                fs::read(path).map_err(|error| PbinfoUserError::ReadConfigError { error })?
+            }
+        };
+        let mut key = [0; 32];
+        argon2::Argon2::default()
+            .hash_password_into(&secret, salt, &mut key)
+            .map_err(|err| PbinfoUserError::StoreError {
+                err: err.to_string(),
+            })?;
+        Ok(chacha20poly1305::ChaCha20Poly1305::new(&key.into()))
+    }
+}
+
+#[cfg(feature = "encrypted-config")]
+impl ConfigStore for EncryptedFileStore {
+    fn load(&self) -> Result<UserConfig, PbinfoUserError> {
+        let file =
+            fs::read(self.path()?).map_err(|error| PbinfoUserError::ReadConfigError { error })?;
+        self.decrypt(&file)?.take(self.profile.as_deref())
+    }
+
+    fn save(&self, config: &UserConfig) -> Result<(), PbinfoUserError> {
+        use chacha20poly1305::aead::Aead;
+
+        // the other profiles are kept, which needs the right key
+        let mut config_file = match fs::read(self.path()?) {
+            Ok(file) => self.decrypt(&file)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => ConfigFile::default(),
+            Err(error) => return Err(PbinfoUserError::ReadConfigError { error }),
+        };
+        config_file.set(self.profile.as_deref(), config);
+
+        let salt: [u8; SALT_LEN] = rand::random();
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let encrypted = self
+            .cipher(&salt)?
+            .encrypt(
+                &nonce.into(),
+                toml::to_string(&config_file).unwrap().as_bytes(),
+            )
+            .map_err(|err| PbinfoUserError::StoreError {
+                err: err.to_string(),
+            })?;
+
+        let mut file = ENCRYPTED_CONFIG_MAGIC.to_vec();
+        file.extend_from_slice(&salt);
+        file.extend_from_slice(&nonce);
+        file.extend_from_slice(&encrypted);
+        write_file(&self.path()?, &file)
+    }
+}
+
+#[cfg(feature = "encrypted-config")]
+impl EncryptedFileStore {
+    fn decrypt(&self, file: &[u8]) -> Result<ConfigFile, PbinfoUserError> {
+        use chacha20poly1305::aead::Aead;
+
+        let not_encrypted = || PbinfoUserError::StoreError {
+            err: "The file isn't an encrypted config".to_string(),
+        };
+        let file = file
+            .strip_prefix(ENCRYPTED_CONFIG_MAGIC)
+            .filter(|file| file.len() >= SALT_LEN + NONCE_LEN)
+            .ok_or_else(not_encrypted)?;
+        let (salt, file) = file.split_at(SALT_LEN);
+        let (nonce, encrypted) = file.split_at(NONCE_LEN);
+
+        let config = self
+            .cipher(salt)?
+            .decrypt(nonce.into(), encrypted)
+            .map_err(|_| PbinfoUserError::StoreError {
+                err: "Couldn't decrypt the config, the key is wrong or the file was changed"
+                    .to_string(),
+            })?;
+        let config = String::from_utf8(config).map_err(|_| not_encrypted())?;
+        ConfigFile::parse(&config)
+    }
+}
+
+/// The name the secrets are saved under in the keyring
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "pbinfo-api";
+
+/// Keeps the password and the session in the secret store of the system
+/// (the Keychain on macos, the Credential Manager on windows and the kernel
+/// keyring on linux) and everything else in a toml file.
+///
+/// A config file written before the keyring was used still loads, with the
+/// password out of the file, until it is saved again
+#[cfg(feature = "keyring")]
+#[derive(Debug, Clone, Default)]
+pub struct KeyringStore {
+    file: TomlFileStore,
+}
+
+/// What [KeyringStore] keeps out of the config file
+#[cfg(feature = "keyring")]
+#[derive(Serialize, Deserialize)]
+struct Secrets {
+    password: String,
+    ssid: String,
+    form_token: String,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringStore {
+    /// A store that keeps the settings that aren't secret in `file`
+    pub fn new(file: TomlFileStore) -> Self {
+        KeyringStore { file }
+    }
+
+    /// The entry of an account, by its email
+    fn entry(email: &str) -> Result<keyring::Entry, PbinfoUserError> {
+        keyring::Entry::new(KEYRING_SERVICE, email).map_err(|err| PbinfoUserError::StoreError {
+            err: err.to_string(),
+        })
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl ConfigStore for KeyringStore {
+    fn load(&self) -> Result<UserConfig, PbinfoUserError> {
+        let mut config = self.file.load()?;
+        let secrets = match Self::entry(&config.email)?.get_password() {
+            Ok(secrets) => secrets,
+            Err(keyring::Error::NoEntry) => return Ok(config),
+            Err(err) => {
+                return Err(PbinfoUserError::StoreError {
+                    err: err.to_string(),
+                })
+            }
+        };
+        let secrets: Secrets =
+            serde_json::from_str(&secrets).map_err(|err| PbinfoUserError::StoreError {
+                err: err.to_string(),
+            })?;
+        config.password = secrets.password;
+        config.ssid = secrets.ssid;
+        config.form_token = secrets.form_token;
+        Ok(config)
+    }
+
+    fn save(&self, config: &UserConfig) -> Result<(), PbinfoUserError> {
+        let secrets = Secrets {
+            password: config.password.clone(),
+            ssid: config.ssid.clone(),
+            form_token: config.form_token.clone(),
+        };
+        Self::entry(&config.email)?
+            .set_password(&serde_json::to_string(&secrets).unwrap())
+            .map_err(|err| PbinfoUserError::StoreError {
+                err: err.to_string(),
+            })?;
+
+        let mut config = config.clone();
+        config.password.clear();
+        config.ssid.clear();
+        config.form_token.clear();
+        self.file.save(&config)
+    }
+}
+
+/// Where [UserConfig::load] and [UserConfig::save] keep the config, in
+/// `profile` or the default profile
+#[cfg(all(feature = "config", not(feature = "keyring")))]
+pub(crate) fn default_store(profile: Option<&str>) -> TomlFileStore {
+    let store = TomlFileStore::default();
+    match profile {
+        Some(profile) => store.profile(profile),
+        None => store,
+    }
+}
+
+/// Where [UserConfig::load] and [UserConfig::save] keep the config, in
+/// `profile` or the default profile
+#[cfg(feature = "keyring")]
+pub(crate) fn default_store(profile: Option<&str>) -> KeyringStore {
+    let store = TomlFileStore::default();
+    KeyringStore::new(match profile {
+        Some(profile) => store.profile(profile),
+        None => store,
+    })
+}
+
+/// A setting of a [UserConfig]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigKey {
+    Email,
+    Password,
+    /// The saved session (ssid, form token and user id)
+    Session,
+    PollInterval,
+    MaxPendingEvaluations,
+    LoginTimeout,
+}
+
+/// The accepted range of a numeric setting
+struct Bounds {
+    key: &'static str,
+    min: u64,
+    max: u64,
+    unit: &'static str,
+}
+
+const POLL_INTERVAL: Bounds = Bounds {
+    key: "poll_interval_ms",
+    min: 500,
+    max: 60_000,
+    unit: "milliseconds",
+};
+const MAX_PENDING_EVALUATIONS: Bounds = Bounds {
+    key: "max_pending_evaluations",
+    min: 1,
+    max: 10,
+    unit: "evaluations",
+};
+const LOGIN_TIMEOUT: Bounds = Bounds {
+    key: "login_timeout_secs",
+    min: 1,
+    max: 600,
+    unit: "seconds",
+};
+
+impl Bounds {
+    fn check(&self, value: Option<u64>) -> Result<Option<u64>, PbinfoUserError> {
+        match value {
+            Some(value) if value < self.min || value > self.max => {
+                Err(PbinfoUserError::InvalidValueError {
+                    key: self.key,
+                    value: value.to_string(),
+                    range: format!("{} to {} {}", self.min, self.max, self.unit),
+                })
+            }
+            value => Ok(value),
+        }
+    }
+}
+
+/// Passed to the listeners of a [UserConfig] after a setting changed
+#[derive(Debug, Clone)]
+pub struct ConfigChange {
+    pub key: ConfigKey,
+}
+
+/// A partial update of a [UserConfig], only the fields that are `Some` are
+/// changed
+#[derive(Debug, Clone, Default)]
+pub struct UserConfigUpdate {
+    pub email: Option<String>,
+    pub password: Option<String>,
+}
+
+type Listener = Arc<dyn Fn(&ConfigChange) + Send + Sync>;
+
+/// Everything that is saved in the config file
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct UserConfig {
+    pub(crate) email: String,
+    /// Empty in the file when the password is kept in the keyring
+    #[serde(default)]
+    pub(crate) password: String,
+    #[serde(default)]
+    pub(crate) ssid: String,
+    #[serde(default)]
+    pub(crate) form_token: String,
+    #[serde(default)]
+    pub(crate) user_id: String,
+    /// How often evaluations are checked, see [UserConfig::poll_interval]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    poll_interval_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_pending_evaluations: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    login_timeout_secs: Option<u64>,
+    #[serde(skip)]
+    listeners: Vec<Listener>,
+}
+
+impl std::fmt::Debug for UserConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserConfig")
+            .field("email", &self.email)
+            .field("password", &"***")
+            .field("ssid", &"***")
+            .field("form_token", &"***")
+            .field("user_id", &self.user_id)
+            .field("poll_interval_ms", &self.poll_interval_ms)
+            .field("max_pending_evaluations", &self.max_pending_evaluations)
+            .field("login_timeout_secs", &self.login_timeout_secs)
+            .field("listeners", &self.listeners.len())
+            .finish()
+    }
+}
+
+impl UserConfig {
+    pub fn new(email: impl Into<String>, password: impl Into<String>) -> Self {
+        UserConfig {
+            email: email.into(),
+            password: password.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Reads the config file, and the secrets out of the keyring with the
+    /// `keyring` feature
+    #[cfg(feature = "config")]
+    pub fn load() -> Result<UserConfig, PbinfoUserError> {
+        Self::load_from(&default_store(None))
+    }
+
+    /// Same as [UserConfig::load] but for a named profile of the config file
+    #[cfg(feature = "config")]
+    pub fn load_profile(profile: &str) -> Result<UserConfig, PbinfoUserError> {
+        Self::load_from(&default_store(Some(profile)))
+    }
+
+    /// Writes the config file, creating its directory if needed. With the
+    /// `keyring` feature the password and the session go in the keyring
+    #[cfg(feature = "config")]
+    pub fn save(&self) -> Result<(), PbinfoUserError> {
+        default_store(None).save(self)
+    }
+
+    /// Same as [UserConfig::save] but in a named profile, the other profiles
+    /// of the file are kept
+    #[cfg(feature = "config")]
+    pub fn save_profile(&self, profile: &str) -> Result<(), PbinfoUserError> {
+        default_store(Some(profile)).save(self)
+    }
+
+    /// Reads the config out of `store`, failing if a setting is out of range
+    pub fn load_from(store: &dyn ConfigStore) -> Result<UserConfig, PbinfoUserError> {
+        let config = store.load()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks that all the settings are in their accepted range
+    pub fn validate(&self) -> Result<(), PbinfoUserError> {
+        POLL_INTERVAL.check(self.poll_interval_ms)?;
+        MAX_PENDING_EVALUATIONS.check(self.max_pending_evaluations)?;
+        LOGIN_TIMEOUT.check(self.login_timeout_secs)?;
+        Ok(())
+    }
+
+    /// Writes the config into `store`
+    pub fn save_to(&self, store: &dyn ConfigStore) -> Result<(), PbinfoUserError> {
+        store.save(self)
+    }
+
+    /// Registers a listener that is called after every change of a setting
+    pub fn on_change(&mut self, listener: impl Fn(&ConfigChange) + Send + Sync + 'static) {
+        self.listeners.push(Arc::new(listener));
+    }
+
+    fn notify(&self, key: ConfigKey) {
+        let change = ConfigChange { key };
+        for listener in &self.listeners {
+            listener(&change);
+        }
+    }
+
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// Whether a session was saved alongside the credentials
+    pub fn has_session(&self) -> bool {
+        !self.ssid.is_empty()
+    }
+
+    pub fn set_email(&mut self, email: impl Into<String>) {
+        let email = email.into();
+        if self.email != email {
+            self.email = email;
+            self.notify(ConfigKey::Email);
+        }
+    }
+
+    pub fn set_password(&mut self, password: impl Into<String>) {
+        let password = password.into();
+        if self.password != password {
+            self.password = password;
+            self.notify(ConfigKey::Password);
+        }
+    }
+
+    /// How often evaluations are checked, between 500ms and 1 minute
+    pub fn poll_interval(&self) -> Option<Duration> {
+        self.poll_interval_ms.map(Duration::from_millis)
+    }
+
+    /// How many solutions can wait for their evaluation at once, between 1 and 10
+    pub fn max_pending_evaluations(&self) -> Option<usize> {
+        self.max_pending_evaluations.map(|max| max as usize)
+    }
+
+    /// How long a login can take, between 1 second and 10 minutes
+    pub fn login_timeout(&self) -> Option<Duration> {
+        self.login_timeout_secs.map(Duration::from_secs)
+    }
+
+    pub fn set_poll_interval(&mut self, interval: Option<Duration>) -> Result<(), PbinfoUserError> {
+        let interval = POLL_INTERVAL.check(
+            interval.map(|interval| u64::try_from(interval.as_millis()).unwrap_or(u64::MAX)),
+        )?;
+        if self.poll_interval_ms != interval {
+            self.poll_interval_ms = interval;
+            self.notify(ConfigKey::PollInterval);
+        }
+        Ok(())
+    }
+
+    pub fn set_max_pending_evaluations(
+        &mut self,
+        max: Option<usize>,
+    ) -> Result<(), PbinfoUserError> {
+        let max = MAX_PENDING_EVALUATIONS.check(max.map(|max| max as u64))?;
+        if self.max_pending_evaluations != max {
+            self.max_pending_evaluations = max;
+            self.notify(ConfigKey::MaxPendingEvaluations);
+        }
+        Ok(())
+    }
+
+    pub fn set_login_timeout(&mut self, timeout: Option<Duration>) -> Result<(), PbinfoUserError> {
+        let timeout = LOGIN_TIMEOUT.check(timeout.map(|timeout| timeout.as_secs()))?;
+        if self.login_timeout_secs != timeout {
+            self.login_timeout_secs = timeout;
+            self.notify(ConfigKey::LoginTimeout);
+        }
+        Ok(())
+    }
+
+    /// The [LoginOptions] the settings ask for
+    pub fn login_options(&self) -> LoginOptions {
+        LoginOptions {
+            timeout: self.login_timeout(),
+            ..Default::default()
+        }
+    }
+
+    /// The [PollOptions] the settings ask for
+    pub fn poll_options(&self) -> PollOptions {
+        let mut options = PollOptions::default();
+        if let Some(interval) = self.poll_interval() {
+            options.interval = interval;
+        }
+        options
+    }
+
+    /// The [BatchOptions] the settings ask for
+    #[cfg(feature = "tokio")]
+    pub fn batch_options(&self) -> BatchOptions {
+        let mut options = BatchOptions::default();
+        if let Some(interval) = self.poll_interval() {
+            options.poll_interval = interval;
+        }
+        if let Some(max) = self.max_pending_evaluations() {
+            options.max_pending_evaluations = max;
+        }
+        options
+    }
+
+    /// Forgets the saved session, the next login will start from scratch
+    pub fn clear_session(&mut self) {
+        if self.has_session() || !self.user_id.is_empty() {
+            self.ssid.clear();
+            self.form_token.clear();
+            self.user_id.clear();
+            self.notify(ConfigKey::Session);
+        }
+    }
+
+    /// Applies all the fields of `update` that are set, returns the
+    /// settings that actually changed
+    pub fn apply(&mut self, update: UserConfigUpdate) -> Vec<ConfigKey> {
+        let mut changed = Vec::new();
+        if let Some(email) = update.email {
+            if self.email != email {
+                self.set_email(email);
+                changed.push(ConfigKey::Email);
+            }
+        }
+        if let Some(password) = update.password {
+            if self.password != password {
+                self.set_password(password);
+                changed.push(ConfigKey::Password);
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn bounds_check_the_range() {
+        assert_eq!(POLL_INTERVAL.check(None).unwrap(), None);
+        assert_eq!(POLL_INTERVAL.check(Some(500)).unwrap(), Some(500));
+        assert_eq!(POLL_INTERVAL.check(Some(60_000)).unwrap(), Some(60_000));
+        for value in [0, 499, 60_001, u64::MAX] {
+            let Err(PbinfoUserError::InvalidValueError { key, range, .. }) =
+                POLL_INTERVAL.check(Some(value))
+            else {
+                panic!("{value} was accepted");
+            };
+            assert_eq!(key, "poll_interval_ms");
+            assert_eq!(range, "500 to 60000 milliseconds");
+        }
+    }
+
+    #[test]
+    fn setters_reject_values_out_of_range() {
+        let mut config = UserConfig::new("ion@example.com", "parola");
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        config.on_change({
+            let changes = changes.clone();
+            move |change| changes.lock().unwrap().push(change.key)
+        });
+
+        config
+            .set_poll_interval(Some(Duration::from_secs(2)))
+            .unwrap();
+        assert!(config.set_poll_interval(Some(Duration::MAX)).is_err());
+        // 2^64 + 1000 milliseconds, which wrapped around to a second before
+        assert!(config
+            .set_poll_interval(Some(Duration::new(18_446_744_073_709_552, 616_000_000)))
+            .is_err());
+        assert!(config
+            .set_poll_interval(Some(Duration::from_millis(10)))
+            .is_err());
+        assert_eq!(config.poll_interval(), Some(Duration::from_secs(2)));
+
+        assert!(config.set_max_pending_evaluations(Some(0)).is_err());
+        config.set_max_pending_evaluations(Some(3)).unwrap();
+        assert!(config
+            .set_login_timeout(Some(Duration::from_secs(601)))
+            .is_err());
+        config.set_login_timeout(None).unwrap();
+        // setting the same value again isn't a change
+        config.set_max_pending_evaluations(Some(3)).unwrap();
+
+        assert_eq!(
+            *changes.lock().unwrap(),
+            [ConfigKey::PollInterval, ConfigKey::MaxPendingEvaluations]
+        );
+        config.validate().unwrap();
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn validate_rejects_files_with_values_out_of_range() {
+        let config: UserConfig =
+            toml::from_str("email = \"ion@example.com\"\nmax_pending_evaluations = 11").unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(PbinfoUserError::InvalidValueError {
+                key: "max_pending_evaluations",
+                ..
+            })
+        ));
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn files_without_profiles_are_the_default_profile() {
+        let legacy = "email = \"ion@example.com\"\npassword = \"parola\"\nssid = \"abc\"\n";
+        let config_file = ConfigFile::parse(legacy).unwrap();
+        assert_eq!(config_file.default_profile, None);
+        assert_eq!(
+            config_file.profiles.keys().collect::<Vec<_>>(),
+            [DEFAULT_PROFILE]
+        );
+
+        let mut config_file = ConfigFile::parse(legacy).unwrap();
+        config_file.set(Some("school"), &UserConfig::new("ion@school.ro", "x"));
+        let migrated = ConfigFile::parse(&toml::to_string(&config_file).unwrap()).unwrap();
+        let config = migrated.take(None).unwrap();
+        assert_eq!(config.email(), "ion@example.com");
+        assert_eq!(config.password(), "parola");
+        assert!(config.has_session());
+
+        let with_profiles = "default_profile = \"school\"\n\n\
+            [profiles.school]\nemail = \"ion@school.ro\"\n\n\
+            [profiles.default]\nemail = \"ion@example.com\"\n";
+        let config_file = ConfigFile::parse(with_profiles).unwrap();
+        assert_eq!(config_file.profile_name(None), "school");
+        assert!(matches!(
+            ConfigFile::parse(with_profiles).unwrap().take(Some("work")),
+            Err(PbinfoUserError::MissingProfileError { .. })
+        ));
+        assert!(ConfigFile::parse("email = ").is_err());
+    }
+
+    #[cfg(feature = "encrypted-config")]
+    #[test]
+    fn encrypted_file_store_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "pbinfo-api-encrypted-{}.toml.enc",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let store = |passphrase: &str| {
+            EncryptedFileStore::new(EncryptionKey::Passphrase(passphrase.to_string()))
+                .with_path(&path)
+        };
+
+        let mut config = UserConfig::new("ion@example.com", "parola");
+        config.set_max_pending_evaluations(Some(2)).unwrap();
+        config.save_to(&store("corect")).unwrap();
+        let file = fs::read(&path).unwrap();
+        assert!(file.starts_with(ENCRYPTED_CONFIG_MAGIC));
+        assert!(!String::from_utf8_lossy(&file).contains("parola"));
+
+        let loaded = UserConfig::load_from(&store("corect")).unwrap();
+        assert_eq!(loaded.email(), "ion@example.com");
+        assert_eq!(loaded.password(), "parola");
+        assert_eq!(loaded.max_pending_evaluations(), Some(2));
+
+        let Err(PbinfoUserError::StoreError { err }) = UserConfig::load_from(&store("gresit"))
+        else {
+            panic!("the wrong key decrypted the config");
+        };
+        assert!(err.contains("the key is wrong"));
+        // nor can it overwrite the profiles it can't read
+        assert!(config.save_to(&store("gresit")).is_err());
+        let _ = fs::remove_file(&path);
+    }
+}